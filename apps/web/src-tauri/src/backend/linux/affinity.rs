@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use super::for_each_thread;
+use crate::LogicalProcessor;
+
+// Kernel cpu list format, e.g. "0-3,8,10-11".
+pub fn parse_cpu_list(list: &str) -> Vec<u32> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|p| !p.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<u32>(), end.parse::<u32>()) {
+                    cpus.extend(start..=end);
+                }
+            }
+            None => cpus.extend(part.parse::<u32>().ok()),
+        }
+    }
+    cpus
+}
+
+fn read_value(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+// The last-level data/unified cache a CPU belongs to, identified by the lowest CPU
+// sharing it, so CPUs on the same L3 (or CCX on AMD) end up with the same domain.
+fn cache_domain(cpu_dir: &Path) -> Option<u32> {
+    let mut best: Option<(u32, u32)> = None;
+    for entry in std::fs::read_dir(cpu_dir.join("cache")).ok()?.filter_map(|e| e.ok()) {
+        let dir = entry.path();
+        if read_value(&dir.join("type")).as_deref() == Some("Instruction") {
+            continue;
+        }
+        let Some(level) = read_value(&dir.join("level")).and_then(|l| l.parse().ok()) else {
+            continue;
+        };
+        let Some(first) = read_value(&dir.join("shared_cpu_list")).and_then(|l| parse_cpu_list(&l).into_iter().min()) else {
+            continue;
+        };
+        if best.is_none_or(|(best_level, _)| level > best_level) {
+            best = Some((level, first));
+        }
+    }
+    best.map(|(_, first)| first)
+}
+
+fn numa_node(cpu_dir: &Path) -> Option<u32> {
+    std::fs::read_dir(cpu_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .find_map(|e| e.file_name().to_str()?.strip_prefix("node")?.parse().ok())
+}
+
+pub fn get_cpu_topology(sys_root: &Path) -> Vec<LogicalProcessor> {
+    let cpu_root = sys_root.join("devices/system/cpu");
+    let online = read_value(&cpu_root.join("online")).map(|l| parse_cpu_list(&l)).unwrap_or_default();
+
+    online
+        .into_iter()
+        .map(|id| {
+            let cpu_dir = cpu_root.join(format!("cpu{}", id));
+            let topology = cpu_dir.join("topology");
+            let siblings = read_value(&topology.join("thread_siblings_list"))
+                .map(|l| parse_cpu_list(&l))
+                .unwrap_or_else(|| vec![id]);
+            LogicalProcessor {
+                id,
+                socket: read_value(&topology.join("physical_package_id"))
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0),
+                core: read_value(&topology.join("core_id")).and_then(|v| v.parse().ok()).unwrap_or(id),
+                siblings,
+                numa_node: numa_node(&cpu_dir),
+                cache_domain: cache_domain(&cpu_dir),
+            }
+        })
+        .collect()
+}
+
+pub fn get_thread_affinity(tid: u32) -> Option<Vec<u32>> {
+    let tid = libc::pid_t::try_from(tid).ok().filter(|t| *t > 0)?;
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return None;
+        }
+        Some(
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|cpu| libc::CPU_ISSET(*cpu, &set))
+                .map(|cpu| cpu as u32)
+                .collect(),
+        )
+    }
+}
+
+pub fn set_thread_affinity(tid: u32, cpus: &[u32]) -> bool {
+    let Some(tid) = libc::pid_t::try_from(tid).ok().filter(|t| *t > 0) else {
+        return false;
+    };
+    if cpus.is_empty() || cpus.iter().any(|cpu| *cpu as usize >= libc::CPU_SETSIZE as usize) {
+        return false;
+    }
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for cpu in cpus {
+            libc::CPU_SET(*cpu as usize, &mut set);
+        }
+        libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set) == 0
+    }
+}
+
+// sched_setaffinity only applies to the thread it is given, so the mask is applied to
+// every thread; threads created afterwards inherit it from whichever thread spawns them.
+pub fn set_process_affinity(proc_root: &Path, pid: u32, cpus: &[u32]) -> bool {
+    for_each_thread(proc_root, pid, |tid| set_thread_affinity(tid, cpus))
+}
@@ -0,0 +1,46 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+// The unified (v2) hierarchy entry of /proc/<pid>/cgroup is the "0::/path" line.
+pub fn parse_cgroup_path(content: &str) -> Option<&str> {
+    content.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+pub fn process_cgroup(proc_root: &Path, pid: u32) -> Option<PathBuf> {
+    let content = std::fs::read_to_string(proc_root.join(pid.to_string()).join("cgroup")).ok()?;
+    let path = parse_cgroup_path(&content)?.trim().trim_start_matches('/');
+    let dir = Path::new(CGROUP_ROOT).join(path);
+    dir.join("cgroup.procs").exists().then_some(dir)
+}
+
+// Cgroups created by the app live next to the app's own cgroup: for a desktop session
+// that is inside user@<uid>.service, the part of the tree systemd delegates to the user.
+pub fn managed_cgroup(name: &str, controllers: &[&str]) -> io::Result<PathBuf> {
+    let own = process_cgroup(Path::new("/proc"), std::process::id())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cgroup v2 hierarchy not available"))?;
+    let parent = own.parent().filter(|p| p.starts_with(CGROUP_ROOT)).unwrap_or(&own);
+
+    for controller in controllers {
+        // Fails harmlessly when the controller is already enabled; a missing controller
+        // shows up later as an error writing the setting itself.
+        let _ = std::fs::write(parent.join("cgroup.subtree_control"), format!("+{}", controller));
+    }
+
+    let dir = parent.join(format!("task-manager-{}", name));
+    match std::fs::create_dir(&dir) {
+        Err(e) if e.kind() != io::ErrorKind::AlreadyExists => Err(e),
+        _ => Ok(dir),
+    }
+}
+
+// Moves every thread of the process; the caller needs write access to the common
+// ancestor of the old and new cgroup.
+pub fn move_process(cgroup: &Path, pid: u32) -> io::Result<()> {
+    std::fs::write(cgroup.join("cgroup.procs"), pid.to_string())
+}
+
+pub fn write_setting(cgroup: &Path, file: &str, value: &str) -> io::Result<()> {
+    std::fs::write(cgroup.join(file), value)
+}
@@ -0,0 +1,211 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+use super::net::socket_owners;
+use crate::ConnectionInfo;
+
+// Unix sockets that are accepting connections carry __SO_ACCEPTCON in their flags.
+const SO_ACCEPTCON: u32 = 0x10000;
+
+pub fn get_connections(proc_root: &Path) -> Vec<ConnectionInfo> {
+    let owners = socket_owners(proc_root);
+    let mut connections = Vec::new();
+
+    for (file, protocol) in [("tcp", "TCP"), ("tcp6", "TCPv6"), ("udp", "UDP"), ("udp6", "UDPv6")] {
+        let Ok(content) = std::fs::read_to_string(proc_root.join("net").join(file)) else {
+            continue;
+        };
+        for (entry, inode) in content.lines().skip(1).filter_map(|line| parse_inet_line(protocol, line)) {
+            connections.push(ConnectionInfo {
+                pid: owners.get(&inode).copied(),
+                ..entry
+            });
+        }
+    }
+
+    if let Ok(content) = std::fs::read_to_string(proc_root.join("net/unix")) {
+        for (entry, inode) in content.lines().skip(1).filter_map(parse_unix_line) {
+            connections.push(ConnectionInfo {
+                pid: owners.get(&inode).copied(),
+                ..entry
+            });
+        }
+    }
+
+    connections
+}
+
+// One line of /proc/net/{tcp,tcp6,udp,udp6}:
+// "sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode ..."
+pub fn parse_inet_line(protocol: &str, line: &str) -> Option<(ConnectionInfo, u64)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let (local_address, local_port) = parse_inet_address(fields.get(1)?)?;
+    let (remote_address, remote_port) = parse_inet_address(fields.get(2)?)?;
+    let state = u8::from_str_radix(fields.get(3)?, 16).ok()?;
+    let inode = fields.get(9)?.parse().ok()?;
+
+    let state = if protocol.starts_with("TCP") {
+        tcp_state_name(state)
+    } else {
+        // UDP sockets have no state beyond whether connect() gave them a fixed peer.
+        ""
+    };
+
+    Some((
+        ConnectionInfo {
+            protocol: protocol.to_string(),
+            local_address,
+            local_port: Some(local_port),
+            remote_address,
+            remote_port: Some(remote_port),
+            state: state.to_string(),
+            pid: None,
+            process_name: None,
+        },
+        inode,
+    ))
+}
+
+// The kernel prints each 32-bit word of the address in host byte order and the port
+// as a plain hex number, e.g. "0100007F:0277" for 127.0.0.1:631.
+pub fn parse_inet_address(field: &str) -> Option<(String, u16)> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let address = match address.len() {
+        8 => Ipv4Addr::from(u32::from_str_radix(address, 16).ok()?.to_ne_bytes()).to_string(),
+        32 => {
+            let mut octets = [0u8; 16];
+            for (i, chunk) in octets.chunks_mut(4).enumerate() {
+                let word = u32::from_str_radix(address.get(i * 8..i * 8 + 8)?, 16).ok()?;
+                chunk.copy_from_slice(&word.to_ne_bytes());
+            }
+            Ipv6Addr::from(octets).to_string()
+        }
+        _ => return None,
+    };
+    Some((address, port))
+}
+
+fn tcp_state_name(state: u8) -> &'static str {
+    match state {
+        0x01 => "ESTABLISHED",
+        0x02 => "SYN_SENT",
+        0x03 => "SYN_RECV",
+        0x04 => "FIN_WAIT1",
+        0x05 => "FIN_WAIT2",
+        0x06 => "TIME_WAIT",
+        0x07 => "CLOSE",
+        0x08 => "CLOSE_WAIT",
+        0x09 => "LAST_ACK",
+        0x0A => "LISTEN",
+        0x0B => "CLOSING",
+        _ => "UNKNOWN",
+    }
+}
+
+// One line of /proc/net/unix: "Num RefCount Protocol Flags Type St Inode [Path]".
+// Unix sockets have no ports or peer address, so only the bound path is reported.
+pub fn parse_unix_line(line: &str) -> Option<(ConnectionInfo, u64)> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let flags = u32::from_str_radix(fields.get(3)?, 16).ok()?;
+    let socket_type = u16::from_str_radix(fields.get(4)?, 16).ok()?;
+    let st = u8::from_str_radix(fields.get(5)?, 16).ok()?;
+    let inode = fields.get(6)?.parse().ok()?;
+    let path = fields.get(7).copied().unwrap_or_default();
+
+    let protocol = match socket_type {
+        1 => "UNIX stream",
+        2 => "UNIX dgram",
+        5 => "UNIX seqpacket",
+        _ => "UNIX",
+    };
+    let state = if flags & SO_ACCEPTCON != 0 {
+        "LISTEN"
+    } else {
+        match st {
+            1 => "UNCONNECTED",
+            2 => "CONNECTING",
+            3 => "CONNECTED",
+            4 => "DISCONNECTING",
+            _ => "UNKNOWN",
+        }
+    };
+
+    Some((
+        ConnectionInfo {
+            protocol: protocol.to_string(),
+            local_address: path.to_string(),
+            local_port: None,
+            remote_address: String::new(),
+            remote_port: None,
+            state: state.to_string(),
+            pid: None,
+            process_name: None,
+        },
+        inode,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inet_line_tcp_listener() {
+        let line = "   0: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 23907 1 0000000000000000 100 0 0 10 0";
+        let (entry, inode) = parse_inet_line("TCP", line).unwrap();
+        assert_eq!(inode, 23907);
+        assert_eq!(entry.local_address, "127.0.0.1");
+        assert_eq!(entry.local_port, Some(631));
+        assert_eq!(entry.remote_address, "0.0.0.0");
+        assert_eq!(entry.remote_port, Some(0));
+        assert_eq!(entry.state, "LISTEN");
+    }
+
+    #[test]
+    fn inet_line_tcp6_established() {
+        let line = "   3: 00000000000000000000000001000000:B2A4 00000000000000000000000001000000:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 912345 1 0000000000000000 20 4 30 10 -1";
+        let (entry, inode) = parse_inet_line("TCPv6", line).unwrap();
+        assert_eq!(inode, 912345);
+        assert_eq!(entry.local_address, "::1");
+        assert_eq!(entry.local_port, Some(45732));
+        assert_eq!(entry.remote_port, Some(8080));
+        assert_eq!(entry.state, "ESTABLISHED");
+    }
+
+    #[test]
+    fn inet_line_udp_has_no_state() {
+        let line = "  512: 00000000:14E9 00000000:0000 07 00000000:00000000 00:00000000 00000000   104        0 18512 2 0000000000000000 0";
+        let (entry, _) = parse_inet_line("UDP", line).unwrap();
+        assert_eq!(entry.local_port, Some(5353));
+        assert_eq!(entry.state, "");
+    }
+
+    #[test]
+    fn inet_line_header_is_skipped() {
+        let header = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode";
+        assert!(parse_inet_line("TCP", header).is_none());
+    }
+
+    #[test]
+    fn unix_line_listener_and_connected() {
+        let listener = "0000000000000000: 00000002 00000000 00010000 0001 01 21543 /run/systemd/private";
+        let (entry, inode) = parse_unix_line(listener).unwrap();
+        assert_eq!(inode, 21543);
+        assert_eq!(entry.protocol, "UNIX stream");
+        assert_eq!(entry.state, "LISTEN");
+        assert_eq!(entry.local_address, "/run/systemd/private");
+        assert_eq!(entry.local_port, None);
+
+        let unnamed = "0000000000000000: 00000003 00000000 00000000 0002 03 30117";
+        let (entry, _) = parse_unix_line(unnamed).unwrap();
+        assert_eq!(entry.protocol, "UNIX dgram");
+        assert_eq!(entry.state, "CONNECTED");
+        assert_eq!(entry.local_address, "");
+    }
+
+    #[test]
+    fn unix_line_header_is_skipped() {
+        assert!(parse_unix_line("Num       RefCount Protocol Flags    Type St Inode Path").is_none());
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+#[derive(Clone)]
+pub struct DesktopEntry {
+    pub icon: Option<String>,
+}
+
+pub fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(home) if !home.is_empty() => dirs.push(PathBuf::from(home)),
+        _ => {
+            if let Some(home) = std::env::var_os("HOME") {
+                dirs.push(PathBuf::from(home).join(".local/share"));
+            }
+        }
+    }
+    let system = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(system.split(':').filter(|s| !s.is_empty()).map(PathBuf::from));
+    dirs
+}
+
+// Desktop entries keyed by the file name of the binary in their Exec line.
+pub fn entries() -> &'static HashMap<String, DesktopEntry> {
+    static ENTRIES: OnceLock<HashMap<String, DesktopEntry>> = OnceLock::new();
+    ENTRIES.get_or_init(|| {
+        let mut entries = HashMap::new();
+        // Earlier data dirs take precedence, so never overwrite an existing key.
+        for dir in data_dirs() {
+            let Ok(read_dir) = std::fs::read_dir(dir.join("applications")) else {
+                continue;
+            };
+            for file in read_dir.filter_map(|e| e.ok()) {
+                let path = file.path();
+                if path.extension().and_then(|e| e.to_str()) != Some("desktop") {
+                    continue;
+                }
+                if let Some((exec, entry)) = parse_desktop_file(&path) {
+                    entries.entry(exec).or_insert(entry);
+                }
+            }
+        }
+        entries
+    })
+}
+
+pub fn lookup(exe_path: &str) -> Option<&'static DesktopEntry> {
+    let name = Path::new(exe_path).file_name()?.to_str()?;
+    entries().get(name)
+}
+
+fn parse_desktop_file(path: &Path) -> Option<(String, DesktopEntry)> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let mut in_main_section = false;
+    let mut exec = None;
+    let mut icon = None;
+    let mut is_application = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_main_section = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_main_section {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "Exec" => exec = exec_binary_name(value),
+            "Icon" => icon = Some(value.trim().to_string()).filter(|s| !s.is_empty()),
+            "Type" => is_application = value.trim() == "Application",
+            "Hidden" if value.trim() == "true" => return None,
+            _ => {}
+        }
+    }
+
+    if !is_application {
+        return None;
+    }
+    Some((exec?, DesktopEntry { icon }))
+}
+
+fn exec_binary_name(exec: &str) -> Option<String> {
+    let mut tokens = exec.split_whitespace().map(|t| t.trim_matches('"'));
+    let mut program = tokens.next()?;
+    if program == "env" || program.ends_with("/env") {
+        program = tokens.find(|t| !t.contains('=') && !t.starts_with('-'))?;
+    }
+    let name = Path::new(program).file_name()?.to_str()?;
+    // Launchers such as `flatpak run` say nothing about the process that ends up running.
+    if matches!(name, "flatpak" | "snap" | "sh" | "bash") {
+        return None;
+    }
+    Some(name.to_string())
+}
@@ -0,0 +1,420 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+use super::memory_map::{parse_mapping, Mapping};
+use super::pid_dirs;
+use crate::DumpOptions;
+
+#[cfg(target_arch = "x86_64")]
+const ELF_MACHINE: u16 = 62;
+#[cfg(target_arch = "aarch64")]
+const ELF_MACHINE: u16 = 183;
+#[cfg(target_arch = "riscv64")]
+const ELF_MACHINE: u16 = 243;
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64", target_arch = "riscv64")))]
+const ELF_MACHINE: u16 = 0;
+
+const NT_PRSTATUS: u32 = 1;
+const NT_PRFPREG: u32 = 2;
+const NT_PRPSINFO: u32 = 3;
+const NT_AUXV: u32 = 6;
+const NT_FILE: u32 = 0x4649_4c45;
+
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PN_XNUM: usize = 0xffff;
+
+const ELF_HEADER_SIZE: u64 = 64;
+const PROGRAM_HEADER_SIZE: u64 = 56;
+const SECTION_HEADER_SIZE: u64 = 64;
+
+const CHUNK_SIZE: usize = 1 << 20;
+// Progress events are sent every this many bytes of memory rather than per chunk.
+const PROGRESS_STEP: u64 = 16 << 20;
+
+struct Tracee {
+    tid: libc::pid_t,
+    // A signal that arrived while attaching; it is handed back on detach so it is not lost.
+    signal: libc::c_int,
+}
+
+// Detaching resumes every thread, so the process keeps running however the dump ends.
+struct Attached(Vec<Tracee>);
+
+impl Drop for Attached {
+    fn drop(&mut self) {
+        for tracee in &self.0 {
+            unsafe {
+                libc::ptrace(libc::PTRACE_DETACH, tracee.tid, 0usize, tracee.signal as usize);
+            }
+        }
+    }
+}
+
+fn stop_thread(tid: libc::pid_t) -> std::io::Result<Tracee> {
+    unsafe {
+        if libc::ptrace(libc::PTRACE_SEIZE, tid, 0usize, 0usize) != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        libc::ptrace(libc::PTRACE_INTERRUPT, tid, 0usize, 0usize);
+        let mut status = 0;
+        let waited = libc::waitpid(tid, &mut status, libc::__WALL);
+        if waited != tid || !libc::WIFSTOPPED(status) {
+            let error = if waited == tid {
+                // It exited rather than stopping.
+                std::io::Error::from_raw_os_error(libc::ESRCH)
+            } else {
+                std::io::Error::last_os_error()
+            };
+            // Seized but never seen stopped, so it is not in Attached; detach it here.
+            libc::ptrace(libc::PTRACE_DETACH, tid, 0usize, 0usize);
+            return Err(error);
+        }
+        // Without an event in the high bits the thread stopped for a signal of its own
+        // rather than for our interrupt.
+        let signal = if status >> 16 == 0 { libc::WSTOPSIG(status) } else { 0 };
+        Ok(Tracee { tid, signal })
+    }
+}
+
+// Threads can be created while the others are being stopped, so the task list is read
+// again until it holds nothing new.
+fn attach(proc_root: &Path, pid: u32) -> Result<Attached, String> {
+    let task_root = proc_root.join(pid.to_string()).join("task");
+    let mut attached = Attached(Vec::new());
+    let mut seen = HashSet::new();
+    loop {
+        let new: Vec<u32> = pid_dirs(&task_root).filter(|tid| seen.insert(*tid)).collect();
+        if new.is_empty() {
+            break;
+        }
+        for tid in new {
+            match stop_thread(tid as libc::pid_t) {
+                Ok(tracee) => attached.0.push(tracee),
+                Err(e) if tid == pid => return Err(format!("cannot attach to process {}: {}", pid, e)),
+                // The thread exited in the meantime.
+                Err(_) => {}
+            }
+        }
+    }
+    if attached.0.is_empty() {
+        return Err(format!("process {} not found", pid));
+    }
+    // The main thread first, as debuggers take the first NT_PRSTATUS as the current thread.
+    attached.0.sort_by_key(|t| (t.tid != pid as libc::pid_t, t.tid));
+    Ok(attached)
+}
+
+fn register_set(tid: libc::pid_t, kind: u32, size: usize) -> Option<Vec<u8>> {
+    let mut buffer = vec![0u8; size];
+    let mut iov = libc::iovec {
+        iov_base: buffer.as_mut_ptr() as *mut libc::c_void,
+        iov_len: buffer.len(),
+    };
+    let result = unsafe { libc::ptrace(libc::PTRACE_GETREGSET, tid, kind as usize, &mut iov as *mut libc::iovec) };
+    if result != 0 {
+        return None;
+    }
+    buffer.truncate(iov.iov_len);
+    Some(buffer)
+}
+
+struct ProcessStat {
+    state: u8,
+    ppid: i32,
+    pgrp: i32,
+    session: i32,
+    nice: i8,
+}
+
+fn read_stat(proc_root: &Path, pid: u32) -> Option<ProcessStat> {
+    let stat = std::fs::read_to_string(proc_root.join(pid.to_string()).join("stat")).ok()?;
+    let fields: Vec<&str> = stat.get(stat.rfind(')')? + 2..)?.split_whitespace().collect();
+    let field = |index: usize| fields.get(index).and_then(|v| v.parse::<i64>().ok());
+    Some(ProcessStat {
+        state: *fields.first()?.as_bytes().first()?,
+        ppid: field(1)? as i32,
+        pgrp: field(2)? as i32,
+        session: field(3)? as i32,
+        nice: field(16)? as i8,
+    })
+}
+
+fn note(out: &mut Vec<u8>, kind: u32, desc: &[u8]) {
+    out.extend_from_slice(&5u32.to_le_bytes());
+    out.extend_from_slice(&(desc.len() as u32).to_le_bytes());
+    out.extend_from_slice(&kind.to_le_bytes());
+    out.extend_from_slice(b"CORE\0\0\0\0");
+    out.extend_from_slice(desc);
+    out.resize(out.len().next_multiple_of(4), 0);
+}
+
+// struct elf_prstatus: signal info, pending and held signal masks, the ids, four timevals,
+// then the general registers exactly as PTRACE_GETREGSET returns them.
+fn prstatus(tracee: &Tracee, stat: &ProcessStat, registers: &[u8], fp_valid: bool) -> Vec<u8> {
+    let mut desc = Vec::with_capacity(112 + registers.len() + 8);
+    desc.extend_from_slice(&tracee.signal.to_le_bytes());
+    desc.extend_from_slice(&[0; 8]);
+    desc.extend_from_slice(&(tracee.signal as i16).to_le_bytes());
+    desc.extend_from_slice(&[0; 2 + 16]);
+    for id in [tracee.tid, stat.ppid, stat.pgrp, stat.session] {
+        desc.extend_from_slice(&id.to_le_bytes());
+    }
+    desc.extend_from_slice(&[0; 64]);
+    desc.extend_from_slice(registers);
+    desc.extend_from_slice(&(fp_valid as i32).to_le_bytes());
+    desc.resize(desc.len().next_multiple_of(8), 0);
+    desc
+}
+
+// struct elf_prpsinfo on 64-bit targets, 136 bytes.
+fn prpsinfo(proc_root: &Path, pid: u32, stat: &ProcessStat) -> Vec<u8> {
+    let dir = proc_root.join(pid.to_string());
+    let status = std::fs::read_to_string(dir.join("status")).unwrap_or_default();
+    let id = |key: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .and_then(|v| v.split_whitespace().next()?.parse::<u32>().ok())
+            .unwrap_or(0)
+    };
+    let comm = std::fs::read_to_string(dir.join("comm")).unwrap_or_default();
+    let cmdline = std::fs::read(dir.join("cmdline")).unwrap_or_default();
+    let state = b"RSDTZW".iter().position(|s| *s == stat.state).unwrap_or(0);
+
+    let mut desc = vec![state as u8, stat.state, (stat.state == b'Z') as u8, stat.nice as u8, 0, 0, 0, 0];
+    desc.extend_from_slice(&0u64.to_le_bytes());
+    desc.extend_from_slice(&id("Uid:").to_le_bytes());
+    desc.extend_from_slice(&id("Gid:").to_le_bytes());
+    for id in [pid as i32, stat.ppid, stat.pgrp, stat.session] {
+        desc.extend_from_slice(&id.to_le_bytes());
+    }
+    let mut fixed = |text: &[u8], size: usize| {
+        let text = &text[..text.len().min(size - 1)];
+        desc.extend_from_slice(text);
+        desc.resize(desc.len() + size - text.len(), 0);
+    };
+    fixed(comm.trim_end().as_bytes(), 16);
+    let args: Vec<u8> = cmdline.iter().map(|b| if *b == 0 { b' ' } else { *b }).collect();
+    fixed(args.trim_ascii_end(), 80);
+    desc
+}
+
+// NT_FILE lets debuggers find the binaries and libraries behind each mapping.
+fn mapped_files(mappings: &[Mapping], page_size: u64) -> Vec<u8> {
+    let files: Vec<&Mapping> = mappings
+        .iter()
+        .filter(|m| m.path.as_ref().is_some_and(|p| p.starts_with('/')))
+        .collect();
+    let mut desc = Vec::new();
+    desc.extend_from_slice(&(files.len() as u64).to_le_bytes());
+    desc.extend_from_slice(&page_size.to_le_bytes());
+    for mapping in &files {
+        for value in [mapping.start, mapping.end, mapping.offset / page_size] {
+            desc.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    for mapping in &files {
+        desc.extend_from_slice(mapping.path.as_deref().unwrap_or_default().as_bytes());
+        desc.push(0);
+    }
+    desc
+}
+
+// Read-only and shared file mappings can be read back from the files themselves, so
+// excluding file-backed memory still keeps private writable mappings, which hold the
+// relocated and modified data of each binary.
+fn dump_contents(mapping: &Mapping, options: &DumpOptions) -> bool {
+    let readable = mapping.permissions.starts_with('r');
+    let special = mapping.path.as_deref().is_some_and(|p| p.starts_with("[vvar") || p == "[vsyscall]");
+    let file_backed = mapping.path.as_deref().is_some_and(|p| p.starts_with('/'));
+    let private_writable = mapping.permissions.get(1..2) == Some("w") && mapping.permissions.ends_with('p');
+    readable && !special && !(options.exclude_file_backed && file_backed && !private_writable)
+}
+
+fn permission_flags(permissions: &str) -> u32 {
+    let bytes = permissions.as_bytes();
+    let flag = |index: usize, ch: u8, value: u32| if bytes.get(index) == Some(&ch) { value } else { 0 };
+    flag(0, b'r', 4) | flag(1, b'w', 2) | flag(2, b'x', 1)
+}
+
+fn elf_header(phnum: usize, shoff: u64) -> Vec<u8> {
+    let mut header = Vec::with_capacity(ELF_HEADER_SIZE as usize);
+    header.extend_from_slice(&[0x7f, b'E', b'L', b'F', 2, 1, 1, 0]);
+    header.extend_from_slice(&[0; 8]);
+    header.extend_from_slice(&4u16.to_le_bytes());
+    header.extend_from_slice(&ELF_MACHINE.to_le_bytes());
+    header.extend_from_slice(&1u32.to_le_bytes());
+    header.extend_from_slice(&0u64.to_le_bytes());
+    header.extend_from_slice(&ELF_HEADER_SIZE.to_le_bytes());
+    header.extend_from_slice(&shoff.to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&(ELF_HEADER_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&(PROGRAM_HEADER_SIZE as u16).to_le_bytes());
+    header.extend_from_slice(&(phnum.min(PN_XNUM) as u16).to_le_bytes());
+    header.extend_from_slice(&(if shoff > 0 { SECTION_HEADER_SIZE as u16 } else { 0 }).to_le_bytes());
+    header.extend_from_slice(&(if shoff > 0 { 1u16 } else { 0 }).to_le_bytes());
+    header.extend_from_slice(&0u16.to_le_bytes());
+    header
+}
+
+fn program_header(kind: u32, flags: u32, offset: u64, vaddr: u64, filesz: u64, memsz: u64, align: u64) -> Vec<u8> {
+    let mut header = Vec::with_capacity(PROGRAM_HEADER_SIZE as usize);
+    header.extend_from_slice(&kind.to_le_bytes());
+    header.extend_from_slice(&flags.to_le_bytes());
+    for value in [offset, vaddr, 0, filesz, memsz, align] {
+        header.extend_from_slice(&value.to_le_bytes());
+    }
+    header
+}
+
+// Pages that cannot be read, such as guard pages or device memory, are written as zeros
+// so the segment keeps its size.
+fn copy_memory(
+    mem: &File,
+    out: &mut impl Write,
+    mapping: &Mapping,
+    page_size: u64,
+    written: &mut u64,
+    total: u64,
+    progress: &mut dyn FnMut(u64, u64),
+) -> std::io::Result<()> {
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut address = mapping.start;
+    while address < mapping.end {
+        let len = ((mapping.end - address) as usize).min(CHUNK_SIZE);
+        let read = match mem.read_at(&mut buffer[..len], address) {
+            Ok(read) if read > 0 => read,
+            _ => {
+                let skip = (page_size - address % page_size).min(mapping.end - address) as usize;
+                buffer[..skip].fill(0);
+                skip
+            }
+        };
+        out.write_all(&buffer[..read])?;
+        address += read as u64;
+
+        let before = *written / PROGRESS_STEP;
+        *written += read as u64;
+        if *written / PROGRESS_STEP != before {
+            progress(*written, total);
+        }
+    }
+    Ok(())
+}
+
+fn write_core(
+    proc_root: &Path,
+    pid: u32,
+    out: &mut BufWriter<File>,
+    options: &DumpOptions,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<(), String> {
+    let dir = proc_root.join(pid.to_string());
+    let page_size = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+        size if size > 0 => size as u64,
+        _ => 4096,
+    };
+    let io_error = |e: std::io::Error| e.to_string();
+
+    let attached = attach(proc_root, pid)?;
+    let stat = read_stat(proc_root, pid).ok_or_else(|| format!("process {} not found", pid))?;
+    let mem = File::open(dir.join("mem")).map_err(io_error)?;
+    let mappings: Vec<Mapping> = std::fs::read_to_string(dir.join("maps"))
+        .map_err(io_error)?
+        .lines()
+        .filter_map(parse_mapping)
+        .collect();
+
+    let mut notes = Vec::new();
+    for (index, tracee) in attached.0.iter().enumerate() {
+        let registers = register_set(tracee.tid, NT_PRSTATUS, 1024).unwrap_or_default();
+        let fp_registers = register_set(tracee.tid, NT_PRFPREG, 4096);
+        note(&mut notes, NT_PRSTATUS, &prstatus(tracee, &stat, &registers, fp_registers.is_some()));
+        if index == 0 {
+            note(&mut notes, NT_PRPSINFO, &prpsinfo(proc_root, pid, &stat));
+            if let Ok(auxv) = std::fs::read(dir.join("auxv")) {
+                note(&mut notes, NT_AUXV, &auxv);
+            }
+            note(&mut notes, NT_FILE, &mapped_files(&mappings, page_size));
+        }
+        if let Some(fp_registers) = fp_registers {
+            note(&mut notes, NT_PRFPREG, &fp_registers);
+        }
+    }
+
+    let phnum = mappings.len() + 1;
+    let notes_offset = ELF_HEADER_SIZE + phnum as u64 * PROGRAM_HEADER_SIZE;
+    let data_offset = (notes_offset + notes.len() as u64).next_multiple_of(page_size);
+    let total: u64 = mappings
+        .iter()
+        .filter(|m| dump_contents(m, options))
+        .map(|m| m.end - m.start)
+        .sum();
+    // Past 65535 program headers the real count goes in the one section header, at the end.
+    let shoff = if phnum >= PN_XNUM { data_offset + total } else { 0 };
+
+    out.write_all(&elf_header(phnum, shoff)).map_err(io_error)?;
+    out.write_all(&program_header(PT_NOTE, 0, notes_offset, 0, notes.len() as u64, 0, 0))
+        .map_err(io_error)?;
+    let mut offset = data_offset;
+    for mapping in &mappings {
+        let size = mapping.end - mapping.start;
+        let filesz = if dump_contents(mapping, options) { size } else { 0 };
+        let flags = permission_flags(&mapping.permissions);
+        out.write_all(&program_header(PT_LOAD, flags, offset, mapping.start, filesz, size, page_size))
+            .map_err(io_error)?;
+        offset += filesz;
+    }
+    out.write_all(&notes).map_err(io_error)?;
+    out.seek(SeekFrom::Start(data_offset)).map_err(io_error)?;
+
+    let mut written = 0;
+    progress(0, total);
+    for mapping in mappings.iter().filter(|m| dump_contents(m, options)) {
+        copy_memory(&mem, out, mapping, page_size, &mut written, total, progress).map_err(io_error)?;
+    }
+    drop(attached);
+
+    if shoff > 0 {
+        let mut section = vec![0u8; SECTION_HEADER_SIZE as usize];
+        section[44..48].copy_from_slice(&(phnum as u32).to_le_bytes());
+        out.write_all(&section).map_err(io_error)?;
+    }
+    out.flush().map_err(io_error)?;
+    progress(written, total);
+    Ok(())
+}
+
+// All threads are stopped with ptrace while the memory is copied. A partial file is
+// removed when the dump fails.
+pub fn create_process_dump(
+    proc_root: &Path,
+    pid: u32,
+    path: &Path,
+    options: &DumpOptions,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<u64, String> {
+    if ELF_MACHINE == 0 || cfg!(not(target_pointer_width = "64")) {
+        return Err("core dumps are not supported on this architecture".to_string());
+    }
+    if pid == std::process::id() {
+        return Err("cannot dump the task manager itself".to_string());
+    }
+    let file = File::create(path).map_err(|e| format!("cannot create {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    let result = write_core(proc_root, pid, &mut out, options, progress);
+    let size = out.get_ref().metadata().map(|m| m.len());
+    drop(out);
+    match (result, size) {
+        (Ok(()), Ok(size)) => Ok(size),
+        (Err(e), _) => {
+            let _ = std::fs::remove_file(path);
+            Err(e)
+        }
+        (_, Err(e)) => Err(e.to_string()),
+    }
+}
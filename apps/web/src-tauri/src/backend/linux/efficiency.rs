@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+use super::{cgroup, for_each_thread, scheduling};
+use crate::{ProcessScheduling, SchedulingUpdate};
+
+// What a process looked like before efficiency mode, so leaving it restores exactly that.
+pub struct EfficiencyRestore {
+    scheduling: ProcessScheduling,
+    // (the cgroup it came from, the efficiency cgroup it was moved to)
+    cgroup: Option<(PathBuf, PathBuf)>,
+}
+
+fn efficiency_update() -> SchedulingUpdate {
+    SchedulingUpdate {
+        policy: Some("idle".to_string()),
+        io_class: Some("idle".to_string()),
+        ..Default::default()
+    }
+}
+
+fn restore_update(previous: &ProcessScheduling) -> SchedulingUpdate {
+    // SCHED_DEADLINE needs parameters we never read, so it comes back as SCHED_OTHER.
+    let policy = match previous.policy.as_str() {
+        "fifo" | "rr" | "batch" | "idle" | "other" => previous.policy.clone(),
+        _ => "other".to_string(),
+    };
+    SchedulingUpdate {
+        nice: Some(previous.nice),
+        priority_class: None,
+        policy: Some(policy),
+        rt_priority: Some(previous.rt_priority),
+        io_class: Some(previous.io_class.clone()),
+        io_priority: Some(previous.io_priority),
+    }
+}
+
+// SCHED_IDLE and the idle I/O class are what actually keep the process out of the way.
+// The cgroup with cpu.weight 1 additionally caps it against whole other cgroups, but
+// needs a delegated cgroup v2 tree, so failing to set it up is not an error.
+pub fn enter(proc_root: &Path, pid: u32) -> Option<EfficiencyRestore> {
+    let previous = scheduling::get_process_scheduling(proc_root, pid)?;
+    let update = efficiency_update();
+    if !for_each_thread(proc_root, pid, |tid| scheduling::set_process_scheduling(tid, &update)) {
+        // Some threads may have switched before one failed; put them back rather than
+        // leave the process half in efficiency mode with nothing to undo it.
+        let restore = restore_update(&previous);
+        for_each_thread(proc_root, pid, |tid| scheduling::set_process_scheduling(tid, &restore));
+        return None;
+    }
+
+    let moved = cgroup::process_cgroup(proc_root, pid).and_then(|original| {
+        let dir = cgroup::managed_cgroup("efficiency", &["cpu"]).ok()?;
+        cgroup::write_setting(&dir, "cpu.weight", "1").ok()?;
+        cgroup::move_process(&dir, pid).ok()?;
+        Some((original, dir))
+    });
+
+    Some(EfficiencyRestore {
+        scheduling: previous,
+        cgroup: moved,
+    })
+}
+
+pub fn leave(proc_root: &Path, pid: u32, restore: &EfficiencyRestore) -> bool {
+    let update = restore_update(&restore.scheduling);
+    let restored = for_each_thread(proc_root, pid, |tid| scheduling::set_process_scheduling(tid, &update));
+    // Only a process still in the efficiency cgroup goes back; one that was moved since
+    // belongs where it is now.
+    if let Some((original, dir)) = &restore.cgroup {
+        if cgroup::process_cgroup(proc_root, pid).as_ref() == Some(dir) {
+            // The original cgroup may be gone by now, e.g. when its scope was stopped.
+            let _ = cgroup::move_process(original, pid);
+        }
+    }
+    restored
+}
@@ -0,0 +1,274 @@
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::Path;
+
+use super::memory_map::parse_mapping;
+use super::pid_dirs;
+use crate::{FileHolder, FileHolders, FileLock, OpenFile};
+
+const OPEN_FLAGS: [(libc::c_int, &str); 8] = [
+    (libc::O_APPEND, "append"),
+    (libc::O_NONBLOCK, "nonblock"),
+    (libc::O_DSYNC, "dsync"),
+    (libc::O_DIRECT, "direct"),
+    (libc::O_DIRECTORY, "directory"),
+    (libc::O_NOATIME, "noatime"),
+    (libc::O_CLOEXEC, "cloexec"),
+    (libc::O_PATH, "path"),
+];
+
+pub fn open_flags(flags: libc::c_int) -> Vec<String> {
+    let access = match flags & libc::O_ACCMODE {
+        libc::O_WRONLY => vec!["write"],
+        libc::O_RDWR => vec!["read", "write"],
+        _ => vec!["read"],
+    };
+    access
+        .into_iter()
+        .chain(OPEN_FLAGS.iter().filter(|(flag, _)| flags & flag == *flag).map(|(_, name)| *name))
+        .map(str::to_string)
+        .collect()
+}
+
+// fdinfo has "pos:" in decimal and "flags:" in octal.
+pub fn parse_fdinfo(content: &str) -> (Option<u64>, Option<libc::c_int>) {
+    let field = |key: &str| content.lines().find_map(|line| line.strip_prefix(key)).map(str::trim);
+    (
+        field("pos:").and_then(|v| v.parse().ok()),
+        field("flags:").and_then(|v| libc::c_int::from_str_radix(v, 8).ok()),
+    )
+}
+
+// Link targets look like "socket:[1234]", "pipe:[1234]", "anon_inode:[eventfd]" or a path.
+fn fd_kind(target: &str, metadata: Option<&std::fs::Metadata>) -> &'static str {
+    if target.starts_with("socket:") {
+        "socket"
+    } else if target.starts_with("pipe:") {
+        "pipe"
+    } else if target.starts_with("anon_inode:") {
+        "anon_inode"
+    } else {
+        match metadata.map(|m| m.file_type()) {
+            Some(t) if t.is_char_device() || t.is_block_device() => "device",
+            Some(t) if t.is_fifo() => "pipe",
+            Some(t) if t.is_socket() => "socket",
+            Some(t) if t.is_dir() => "directory",
+            _ => "file",
+        }
+    }
+}
+
+struct Fd {
+    file: OpenFile,
+    // device and inode of the target, for matching /proc/locks
+    id: Option<(u64, u64)>,
+}
+
+fn read_fds(proc_root: &Path, pid: u32) -> Vec<Fd> {
+    let dir = proc_root.join(pid.to_string());
+    let mut fds: Vec<Fd> = pid_dirs(&dir.join("fd"))
+        .filter_map(|fd| {
+            let link = dir.join("fd").join(fd.to_string());
+            let target = std::fs::read_link(&link).ok()?.to_string_lossy().into_owned();
+            // Follows the magic link, so it works for deleted files too.
+            let metadata = std::fs::metadata(&link).ok();
+            let (position, flags) = std::fs::read_to_string(dir.join("fdinfo").join(fd.to_string()))
+                .map(|info| parse_fdinfo(&info))
+                .unwrap_or_default();
+            Some(Fd {
+                file: OpenFile {
+                    fd: Some(fd as u64),
+                    kind: fd_kind(&target, metadata.as_ref()).to_string(),
+                    path: target,
+                    flags: flags.map(open_flags).unwrap_or_default(),
+                    position,
+                },
+                id: metadata.map(|m| (m.dev(), m.ino())),
+            })
+        })
+        .collect();
+    fds.sort_by_key(|fd| fd.file.fd);
+    fds
+}
+
+pub fn get_process_open_files(proc_root: &Path, pid: u32) -> Vec<OpenFile> {
+    read_fds(proc_root, pid).into_iter().map(|fd| fd.file).collect()
+}
+
+fn matches_prefix(path: &str, prefix: &str) -> bool {
+    let path = path.strip_suffix(" (deleted)").unwrap_or(path);
+    let prefix = match prefix.trim_end_matches('/') {
+        "" => return prefix.starts_with('/'),
+        trimmed => trimmed,
+    };
+    path == prefix || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('/'))
+}
+
+fn holder(pid: u32, kind: &str, path: String) -> FileHolder {
+    FileHolder {
+        pid,
+        process_name: None,
+        file: OpenFile {
+            fd: None,
+            kind: kind.to_string(),
+            path,
+            flags: Vec::new(),
+            position: None,
+        },
+    }
+}
+
+// A process also keeps a mount busy through its working directory, root, executable and
+// memory-mapped files, so those are reported next to open descriptors, like lsof does.
+fn process_holders(proc_root: &Path, pid: u32, prefix: &str, paths: &mut HashMap<(u64, u64), String>) -> Vec<FileHolder> {
+    let dir = proc_root.join(pid.to_string());
+    let mut holders = Vec::new();
+
+    for fd in read_fds(proc_root, pid) {
+        if matches_prefix(&fd.file.path, prefix) {
+            if let Some(id) = fd.id {
+                paths.insert(id, fd.file.path.clone());
+            }
+            holders.push(FileHolder {
+                pid,
+                process_name: None,
+                file: fd.file,
+            });
+        }
+    }
+
+    for (link, kind) in [("cwd", "cwd"), ("root", "root"), ("exe", "exe")] {
+        if let Ok(target) = std::fs::read_link(dir.join(link)) {
+            let target = target.to_string_lossy().into_owned();
+            if matches_prefix(&target, prefix) {
+                holders.push(holder(pid, kind, target));
+            }
+        }
+    }
+
+    let mapped: HashSet<String> = std::fs::read_to_string(dir.join("maps"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(parse_mapping)
+        .filter_map(|m| {
+            let path = m.path?;
+            let path = if m.deleted { format!("{} (deleted)", path) } else { path };
+            matches_prefix(&path, prefix).then_some(path)
+        })
+        .collect();
+    let mut mapped: Vec<String> = mapped.into_iter().collect();
+    mapped.sort();
+    holders.extend(mapped.into_iter().map(|path| holder(pid, "mapped", path)));
+    holders
+}
+
+// "1: POSIX  ADVISORY  WRITE 1234 08:01:5678 0 EOF"; waiters have "->" after the id.
+// Device numbers are hex, the inode decimal. OFD locks belong to no process and show -1.
+pub fn parse_lock_line(line: &str) -> Option<(FileLock, (u64, u64))> {
+    let mut fields = line.split_whitespace().skip(1).peekable();
+    let blocked = fields.next_if_eq(&"->").is_some();
+    let kind = match fields.next()? {
+        "POSIX" => "posix",
+        "FLOCK" => "flock",
+        "OFDLCK" => "ofd",
+        "LEASE" => "lease",
+        "DELEG" => "delegation",
+        _ => "unknown",
+    };
+    let mandatory = fields.next()? == "MANDATORY";
+    let mode = fields.next()?.to_lowercase();
+    let pid = fields.next()?.parse::<i64>().ok()?;
+    let mut id = fields.next()?.split(':');
+    let major = u32::from_str_radix(id.next()?, 16).ok()?;
+    let minor = u32::from_str_radix(id.next()?, 16).ok()?;
+    let inode = id.next()?.parse().ok()?;
+    let start = fields.next()?.parse().ok()?;
+    let end = fields.next()?;
+
+    Some((
+        FileLock {
+            pid: u32::try_from(pid).ok().filter(|pid| *pid > 0),
+            process_name: None,
+            kind: kind.to_string(),
+            mode,
+            mandatory,
+            blocked,
+            path: None,
+            start,
+            end: end.parse().ok(),
+        },
+        (libc::makedev(major, minor), inode),
+    ))
+}
+
+// Descriptor targets are fully resolved, so the prefix is too when it still exists.
+pub fn find_file_holders(proc_root: &Path, prefix: &str) -> FileHolders {
+    let resolved = std::fs::canonicalize(prefix)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| prefix.to_string());
+    let prefix = resolved.as_str();
+    let mut paths = HashMap::new();
+    let mut holders: Vec<FileHolder> = pid_dirs(proc_root)
+        .flat_map(|pid| process_holders(proc_root, pid, prefix, &mut paths))
+        .collect();
+    holders.sort_by_key(|h| (h.pid, h.file.fd));
+
+    // A lock always has a descriptor open on its file, so matching the descriptors
+    // already found is enough to name the locked file.
+    let locks = std::fs::read_to_string(proc_root.join("locks"))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(parse_lock_line)
+        .filter_map(|(mut lock, id)| {
+            lock.path = Some(paths.get(&id)?.clone());
+            Some(lock)
+        })
+        .collect();
+
+    FileHolders { holders, locks }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posix_lock_with_range() {
+        let (lock, (dev, inode)) =
+            parse_lock_line("1: POSIX  ADVISORY  WRITE 1234 fd:01:393274 0 99").unwrap();
+        assert_eq!(lock.pid, Some(1234));
+        assert_eq!(lock.kind, "posix");
+        assert_eq!(lock.mode, "write");
+        assert!(!lock.mandatory);
+        assert!(!lock.blocked);
+        assert_eq!((lock.start, lock.end), (0, Some(99)));
+        assert_eq!(dev, libc::makedev(0xfd, 0x01));
+        assert_eq!(inode, 393274);
+    }
+
+    #[test]
+    fn flock_to_end_of_file() {
+        let (lock, _) = parse_lock_line("2: FLOCK  ADVISORY  READ  5678 08:02:1048601 0 EOF").unwrap();
+        assert_eq!(lock.kind, "flock");
+        assert_eq!(lock.mode, "read");
+        assert_eq!(lock.end, None);
+    }
+
+    #[test]
+    fn blocked_waiter_and_ofd_lock() {
+        let (lock, _) =
+            parse_lock_line("1: -> POSIX  ADVISORY  WRITE 4321 fd:01:393274 0 EOF").unwrap();
+        assert!(lock.blocked);
+        assert_eq!(lock.pid, Some(4321));
+
+        let (lock, _) = parse_lock_line("3: OFDLCK ADVISORY  READ  -1 00:2f:1567 0 EOF").unwrap();
+        assert_eq!(lock.kind, "ofd");
+        assert_eq!(lock.pid, None);
+    }
+
+    #[test]
+    fn truncated_line_is_skipped() {
+        assert!(parse_lock_line("1: POSIX  ADVISORY  WRITE 1234").is_none());
+        assert!(parse_lock_line("").is_none());
+    }
+}
@@ -0,0 +1,503 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::pid_dirs;
+use crate::backend::{pci_vendor_name, GpuCollector, GpuEngineSample, GpuSample};
+use crate::GpuAdapterInfo;
+
+// A DRM client is identified by the device it was opened on plus its client id; the
+// same client shows up once per fd that refers to it, including across fork/dup.
+type ClientKey = (String, u64);
+
+#[derive(Default, Clone)]
+pub struct EngineCounter {
+    // Busy nanoseconds (drm-engine-*) or busy cycles (drm-cycles-*).
+    busy: u64,
+    // Total cycles elapsed (drm-total-cycles-*), when the driver reports cycles.
+    total: Option<u64>,
+    capacity: u32,
+}
+
+#[derive(Default)]
+pub struct DrmClientSample {
+    pub pdev: String,
+    pub client_id: u64,
+    pub engines: HashMap<String, EngineCounter>,
+    pub dedicated_memory: u64,
+    pub shared_memory: u64,
+}
+
+pub struct GpuMonitor {
+    proc_root: PathBuf,
+    sys_root: PathBuf,
+    previous: HashMap<ClientKey, HashMap<String, EngineCounter>>,
+    last_refresh: Option<Instant>,
+}
+
+impl GpuMonitor {
+    pub fn with_roots(proc_root: impl Into<PathBuf>, sys_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            sys_root: sys_root.into(),
+            previous: HashMap::new(),
+            last_refresh: None,
+        }
+    }
+}
+
+impl GpuCollector for GpuMonitor {
+    fn new() -> Self {
+        Self::with_roots("/proc", "/sys")
+    }
+
+    fn refresh(&mut self) -> GpuSample {
+        let now = Instant::now();
+        let elapsed_ns = self
+            .last_refresh
+            .map(|t| now.duration_since(t).as_nanos() as u64)
+            .unwrap_or(0);
+        self.last_refresh = Some(now);
+
+        let clients = collect_clients(&self.proc_root);
+        let cards = drm_cards(&self.sys_root);
+        let adapter_indices: HashMap<&str, u32> =
+            cards.iter().map(|c| (c.slot.as_str(), c.index)).collect();
+
+        // Busy percentage per (pid, adapter, engine type), summed over that process's clients.
+        let mut engine_usage: HashMap<(u32, u32, &'static str), f32> = HashMap::new();
+        let mut adapter_memory: HashMap<u32, (u64, u64)> = HashMap::new();
+        let mut current = HashMap::new();
+
+        for (key, (pid, sample)) in clients {
+            let adapter_index = adapter_indices.get(sample.pdev.as_str()).copied().unwrap_or(0);
+            let memory = adapter_memory.entry(adapter_index).or_default();
+            memory.0 += sample.dedicated_memory;
+            memory.1 += sample.shared_memory;
+
+            if let Some(prev_engines) = self.previous.get(&key) {
+                for (engine, counter) in &sample.engines {
+                    let Some(prev) = prev_engines.get(engine) else {
+                        continue;
+                    };
+                    if let Some(pct) = engine_utilization(prev, counter, elapsed_ns) {
+                        *engine_usage
+                            .entry((pid, adapter_index, engine_type(engine)))
+                            .or_insert(0.0) += pct;
+                    }
+                }
+            }
+            current.insert(key, sample.engines);
+        }
+        self.previous = current;
+
+        // amdgpu reports device-wide VRAM/GTT usage, which also covers kernel and
+        // compositor allocations that no fdinfo accounts for.
+        for card in &cards {
+            let vram = read_sysfs_u64(&card.device.join("mem_info_vram_used"));
+            let gtt = read_sysfs_u64(&card.device.join("mem_info_gtt_used"));
+            if vram.is_none() && gtt.is_none() {
+                continue;
+            }
+            let memory = adapter_memory.entry(card.index).or_default();
+            memory.0 = vram.unwrap_or(memory.0);
+            memory.1 = gtt.unwrap_or(memory.1);
+        }
+
+        let engines = engine_usage
+            .into_iter()
+            .map(|((pid, adapter_index, engine_type), utilization)| GpuEngineSample {
+                pid,
+                adapter_index,
+                engine_type: engine_type.to_string(),
+                utilization: utilization.min(100.0),
+            })
+            .collect();
+
+        GpuSample {
+            engines,
+            dedicated_memory: adapter_memory.values().map(|m| m.0).sum(),
+            shared_memory: adapter_memory.values().map(|m| m.1).sum(),
+            adapter_memory,
+        }
+    }
+}
+
+// Maps the driver-specific engine names (amdgpu, i915, xe, panfrost, ...) onto the
+// engine types Windows reports so both backends group the same way.
+pub fn engine_type(engine: &str) -> &'static str {
+    match engine {
+        "gfx" | "render" | "rcs" | "fragment" | "vertex-tiler" => "3D",
+        "compute" | "ccs" => "Compute",
+        "dma" | "copy" | "bcs" => "Copy",
+        "dec" | "video" | "vcs" | "jpeg" => "VideoDecode",
+        "enc" | "enc_1" => "VideoEncode",
+        "video-enhance" | "vecs" => "VideoProcessing",
+        _ => "Other",
+    }
+}
+
+pub struct DrmCard {
+    pub index: u32,
+    pub device: PathBuf,
+    pub slot: String,
+}
+
+// Primary DRM nodes (card0, card1, ...) backed by a real device. Connector entries such
+// as card0-DP-1 and the firmware framebuffer that simpledrm exposes are skipped.
+pub fn drm_cards(sys_root: &Path) -> Vec<DrmCard> {
+    let mut cards = Vec::new();
+    let Ok(entries) = std::fs::read_dir(sys_root.join("class/drm")) else {
+        return cards;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name();
+        let Some(index) = name
+            .to_str()
+            .and_then(|n| n.strip_prefix("card"))
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let device = entry.path().join("device");
+        let driver = std::fs::read_link(device.join("driver")).ok();
+        if driver.as_deref().and_then(|d| d.file_name()) == Some("simple-framebuffer".as_ref()) {
+            continue;
+        }
+        if let Ok(target) = std::fs::read_link(&device) {
+            if let Some(slot) = target.file_name().and_then(|s| s.to_str()) {
+                cards.push(DrmCard { index, device, slot: slot.to_string() });
+            }
+        }
+    }
+    cards.sort_by_key(|c| c.index);
+    cards
+}
+
+fn read_sysfs_u64(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn read_sysfs_hex(path: &Path) -> Option<u32> {
+    let value = std::fs::read_to_string(path).ok()?;
+    u32::from_str_radix(value.trim().trim_start_matches("0x"), 16).ok()
+}
+
+// Device names from the pci.ids database shipped by hwdata/pciutils, when installed.
+fn pci_device_name(vendor_id: u32, device_id: u32) -> Option<String> {
+    let content = ["/usr/share/hwdata/pci.ids", "/usr/share/misc/pci.ids", "/usr/share/pci.ids"]
+        .iter()
+        .find_map(|p| std::fs::read_to_string(p).ok())?;
+    let vendor_prefix = format!("{:04x}  ", vendor_id);
+    let device_prefix = format!("\t{:04x}  ", device_id);
+    let mut in_vendor = false;
+    for line in content.lines() {
+        if line.starts_with('#') || line.is_empty() {
+            continue;
+        }
+        if !line.starts_with('\t') {
+            if in_vendor {
+                return None;
+            }
+            in_vendor = line.starts_with(&vendor_prefix);
+        } else if in_vendor {
+            if let Some(name) = line.strip_prefix(&device_prefix) {
+                return Some(name.trim().to_string());
+            }
+        }
+    }
+    None
+}
+
+// The proprietary NVIDIA driver exposes the marketing name under /proc/driver/nvidia.
+fn nvidia_model(slot: &str) -> Option<String> {
+    let info = std::fs::read_to_string(format!("/proc/driver/nvidia/gpus/{}/information", slot)).ok()?;
+    info.lines()
+        .find_map(|line| line.strip_prefix("Model:"))
+        .map(|model| model.trim().to_string())
+}
+
+// Out-of-tree modules such as nvidia carry their own version; in-tree drivers are
+// versioned with the kernel.
+fn driver_version(driver: &str) -> String {
+    std::fs::read_to_string(format!("/sys/module/{}/version", driver))
+        .or_else(|_| std::fs::read_to_string("/proc/sys/kernel/osrelease"))
+        .map(|v| v.trim().to_string())
+        .unwrap_or_else(|_| "Unknown".to_string())
+}
+
+// Formats a PCI slot like 0000:01:00.0 the way Windows reports device locations.
+fn pci_location(slot: &str) -> Option<String> {
+    let (_, rest) = slot.split_once(':')?;
+    let (bus, rest) = rest.split_once(':')?;
+    let (device, function) = rest.split_once('.')?;
+    Some(format!(
+        "PCI bus {}, device {}, function {}",
+        u32::from_str_radix(bus, 16).ok()?,
+        u32::from_str_radix(device, 16).ok()?,
+        u32::from_str_radix(function, 16).ok()?,
+    ))
+}
+
+pub fn get_gpu_info() -> Vec<GpuAdapterInfo> {
+    drm_cards(Path::new("/sys"))
+        .into_iter()
+        .map(|card| {
+            let vendor_id = read_sysfs_hex(&card.device.join("vendor")).unwrap_or(0);
+            let device_id = read_sysfs_hex(&card.device.join("device")).unwrap_or(0);
+            let driver = std::fs::read_link(card.device.join("driver"))
+                .ok()
+                .and_then(|d| d.file_name().map(|n| n.to_string_lossy().into_owned()));
+            let vendor = pci_vendor_name(vendor_id);
+            let name = nvidia_model(&card.slot)
+                .or_else(|| pci_device_name(vendor_id, device_id))
+                .unwrap_or_else(|| format!("{} GPU", vendor));
+
+            GpuAdapterInfo {
+                index: card.index,
+                name,
+                vendor,
+                memory_total: read_sysfs_u64(&card.device.join("mem_info_vram_total")).unwrap_or(0),
+                driver_version: driver.as_deref().map(driver_version).unwrap_or("Unknown".to_string()),
+                driver_date: "Unknown".to_string(),
+                location: pci_location(&card.slot).unwrap_or_else(|| card.slot.clone()),
+                dedicated_memory_used: 0,
+                shared_memory_used: 0,
+            }
+        })
+        .collect()
+}
+
+pub fn engine_utilization(prev: &EngineCounter, current: &EngineCounter, elapsed_ns: u64) -> Option<f32> {
+    let busy = current.busy.checked_sub(prev.busy)?;
+    let pct = match (prev.total, current.total) {
+        (Some(prev_total), Some(total)) => {
+            let total = total.checked_sub(prev_total)?;
+            if total == 0 {
+                return None;
+            }
+            busy as f64 / total as f64 * 100.0
+        }
+        _ => {
+            if elapsed_ns == 0 {
+                return None;
+            }
+            busy as f64 / elapsed_ns as f64 * 100.0
+        }
+    };
+    Some((pct / current.capacity.max(1) as f64).clamp(0.0, 100.0) as f32)
+}
+
+fn collect_clients(proc_root: &Path) -> HashMap<ClientKey, (u32, DrmClientSample)> {
+    let mut clients: HashMap<ClientKey, (u32, DrmClientSample)> = HashMap::new();
+    let mut pids: Vec<u32> = pid_dirs(proc_root).collect();
+    // Lowest pid wins when a client is shared, which is usually the parent that opened it.
+    pids.sort_unstable();
+
+    for pid in pids {
+        let pid_dir = proc_root.join(pid.to_string());
+        let Ok(fds) = std::fs::read_dir(pid_dir.join("fd")) else {
+            continue;
+        };
+        for fd in fds.filter_map(|e| e.ok()) {
+            let is_drm = std::fs::read_link(fd.path())
+                .map(|target| target.starts_with("/dev/dri"))
+                .unwrap_or(false);
+            if !is_drm {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(pid_dir.join("fdinfo").join(fd.file_name())) else {
+                continue;
+            };
+            if let Some(sample) = parse_fdinfo(&content) {
+                let key = (sample.pdev.clone(), sample.client_id);
+                clients.entry(key).or_insert((pid, sample));
+            }
+        }
+    }
+    clients
+}
+
+fn parse_memory_value(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let amount: u64 = parts.next()?.parse().ok()?;
+    Some(match parts.next() {
+        Some("KiB") => amount * 1024,
+        Some("MiB") => amount * 1024 * 1024,
+        Some("GiB") => amount * 1024 * 1024 * 1024,
+        _ => amount,
+    })
+}
+
+fn is_dedicated_region(region: &str) -> bool {
+    region.starts_with("vram") || region.starts_with("local")
+}
+
+// Parses the DRM usage stats described in Documentation/gpu/drm-usage-stats.rst.
+// Returns None for fds that are not DRM clients.
+pub fn parse_fdinfo(content: &str) -> Option<DrmClientSample> {
+    let mut sample = DrmClientSample::default();
+    let mut client_id = None;
+    let mut busy_ns: HashMap<String, u64> = HashMap::new();
+    let mut cycles: HashMap<String, u64> = HashMap::new();
+    let mut total_cycles: HashMap<String, u64> = HashMap::new();
+    let mut capacities: HashMap<String, u32> = HashMap::new();
+    // Memory keys in order of preference: resident, legacy drm-memory, then total.
+    let mut memory: [HashMap<String, u64>; 3] = Default::default();
+
+    for line in content.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if key == "drm-client-id" {
+            client_id = value.parse().ok();
+        } else if key == "drm-pdev" {
+            sample.pdev = value.to_string();
+        } else if let Some(engine) = key.strip_prefix("drm-engine-capacity-") {
+            if let Ok(capacity) = value.parse() {
+                capacities.insert(engine.to_string(), capacity);
+            }
+        } else if let Some(engine) = key.strip_prefix("drm-engine-") {
+            if let Some(ns) = value.split_whitespace().next().and_then(|v| v.parse().ok()) {
+                busy_ns.insert(engine.to_string(), ns);
+            }
+        } else if let Some(engine) = key.strip_prefix("drm-total-cycles-") {
+            if let Ok(value) = value.parse() {
+                total_cycles.insert(engine.to_string(), value);
+            }
+        } else if let Some(engine) = key.strip_prefix("drm-cycles-") {
+            if let Ok(value) = value.parse() {
+                cycles.insert(engine.to_string(), value);
+            }
+        } else if let Some(region) = key.strip_prefix("drm-resident-") {
+            if let Some(bytes) = parse_memory_value(value) {
+                memory[0].insert(region.to_string(), bytes);
+            }
+        } else if let Some(region) = key.strip_prefix("drm-memory-") {
+            if let Some(bytes) = parse_memory_value(value) {
+                memory[1].insert(region.to_string(), bytes);
+            }
+        } else if let Some(region) = key.strip_prefix("drm-total-") {
+            if let Some(bytes) = parse_memory_value(value) {
+                memory[2].insert(region.to_string(), bytes);
+            }
+        }
+    }
+
+    sample.client_id = client_id?;
+    // Busy nanoseconds when the driver has them; cycles only mean something next to the
+    // total cycles elapsed, so an engine with neither is left out.
+    for (engine, busy) in busy_ns {
+        sample.engines.insert(engine, EngineCounter { busy, total: None, capacity: 0 });
+    }
+    for (engine, busy) in cycles {
+        let Some(total) = total_cycles.get(&engine).copied() else {
+            continue;
+        };
+        sample.engines.entry(engine).or_insert(EngineCounter { busy, total: Some(total), capacity: 0 });
+    }
+    for (engine, counter) in &mut sample.engines {
+        counter.capacity = capacities.get(engine).copied().unwrap_or(0);
+    }
+    if let Some(regions) = memory.iter().find(|m| !m.is_empty()) {
+        for (region, bytes) in regions {
+            if is_dedicated_region(region) {
+                sample.dedicated_memory += bytes;
+            } else {
+                sample.shared_memory += bytes;
+            }
+        }
+    }
+    Some(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMDGPU: &str = "\
+pos:\t0
+flags:\t02100002
+mnt_id:\t24
+ino:\t1073
+drm-driver:\tamdgpu
+drm-client-id:\t14
+drm-pdev:\t0000:03:00.0
+pasid:\t32771
+drm-memory-vram:\t166984 KiB
+drm-memory-gtt:\t2052 KiB
+drm-memory-cpu:\t0 KiB
+drm-engine-gfx:\t1522000523 ns
+drm-engine-compute:\t0 ns
+drm-engine-dec:\t60211 ns
+";
+
+    const I915: &str = "\
+drm-driver:\ti915
+drm-client-id:\t7
+drm-pdev:\t0000:00:02.0
+drm-total-system0:\t12 MiB
+drm-resident-system0:\t8 MiB
+drm-engine-render:\t25662044495 ns
+drm-engine-video:\t0 ns
+drm-engine-capacity-video:\t2
+";
+
+    #[test]
+    fn fdinfo_reads_engines_and_memory() {
+        let sample = parse_fdinfo(AMDGPU).unwrap();
+        assert_eq!(sample.client_id, 14);
+        assert_eq!(sample.pdev, "0000:03:00.0");
+        assert_eq!(sample.engines["gfx"].busy, 1522000523);
+        assert_eq!(sample.engines["gfx"].total, None);
+        assert_eq!(sample.engines["dec"].busy, 60211);
+        assert_eq!(sample.dedicated_memory, 166984 * 1024);
+        assert_eq!(sample.shared_memory, 2052 * 1024);
+    }
+
+    #[test]
+    fn fdinfo_prefers_resident_memory_and_reads_capacity() {
+        let sample = parse_fdinfo(I915).unwrap();
+        assert_eq!(sample.shared_memory, 8 * 1024 * 1024);
+        assert_eq!(sample.dedicated_memory, 0);
+        assert_eq!(sample.engines["video"].capacity, 2);
+        assert_eq!(sample.engines["render"].capacity, 0);
+    }
+
+    #[test]
+    fn fdinfo_without_client_id_is_not_a_drm_client() {
+        assert!(parse_fdinfo("pos:\t0\nflags:\t02\nmnt_id:\t15\n").is_none());
+    }
+
+    #[test]
+    fn fdinfo_cycles_need_a_total_and_lose_to_nanoseconds() {
+        let content = "\
+drm-client-id:\t3
+drm-pdev:\t0000:00:01.0
+drm-engine-frg:\t1000 ns
+drm-cycles-frg:\t999999
+drm-total-cycles-frg:\t5000000
+drm-cycles-jm:\t400
+drm-total-cycles-jm:\t1000
+drm-cycles-orphan:\t50
+";
+        let sample = parse_fdinfo(content).unwrap();
+        assert_eq!(sample.engines["frg"].busy, 1000);
+        assert_eq!(sample.engines["frg"].total, None);
+        assert_eq!(sample.engines["jm"].busy, 400);
+        assert_eq!(sample.engines["jm"].total, Some(1000));
+        assert!(!sample.engines.contains_key("orphan"));
+    }
+
+    #[test]
+    fn utilization_from_nanoseconds_and_cycles() {
+        let counter = |busy, total| EngineCounter { busy, total, capacity: 0 };
+        let pct = engine_utilization(&counter(0, None), &counter(250_000_000, None), 1_000_000_000);
+        assert_eq!(pct, Some(25.0));
+        let pct = engine_utilization(&counter(100, Some(1000)), &counter(600, Some(2000)), 1_000_000_000);
+        assert_eq!(pct, Some(50.0));
+        // A counter that went backwards belongs to a different client.
+        assert_eq!(engine_utilization(&counter(10, None), &counter(5, None), 1_000), None);
+    }
+}
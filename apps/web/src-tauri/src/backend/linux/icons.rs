@@ -0,0 +1,260 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::desktop;
+use crate::icons::IconImage;
+
+// Icons are shown at up to 32px on 1.5x displays; 48 is the nearest common theme size.
+const ICON_SIZE: u32 = 48;
+const EXTENSIONS: [&str; 2] = ["png", "svg"];
+
+enum DirKind {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+struct ThemeDir {
+    path: String,
+    size: u32,
+    scale: u32,
+    kind: DirKind,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+impl ThemeDir {
+    fn matches_size(&self) -> bool {
+        if self.scale != 1 {
+            return false;
+        }
+        match self.kind {
+            DirKind::Fixed => self.size == ICON_SIZE,
+            DirKind::Scalable => (self.min_size..=self.max_size).contains(&ICON_SIZE),
+            DirKind::Threshold => {
+                (self.size.saturating_sub(self.threshold)..=self.size + self.threshold).contains(&ICON_SIZE)
+            }
+        }
+    }
+
+    fn size_distance(&self) -> u32 {
+        let (min, max) = match self.kind {
+            DirKind::Fixed => (self.size, self.size),
+            DirKind::Scalable => (self.min_size, self.max_size),
+            DirKind::Threshold => (self.size.saturating_sub(self.threshold), self.size + self.threshold),
+        };
+        let (min, max) = (min * self.scale, max * self.scale);
+        if ICON_SIZE < min {
+            min - ICON_SIZE
+        } else {
+            ICON_SIZE.saturating_sub(max)
+        }
+    }
+}
+
+struct Theme {
+    name: String,
+    dirs: Vec<ThemeDir>,
+    inherits: Vec<String>,
+}
+
+// Where themes are looked for, in the order the icon theme spec gives.
+fn base_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(home) = std::env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".icons"));
+    }
+    dirs.extend(desktop::data_dirs().into_iter().map(|dir| dir.join("icons")));
+    dirs.push(PathBuf::from("/usr/share/pixmaps"));
+    dirs
+}
+
+fn config_dir() -> Option<PathBuf> {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")),
+    }
+}
+
+// Sections of an ini-style file, each a map of its keys; localized keys are skipped.
+fn parse_ini(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = String::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = name.to_string();
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.contains('[') {
+            continue;
+        }
+        let value = value.trim().trim_matches('"').to_string();
+        sections.entry(current.clone()).or_default().insert(key.to_string(), value);
+    }
+    sections
+}
+
+fn read_setting(path: &Path, section: &str, key: &str) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    parse_ini(&content).remove(section)?.remove(key).filter(|v| !v.is_empty())
+}
+
+// The icon theme the desktop is set to: KDE keeps it in kdeglobals, everything GTK based
+// in the GTK settings files.
+fn current_theme_name() -> Option<String> {
+    let config = config_dir()?;
+    let kde = std::env::var("XDG_CURRENT_DESKTOP").is_ok_and(|d| d.to_uppercase().contains("KDE"));
+    let gtk = || {
+        read_setting(&config.join("gtk-4.0/settings.ini"), "Settings", "gtk-icon-theme-name")
+            .or_else(|| read_setting(&config.join("gtk-3.0/settings.ini"), "Settings", "gtk-icon-theme-name"))
+    };
+    let kdeglobals = || read_setting(&config.join("kdeglobals"), "Icons", "Theme");
+    if kde {
+        kdeglobals().or_else(gtk)
+    } else {
+        gtk().or_else(kdeglobals)
+    }
+}
+
+fn split_list(value: Option<&String>) -> Vec<String> {
+    value
+        .map(|v| v.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default()
+}
+
+fn load_theme(name: &str) -> Option<Theme> {
+    let index = base_dirs().into_iter().map(|dir| dir.join(name).join("index.theme")).find(|p| p.is_file())?;
+    let mut sections = parse_ini(&std::fs::read_to_string(index).ok()?);
+    let header = sections.remove("Icon Theme")?;
+    let mut paths = split_list(header.get("Directories"));
+    paths.extend(split_list(header.get("ScaledDirectories")));
+
+    let dirs = paths
+        .into_iter()
+        .filter_map(|path| {
+            let section = sections.get(&path)?;
+            let number = |key: &str| section.get(key).and_then(|v| v.parse::<u32>().ok());
+            let size = number("Size")?;
+            let kind = match section.get("Type").map(String::as_str) {
+                Some("Fixed") => DirKind::Fixed,
+                Some("Scalable") => DirKind::Scalable,
+                _ => DirKind::Threshold,
+            };
+            Some(ThemeDir {
+                size,
+                scale: number("Scale").unwrap_or(1),
+                kind,
+                min_size: number("MinSize").unwrap_or(size),
+                max_size: number("MaxSize").unwrap_or(size),
+                threshold: number("Threshold").unwrap_or(2),
+                path,
+            })
+        })
+        .collect();
+    Some(Theme {
+        name: name.to_string(),
+        dirs,
+        inherits: split_list(header.get("Inherits")),
+    })
+}
+
+// The current theme followed by everything it inherits from, depth first, with hicolor
+// always last.
+fn theme_chain() -> &'static [Theme] {
+    static CHAIN: OnceLock<Vec<Theme>> = OnceLock::new();
+    CHAIN.get_or_init(|| {
+        fn visit(name: &str, seen: &mut HashSet<String>, chain: &mut Vec<Theme>) {
+            if name == "hicolor" || !seen.insert(name.to_string()) {
+                return;
+            }
+            let Some(theme) = load_theme(name) else {
+                return;
+            };
+            let parents = theme.inherits.clone();
+            chain.push(theme);
+            for parent in parents {
+                visit(&parent, seen, chain);
+            }
+        }
+        let mut chain = Vec::new();
+        if let Some(name) = current_theme_name() {
+            visit(&name, &mut HashSet::new(), &mut chain);
+        }
+        chain.extend(load_theme("hicolor"));
+        chain
+    })
+}
+
+fn find_in_dir(theme: &Theme, dir: &ThemeDir, icon: &str, bases: &[PathBuf]) -> Option<PathBuf> {
+    bases
+        .iter()
+        .flat_map(|base| EXTENSIONS.iter().map(move |ext| (base, ext)))
+        .map(|(base, ext)| base.join(&theme.name).join(&dir.path).join(format!("{}.{}", icon, ext)))
+        .find(|p| p.is_file())
+}
+
+fn lookup_in_theme(theme: &Theme, icon: &str, bases: &[PathBuf]) -> Option<PathBuf> {
+    for dir in theme.dirs.iter().filter(|d| d.matches_size()) {
+        if let Some(found) = find_in_dir(theme, dir, icon, bases) {
+            return Some(found);
+        }
+    }
+    let mut closest: Option<(u32, PathBuf)> = None;
+    for dir in &theme.dirs {
+        let distance = dir.size_distance();
+        if closest.as_ref().is_some_and(|(best, _)| *best <= distance) {
+            continue;
+        }
+        if let Some(found) = find_in_dir(theme, dir, icon, bases) {
+            closest = Some((distance, found));
+        }
+    }
+    closest.map(|(_, path)| path)
+}
+
+fn find_icon_file(icon: &str) -> Option<PathBuf> {
+    let icon_path = Path::new(icon);
+    if icon_path.is_absolute() {
+        return icon_path.is_file().then(|| icon_path.to_path_buf());
+    }
+    // Some entries name the file rather than the icon.
+    let icon = EXTENSIONS
+        .iter()
+        .find_map(|ext| icon.strip_suffix(&format!(".{}", ext)))
+        .unwrap_or(icon);
+
+    let bases = base_dirs();
+    for theme in theme_chain() {
+        if let Some(found) = lookup_in_theme(theme, icon, &bases) {
+            return Some(found);
+        }
+    }
+    // Unthemed icons sit directly in a base dir, usually /usr/share/pixmaps.
+    bases
+        .iter()
+        .flat_map(|base| EXTENSIONS.iter().map(move |ext| base.join(format!("{}.{}", icon, ext))))
+        .find(|p| p.is_file())
+}
+
+// The icon of the desktop entry that launches the executable, from the current theme.
+pub fn extract_icon(path: &str) -> Option<IconImage> {
+    let icon = desktop::lookup(path)?.icon.as_deref()?;
+    let file = find_icon_file(icon)?;
+    let extension = match file.extension().and_then(|e| e.to_str()) {
+        Some("svg") => "svg",
+        Some("png") => "png",
+        _ => return None,
+    };
+    let bytes = std::fs::read(&file).ok()?;
+    Some(IconImage { bytes, extension })
+}
@@ -0,0 +1,76 @@
+use std::io::Read;
+use std::path::Path;
+
+use crate::backend::ProcessIdentity;
+
+// e_machine values from the ELF specification, named the way Rust's target arches are.
+fn elf_machine_name(machine: u16, class: u8) -> Option<&'static str> {
+    Some(match (machine, class) {
+        (0x03, _) => "x86",
+        (0x3E, _) => "x86_64",
+        (0x28, _) => "arm",
+        (0xB7, _) => "aarch64",
+        (0xF3, 1) => "riscv32",
+        (0xF3, 2) => "riscv64",
+        (0x08, _) => "mips",
+        (0x14, _) => "powerpc",
+        (0x15, _) => "powerpc64",
+        (0x16, _) => "s390x",
+        _ => return None,
+    })
+}
+
+// Reads just the ELF identification and e_machine field of the executable.
+pub fn parse_elf_architecture(header: &[u8]) -> Option<&'static str> {
+    if header.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    let class = *header.get(4)?;
+    let machine = match header.get(5)? {
+        2 => u16::from_be_bytes([*header.get(18)?, *header.get(19)?]),
+        _ => u16::from_le_bytes([*header.get(18)?, *header.get(19)?]),
+    };
+    elf_machine_name(machine, class)
+}
+
+// The effective uid is the second column of the Uid: line in /proc/<pid>/status.
+pub fn parse_effective_uid(status: &str) -> Option<u32> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("Uid:"))?
+        .split_whitespace()
+        .nth(1)?
+        .parse()
+        .ok()
+}
+
+fn read_session_id(dir: &Path) -> Option<u32> {
+    let stat = std::fs::read_to_string(dir.join("stat")).ok()?;
+    // The command name may contain spaces and parentheses, so split after the last ')'.
+    let rest = &stat[stat.rfind(')')? + 2..];
+    rest.split_whitespace().nth(3)?.parse().ok()
+}
+
+// The executable of a process owned by another user is only readable as root, so the
+// architecture is unknown for those unless the app itself runs elevated.
+pub fn process_identity(proc_root: &Path, pid: u32) -> ProcessIdentity {
+    let dir = proc_root.join(pid.to_string());
+
+    let mut header = [0u8; 20];
+    let architecture = std::fs::File::open(dir.join("exe"))
+        .and_then(|mut file| file.read_exact(&mut header))
+        .ok()
+        .and_then(|_| parse_elf_architecture(&header))
+        .map(str::to_string);
+
+    let elevated = std::fs::read_to_string(dir.join("status"))
+        .ok()
+        .and_then(|status| parse_effective_uid(&status))
+        .map(|uid| uid == 0);
+
+    ProcessIdentity {
+        architecture,
+        elevated,
+        session_id: read_session_id(&dir),
+    }
+}
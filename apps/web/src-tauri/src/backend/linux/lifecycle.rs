@@ -0,0 +1,303 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::threads::clock_ticks;
+use super::{pid_dirs, read_ppid};
+use crate::{epoch_ms, join_command_line, ProcessEvent};
+
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+const PROC_EVENT_FORK: u32 = 0x0000_0001;
+const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+
+const NLMSG_HEADER_SIZE: usize = 16;
+const CN_MSG_SIZE: usize = 20;
+// what, cpu and a nanosecond timestamp precede the per-event data
+const PROC_EVENT_HEADER_SIZE: usize = 16;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const PEAK_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+fn boot_time_ms() -> u64 {
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut now) };
+    now.tv_sec as u64 * 1000 + now.tv_nsec as u64 / 1_000_000
+}
+
+fn vm_hwm(dir: &Path) -> Option<u64> {
+    let status = std::fs::read_to_string(dir.join("status")).ok()?;
+    let value = status.lines().find_map(|line| line.strip_prefix("VmHWM:"))?;
+    value.trim().strip_suffix("kB")?.trim().parse::<u64>().ok().map(|kb| kb * 1024)
+}
+
+// Start time in milliseconds since boot, from the starttime field of stat.
+fn start_ms(dir: &Path) -> Option<u64> {
+    let stat = std::fs::read_to_string(dir.join("stat")).ok()?;
+    let ticks: u64 = stat.get(stat.rfind(')')? + 2..)?.split_whitespace().nth(19)?.parse().ok()?;
+    Some(ticks * 1000 / clock_ticks())
+}
+
+fn is_zombie(dir: &Path) -> bool {
+    std::fs::read_to_string(dir.join("stat"))
+        .ok()
+        .and_then(|stat| Some(stat.get(stat.rfind(')')? + 2..)?.starts_with('Z')))
+        .unwrap_or(false)
+}
+
+fn read_command_line(dir: &Path) -> String {
+    let raw = std::fs::read(dir.join("cmdline")).unwrap_or_default();
+    join_command_line(raw.split(|b| *b == 0).filter(|arg| !arg.is_empty()).map(String::from_utf8_lossy))
+}
+
+struct Tracked {
+    start_ms: u64,
+    peak_memory: Option<u64>,
+}
+
+// What is known about every live process, so an exit can still be reported with its run
+// time and peak memory after /proc has let go of it.
+struct Tracker {
+    proc_root: PathBuf,
+    processes: HashMap<u32, Tracked>,
+    last_sample: Instant,
+}
+
+impl Tracker {
+    fn new(proc_root: &Path) -> Self {
+        let processes = pid_dirs(proc_root)
+            .map(|pid| {
+                let dir = proc_root.join(pid.to_string());
+                let tracked = Tracked {
+                    start_ms: start_ms(&dir).unwrap_or_else(boot_time_ms),
+                    peak_memory: vm_hwm(&dir),
+                };
+                (pid, tracked)
+            })
+            .collect();
+        Tracker {
+            proc_root: proc_root.to_path_buf(),
+            processes,
+            last_sample: Instant::now(),
+        }
+    }
+
+    // A forked child is reported with its parent's image; the exec that usually follows
+    // is reported separately with the new one.
+    fn started(&mut self, kind: &str, pid: u32, parent_pid: Option<u32>, emit: &mut dyn FnMut(ProcessEvent)) {
+        let dir = self.proc_root.join(pid.to_string());
+        let tracked = self.processes.entry(pid).or_insert_with(|| Tracked {
+            start_ms: start_ms(&dir).unwrap_or_else(boot_time_ms),
+            peak_memory: None,
+        });
+        tracked.peak_memory = tracked.peak_memory.max(vm_hwm(&dir));
+        emit(ProcessEvent {
+            id: 0,
+            timestamp: epoch_ms(),
+            kind: kind.to_string(),
+            pid,
+            parent_pid: parent_pid.or_else(|| read_ppid(&self.proc_root, pid)),
+            exe_path: std::fs::read_link(dir.join("exe"))
+                .ok()
+                .map(|p| p.to_string_lossy().into_owned()),
+            command_line: read_command_line(&dir),
+            exit_code: None,
+            signal: None,
+            run_time: None,
+            peak_memory: None,
+        });
+    }
+
+    // status is the wait status the kernel passes to the parent, when known.
+    fn exited(&mut self, pid: u32, status: Option<i32>, emit: &mut dyn FnMut(ProcessEvent)) {
+        let tracked = self.processes.remove(&pid);
+        emit(ProcessEvent {
+            id: 0,
+            timestamp: epoch_ms(),
+            kind: "exit".to_string(),
+            pid,
+            parent_pid: None,
+            exe_path: None,
+            command_line: String::new(),
+            exit_code: status.filter(|s| libc::WIFEXITED(*s)).map(|s| libc::WEXITSTATUS(s)),
+            signal: status.filter(|s| libc::WIFSIGNALED(*s)).map(|s| libc::WTERMSIG(s)),
+            run_time: tracked.as_ref().map(|t| boot_time_ms().saturating_sub(t.start_ms)),
+            peak_memory: tracked.and_then(|t| t.peak_memory),
+        });
+    }
+
+    // VmHWM disappears with the address space before the exit is reported, so the peak
+    // is whatever was last sampled while the process ran.
+    fn sample_peaks(&mut self) {
+        if self.last_sample.elapsed() < PEAK_SAMPLE_INTERVAL {
+            return;
+        }
+        self.last_sample = Instant::now();
+        for (pid, tracked) in &mut self.processes {
+            let peak = vm_hwm(&self.proc_root.join(pid.to_string()));
+            tracked.peak_memory = tracked.peak_memory.max(peak);
+        }
+    }
+
+    // Snapshot diffing: anything that appeared or vanished since the last look. Exit
+    // codes are only available to the parent, so none are reported this way. Zombies
+    // are not new: with the connector they have already been reported as exited.
+    fn resync(&mut self, emit: &mut dyn FnMut(ProcessEvent)) {
+        let current: HashSet<u32> = pid_dirs(&self.proc_root).collect();
+        let gone: Vec<u32> = self.processes.keys().filter(|pid| !current.contains(pid)).copied().collect();
+        for pid in gone {
+            self.exited(pid, None, emit);
+        }
+        let mut new: Vec<u32> = current
+            .into_iter()
+            .filter(|pid| !self.processes.contains_key(pid) && !is_zombie(&self.proc_root.join(pid.to_string())))
+            .collect();
+        new.sort_unstable();
+        for pid in new {
+            self.started("start", pid, None, emit);
+        }
+    }
+}
+
+struct ProcConnector(libc::c_int);
+
+impl Drop for ProcConnector {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
+
+impl ProcConnector {
+    // Subscribing needs CAP_NET_ADMIN, so this usually only works when running as root.
+    fn open() -> Option<Self> {
+        unsafe {
+            let fd = libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_CONNECTOR);
+            if fd < 0 {
+                return None;
+            }
+            let socket = ProcConnector(fd);
+
+            let mut address: libc::sockaddr_nl = std::mem::zeroed();
+            address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+            address.nl_groups = CN_IDX_PROC;
+            let address_ptr = &address as *const libc::sockaddr_nl as *const libc::sockaddr;
+            if libc::bind(fd, address_ptr, std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t) != 0 {
+                return None;
+            }
+
+            let mut message = Vec::with_capacity(NLMSG_HEADER_SIZE + CN_MSG_SIZE + 4);
+            message.extend_from_slice(&((NLMSG_HEADER_SIZE + CN_MSG_SIZE + 4) as u32).to_ne_bytes());
+            message.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+            message.extend_from_slice(&[0; 10]);
+            message.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+            message.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+            message.extend_from_slice(&[0; 8]);
+            message.extend_from_slice(&4u16.to_ne_bytes());
+            message.extend_from_slice(&[0; 2]);
+            message.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+            if libc::send(fd, message.as_ptr() as *const libc::c_void, message.len(), 0) < 0 {
+                return None;
+            }
+
+            // The timeout lets peak memory be sampled while nothing starts or exits.
+            let timeout = libc::timeval { tv_sec: 1, tv_usec: 0 };
+            libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const libc::timeval as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            );
+            let buffer_size: libc::c_int = 4 << 20;
+            libc::setsockopt(
+                fd,
+                libc::SOL_SOCKET,
+                libc::SO_RCVBUFFORCE,
+                &buffer_size as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            );
+            Some(socket)
+        }
+    }
+
+    fn recv(&self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let read = unsafe { libc::recv(self.0, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len(), 0) };
+        if read < 0 {
+            Err(std::io::Error::last_os_error())
+        } else {
+            Ok(read as usize)
+        }
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+// Only whole processes are reported; the connector also announces every thread.
+fn handle_messages(data: &[u8], tracker: &mut Tracker, emit: &mut dyn FnMut(ProcessEvent)) {
+    let mut offset = 0;
+    while let Some(length) = read_u32(data, offset).map(|l| l as usize) {
+        if length < NLMSG_HEADER_SIZE || offset + length > data.len() {
+            break;
+        }
+        let event = offset + NLMSG_HEADER_SIZE + CN_MSG_SIZE;
+        let field = |index: usize| read_u32(data, event + PROC_EVENT_HEADER_SIZE + index * 4);
+        let is_proc = read_u32(data, offset + NLMSG_HEADER_SIZE) == Some(CN_IDX_PROC);
+        match read_u32(data, event).filter(|_| is_proc) {
+            Some(PROC_EVENT_FORK) => {
+                if let (Some(parent), Some(pid), Some(tgid)) = (field(1), field(2), field(3)) {
+                    if pid == tgid {
+                        tracker.started("start", tgid, Some(parent), emit);
+                    }
+                }
+            }
+            Some(PROC_EVENT_EXEC) => {
+                if let Some(tgid) = field(1) {
+                    tracker.started("exec", tgid, None, emit);
+                }
+            }
+            Some(PROC_EVENT_EXIT) => {
+                if let (Some(pid), Some(tgid), Some(status)) = (field(0), field(1), field(2)) {
+                    if pid == tgid {
+                        tracker.exited(tgid, Some(status as i32), emit);
+                    }
+                }
+            }
+            _ => {}
+        }
+        offset += length.next_multiple_of(4);
+    }
+}
+
+// Runs for the life of the app. The proc connector sees every process, however briefly it
+// lives; without it /proc is diffed every POLL_INTERVAL.
+pub fn watch_processes(proc_root: &Path, emit: &mut dyn FnMut(ProcessEvent)) {
+    let mut tracker = Tracker::new(proc_root);
+
+    if let Some(connector) = ProcConnector::open() {
+        // Whatever started or exited before the subscription took effect.
+        tracker.resync(emit);
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            match connector.recv(&mut buffer) {
+                Ok(read) => handle_messages(&buffer[..read], &mut tracker, emit),
+                Err(e) => match e.raw_os_error() {
+                    Some(libc::EAGAIN) | Some(libc::EINTR) => {}
+                    // The socket overflowed and events were dropped; catch up from /proc.
+                    Some(libc::ENOBUFS) => tracker.resync(emit),
+                    _ => break,
+                },
+            }
+            tracker.sample_peaks();
+        }
+    }
+
+    loop {
+        tracker.resync(emit);
+        tracker.sample_peaks();
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::cgroup;
+use crate::{ResourceLimits, ResourceUsage};
+
+const CPU_PERIOD_USEC: u64 = 100_000;
+
+pub struct ResourceGroup {
+    dir: PathBuf,
+    // cgroup each process was in before it was limited
+    original: HashMap<u32, PathBuf>,
+}
+
+fn limit_value(value: Option<u64>) -> String {
+    value.map_or_else(|| "max".to_string(), |v| v.to_string())
+}
+
+// Whole block devices only; io.max rejects partitions, and loop/ram devices are not
+// worth throttling.
+fn block_devices(sys_root: &Path) -> Vec<String> {
+    std::fs::read_dir(sys_root.join("block"))
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_string_lossy();
+            !name.starts_with("loop") && !name.starts_with("ram")
+        })
+        .filter_map(|e| std::fs::read_to_string(e.path().join("dev")).ok())
+        .map(|dev| dev.trim().to_string())
+        .collect()
+}
+
+pub fn apply_limits(group: &ResourceGroup, limits: &ResourceLimits) -> bool {
+    let dir = &group.dir;
+    let cpu_max = match limits.cpu_max_percent {
+        // 100% is one full CPU, so 400% lets the group use four.
+        Some(percent) => format!("{} {}", ((percent / 100.0) * CPU_PERIOD_USEC as f64).max(1000.0) as u64, CPU_PERIOD_USEC),
+        None => format!("max {}", CPU_PERIOD_USEC),
+    };
+
+    let mut ok = cgroup::write_setting(dir, "memory.max", &limit_value(limits.memory_max)).is_ok();
+    ok &= cgroup::write_setting(dir, "memory.high", &limit_value(limits.memory_high)).is_ok();
+    ok &= cgroup::write_setting(dir, "cpu.max", &cpu_max).is_ok();
+
+    let io = format!(
+        "rbps={} wbps={}",
+        limit_value(limits.io_read_bps),
+        limit_value(limits.io_write_bps)
+    );
+    for device in block_devices(Path::new("/sys")) {
+        // Devices without an I/O scheduler that supports io.max reject the write; that
+        // only matters when an I/O limit was actually asked for.
+        let written = cgroup::write_setting(dir, "io.max", &format!("{} {}", device, io)).is_ok();
+        if limits.io_read_bps.is_some() || limits.io_write_bps.is_some() {
+            ok &= written;
+        }
+    }
+    ok
+}
+
+pub fn create(proc_root: &Path, pid: u32, pids: &[u32], limits: &ResourceLimits) -> Option<ResourceGroup> {
+    let dir = cgroup::managed_cgroup(&format!("limit-{}", pid), &["cpu", "memory", "io"]).ok()?;
+    let mut group = ResourceGroup {
+        dir,
+        original: HashMap::new(),
+    };
+    // Limits go on before any process is moved in, so nothing runs unconstrained in between.
+    if !apply_limits(&group, limits) {
+        release(&group);
+        return None;
+    }
+
+    for pid in pids {
+        let Some(original) = cgroup::process_cgroup(proc_root, *pid) else {
+            continue;
+        };
+        if cgroup::move_process(&group.dir, *pid).is_ok() {
+            group.original.insert(*pid, original);
+        }
+    }
+    if group.original.is_empty() {
+        release(&group);
+        return None;
+    }
+    Some(group)
+}
+
+// Processes go back to where they came from; anything forked while limited joins the
+// cgroup its parent came from, or failing that the one next to the managed cgroup.
+pub fn release(group: &ResourceGroup) -> bool {
+    let fallback = group
+        .original
+        .values()
+        .next()
+        .cloned()
+        .or_else(|| group.dir.parent().map(Path::to_path_buf));
+
+    for pid in members(group) {
+        let target = group.original.get(&pid).or(fallback.as_ref());
+        if let Some(target) = target {
+            let _ = cgroup::move_process(target, pid);
+        }
+    }
+    std::fs::remove_dir(&group.dir).is_ok()
+}
+
+pub fn members(group: &ResourceGroup) -> Vec<u32> {
+    std::fs::read_to_string(group.dir.join("cgroup.procs"))
+        .map(|content| content.lines().filter_map(|l| l.trim().parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+// Flat "key value" files such as memory.events and cpu.stat.
+pub fn parse_flat_keyed(content: &str) -> HashMap<String, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn read_u64(dir: &Path, file: &str) -> Option<u64> {
+    std::fs::read_to_string(dir.join(file)).ok()?.trim().parse().ok()
+}
+
+fn read_keyed(dir: &Path, file: &str) -> HashMap<String, u64> {
+    std::fs::read_to_string(dir.join(file))
+        .map(|content| parse_flat_keyed(&content))
+        .unwrap_or_default()
+}
+
+pub fn usage(group: &ResourceGroup) -> ResourceUsage {
+    let memory_events = read_keyed(&group.dir, "memory.events");
+    let cpu_stat = read_keyed(&group.dir, "cpu.stat");
+    let event = |map: &HashMap<String, u64>, key: &str| map.get(key).copied().unwrap_or(0);
+
+    ResourceUsage {
+        pids: members(group),
+        memory_current: read_u64(&group.dir, "memory.current").unwrap_or(0),
+        // memory.peak only exists on kernels 5.19 and later
+        memory_peak: read_u64(&group.dir, "memory.peak"),
+        cpu_usage_usec: event(&cpu_stat, "usage_usec"),
+        cpu_nr_throttled: event(&cpu_stat, "nr_throttled"),
+        cpu_throttled_usec: event(&cpu_stat, "throttled_usec"),
+        memory_high_events: event(&memory_events, "high"),
+        memory_max_events: event(&memory_events, "max"),
+        oom_events: event(&memory_events, "oom"),
+        oom_kill_events: event(&memory_events, "oom_kill"),
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use super::pid_dirs;
+use crate::{MemoryBreakdown, MemoryRegion, ModuleInfo};
+
+pub struct Mapping {
+    pub start: u64,
+    pub end: u64,
+    pub permissions: String,
+    pub offset: u64,
+    pub inode: u64,
+    pub path: Option<String>,
+    pub deleted: bool,
+}
+
+fn split_field(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_start();
+    let end = s.find(char::is_whitespace).unwrap_or(s.len());
+    (end > 0).then(|| s.split_at(end))
+}
+
+// "start-end perms offset dev inode [path]"; the path may itself contain spaces.
+pub fn parse_mapping(line: &str) -> Option<Mapping> {
+    let (range, rest) = split_field(line)?;
+    let (start, end) = range.split_once('-')?;
+    let (permissions, rest) = split_field(rest)?;
+    let (offset, rest) = split_field(rest)?;
+    let (_device, rest) = split_field(rest)?;
+    let (inode, rest) = split_field(rest)?;
+
+    let path = rest.trim();
+    let (path, deleted) = match path.strip_suffix(" (deleted)") {
+        Some(path) => (path, true),
+        None => (path, false),
+    };
+    Some(Mapping {
+        start: u64::from_str_radix(start, 16).ok()?,
+        end: u64::from_str_radix(end, 16).ok()?,
+        permissions: permissions.to_string(),
+        offset: u64::from_str_radix(offset, 16).ok()?,
+        inode: inode.parse().ok()?,
+        path: (!path.is_empty()).then(|| path.to_string()),
+        deleted,
+    })
+}
+
+// The path is resolved inside the process's root so processes in containers or chroots
+// are compared against their own files rather than the host's.
+fn current_inode(proc_root: &Path, pid: u32, path: &str) -> Option<u64> {
+    let root = proc_root.join(pid.to_string()).join("root");
+    std::fs::metadata(root.join(path.trim_start_matches('/')))
+        .or_else(|_| std::fs::metadata(path))
+        .ok()
+        .map(|m| m.ino())
+}
+
+// Every file with an executable mapping: the executable, shared libraries and anything
+// else mapped for execution. The module spans all mappings of the file.
+pub fn get_process_modules(proc_root: &Path, pid: u32) -> Vec<ModuleInfo> {
+    let Ok(content) = std::fs::read_to_string(proc_root.join(pid.to_string()).join("maps")) else {
+        return Vec::new();
+    };
+
+    let mut files: HashMap<String, (u64, u64, u64, bool, bool)> = HashMap::new();
+    for mapping in content.lines().filter_map(parse_mapping) {
+        let Some(path) = mapping.path.filter(|p| p.starts_with('/')) else {
+            continue;
+        };
+        let executable = mapping.permissions.contains('x');
+        let entry = files
+            .entry(path)
+            .or_insert((mapping.start, mapping.end, mapping.inode, mapping.deleted, false));
+        entry.0 = entry.0.min(mapping.start);
+        entry.1 = entry.1.max(mapping.end);
+        entry.4 |= executable;
+    }
+
+    let mut modules: Vec<ModuleInfo> = files
+        .into_iter()
+        .filter(|(_, (_, _, _, _, executable))| *executable)
+        .map(|(path, (start, end, inode, unlinked, _))| {
+            // Package upgrades rename a new file over the old one, which the kernel reports
+            // as "(deleted)" even though the path exists again.
+            let current = current_inode(proc_root, pid, &path);
+            ModuleInfo {
+                deleted: current.is_none() && unlinked,
+                replaced: current.is_some_and(|ino| unlinked || (inode != 0 && ino != inode)),
+                path,
+                base_address: start,
+                size: end - start,
+            }
+        })
+        .collect();
+    modules.sort_by_key(|m| m.base_address);
+    modules
+}
+
+// smaps repeats the maps line for every VMA, followed by "Key: value kB" lines.
+pub fn parse_smaps(content: &str) -> Vec<MemoryRegion> {
+    let mut regions: Vec<MemoryRegion> = Vec::new();
+    for line in content.lines() {
+        if let Some(mapping) = parse_mapping(line) {
+            let backing = match &mapping.path {
+                Some(path) if path.starts_with('/') => "file",
+                _ => "anonymous",
+            };
+            regions.push(MemoryRegion {
+                start: mapping.start,
+                end: mapping.end,
+                permissions: mapping.permissions,
+                offset: mapping.offset,
+                path: mapping.path.map(|p| if mapping.deleted { format!("{} (deleted)", p) } else { p }),
+                backing: backing.to_string(),
+                rss: Some(0),
+                pss: Some(0),
+                swap: Some(0),
+            });
+            continue;
+        }
+
+        let (Some(region), Some((key, value))) = (regions.last_mut(), line.split_once(':')) else {
+            continue;
+        };
+        let bytes = value.trim().strip_suffix("kB").and_then(|v| v.trim().parse::<u64>().ok()).map(|kb| kb * 1024);
+        match key {
+            "Rss" => region.rss = bytes,
+            "Pss" => region.pss = bytes,
+            "Swap" => region.swap = bytes,
+            _ => {}
+        }
+    }
+    regions
+}
+
+pub fn get_process_memory_map(proc_root: &Path, pid: u32) -> Vec<MemoryRegion> {
+    std::fs::read_to_string(proc_root.join(pid.to_string()).join("smaps"))
+        .map(|content| parse_smaps(&content))
+        .unwrap_or_default()
+}
+
+// "Key:   1234 kB" lines, as in smaps_rollup and status, in bytes.
+fn kb_value(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let value = line.strip_prefix(key)?.strip_prefix(':')?;
+        value.trim().strip_suffix("kB")?.trim().parse::<u64>().ok().map(|kb| kb * 1024)
+    })
+}
+
+// Linux has no per-process commit charge; private anonymous memory plus what has been
+// swapped out is the memory the process would need backed if everything were touched.
+pub fn parse_memory_breakdown(rollup: &str, status: &str) -> Option<MemoryBreakdown> {
+    let value = |key: &str| kb_value(rollup, key).unwrap_or(0);
+    // Kernel threads have an empty rollup.
+    kb_value(rollup, "Rss")?;
+    Some(MemoryBreakdown {
+        private: value("Private_Clean") + value("Private_Dirty"),
+        shared: value("Shared_Clean") + value("Shared_Dirty"),
+        pss: kb_value(rollup, "Pss"),
+        swap: kb_value(rollup, "Swap"),
+        virtual_size: kb_value(status, "VmSize").unwrap_or(0),
+        commit: value("Anonymous") + value("Swap"),
+    })
+}
+
+// smaps_rollup needs the same ptrace access as smaps, so other users' processes are
+// missing unless the app runs as root.
+pub fn get_memory_breakdowns(proc_root: &Path) -> HashMap<u32, MemoryBreakdown> {
+    pid_dirs(proc_root)
+        .filter_map(|pid| {
+            let dir = proc_root.join(pid.to_string());
+            let rollup = std::fs::read_to_string(dir.join("smaps_rollup")).ok()?;
+            let status = std::fs::read_to_string(dir.join("status")).unwrap_or_default();
+            Some((pid, parse_memory_breakdown(&rollup, &status)?))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMAPS: &str = "\
+55d0c8a4b000-55d0c8a79000 r-xp 00028000 103:02 1835029                   /usr/bin/bash
+Size:                184 kB
+KernelPageSize:        4 kB
+Rss:                 180 kB
+Pss:                  36 kB
+Shared_Clean:        180 kB
+Swap:                  0 kB
+VmFlags: rd ex mr mw me sd
+55d0c9e1c000-55d0c9fb2000 rw-p 00000000 00:00 0                          [heap]
+Size:               1624 kB
+Rss:                1532 kB
+Pss:                1532 kB
+Swap:                 12 kB
+VmFlags: rd wr mr mw me ac sd
+";
+
+    #[test]
+    fn mapping_with_spaces_and_deleted_path() {
+        let line = "7f1c2a000000-7f1c2a021000 r-xp 00001000 fd:01 393274  /opt/My App/lib.so (deleted)";
+        let mapping = parse_mapping(line).unwrap();
+        assert_eq!(mapping.start, 0x7f1c2a000000);
+        assert_eq!(mapping.end, 0x7f1c2a021000);
+        assert_eq!(mapping.permissions, "r-xp");
+        assert_eq!(mapping.offset, 0x1000);
+        assert_eq!(mapping.inode, 393274);
+        assert_eq!(mapping.path.as_deref(), Some("/opt/My App/lib.so"));
+        assert!(mapping.deleted);
+    }
+
+    #[test]
+    fn anonymous_mapping_has_no_path() {
+        let mapping = parse_mapping("7ffd4c5e9000-7ffd4c5ed000 rw-p 00000000 00:00 0 ").unwrap();
+        assert_eq!(mapping.path, None);
+        assert!(!mapping.deleted);
+        assert!(parse_mapping("Rss:                 180 kB").is_none());
+        assert!(parse_mapping("VmFlags: rd ex mr mw me sd").is_none());
+    }
+
+    #[test]
+    fn smaps_attaches_sizes_to_the_preceding_region() {
+        let regions = parse_smaps(SMAPS);
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].path.as_deref(), Some("/usr/bin/bash"));
+        assert_eq!(regions[0].backing, "file");
+        assert_eq!(regions[0].rss, Some(180 * 1024));
+        assert_eq!(regions[0].pss, Some(36 * 1024));
+        assert_eq!(regions[1].path.as_deref(), Some("[heap]"));
+        assert_eq!(regions[1].backing, "anonymous");
+        assert_eq!(regions[1].swap, Some(12 * 1024));
+    }
+
+    #[test]
+    fn memory_breakdown_from_rollup_and_status() {
+        let rollup = "\
+55d0c8a23000-7ffd4c5ef000 ---p 00000000 00:00 0                          [rollup]
+Rss:                5120 kB
+Pss:                3000 kB
+Shared_Clean:       2048 kB
+Shared_Dirty:          0 kB
+Private_Clean:       512 kB
+Private_Dirty:      2560 kB
+Anonymous:          2600 kB
+Swap:                100 kB
+";
+        let status = "Name:\tbash\nVmPeak:\t   12000 kB\nVmSize:\t   11000 kB\n";
+        let breakdown = parse_memory_breakdown(rollup, status).unwrap();
+        assert_eq!(breakdown.private, 3072 * 1024);
+        assert_eq!(breakdown.shared, 2048 * 1024);
+        assert_eq!(breakdown.pss, Some(3000 * 1024));
+        assert_eq!(breakdown.swap, Some(100 * 1024));
+        assert_eq!(breakdown.virtual_size, 11000 * 1024);
+        assert_eq!(breakdown.commit, 2700 * 1024);
+        // Kernel threads have nothing mapped.
+        assert!(parse_memory_breakdown("", "Name:\tkthreadd\n").is_none());
+    }
+}
@@ -0,0 +1,340 @@
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use super::{MetricsBackend, ProcessIdentity};
+use crate::icons::IconImage;
+use crate::{
+    ConnectionInfo, DumpOptions, FileHolders, GpuAdapterInfo, LogicalProcessor, MemoryBreakdown, MemoryConfigInfo,
+    MemoryRegion, ModuleInfo, OpenFile, ProcessEvent, ProcessScheduling, ResourceLimits, ResourceUsage, SchedulingUpdate,
+    ServiceInfo, ThreadInfo,
+};
+
+mod affinity;
+mod cgroup;
+mod connections;
+mod desktop;
+mod dump;
+mod efficiency;
+mod files;
+mod gpu;
+mod icons;
+mod identity;
+mod lifecycle;
+mod limits;
+mod memory_map;
+mod net;
+mod scheduling;
+mod services;
+mod system_metrics;
+mod threads;
+
+pub use efficiency::EfficiencyRestore;
+pub use gpu::GpuMonitor;
+pub use limits::ResourceGroup;
+pub use net::NetworkMonitor;
+pub use system_metrics::SystemMetricsMonitor;
+
+pub struct LinuxBackend;
+
+impl MetricsBackend for LinuxBackend {
+    type GpuMonitor = GpuMonitor;
+    type NetworkMonitor = NetworkMonitor;
+    type SystemMetricsMonitor = SystemMetricsMonitor;
+    type EfficiencyRestore = EfficiencyRestore;
+    type ResourceGroup = ResourceGroup;
+
+    fn get_app_pids() -> HashSet<u32> {
+        get_app_pids()
+    }
+
+    fn extract_icon(path: &str) -> Option<IconImage> {
+        icons::extract_icon(path)
+    }
+
+    fn get_gpu_info() -> Vec<GpuAdapterInfo> {
+        gpu::get_gpu_info()
+    }
+
+    fn get_memory_config(total_memory: u64) -> MemoryConfigInfo {
+        get_memory_config(total_memory)
+    }
+
+    fn get_connections() -> Vec<ConnectionInfo> {
+        connections::get_connections(Path::new("/proc"))
+    }
+
+    fn get_services() -> Vec<ServiceInfo> {
+        services::get_services()
+    }
+
+    fn manage_service(name: &str, action: &str) -> bool {
+        services::manage_service(name, action)
+    }
+
+    fn suspend_process(pid: u32) -> bool {
+        send_signal(pid, libc::SIGSTOP)
+    }
+
+    fn resume_process(pid: u32) -> bool {
+        send_signal(pid, libc::SIGCONT)
+    }
+
+    fn get_process_scheduling(pid: u32) -> Option<ProcessScheduling> {
+        scheduling::get_process_scheduling(Path::new("/proc"), pid)
+    }
+
+    // Scheduling attributes are per thread on Linux, so they are applied to all of them.
+    fn set_process_scheduling(pid: u32, update: &SchedulingUpdate) -> bool {
+        for_each_thread(Path::new("/proc"), pid, |tid| scheduling::set_process_scheduling(tid, update))
+    }
+
+    fn get_cpu_topology() -> Vec<LogicalProcessor> {
+        affinity::get_cpu_topology(Path::new("/sys"))
+    }
+
+    // The main thread's tid is the pid, and its mask is what new processes inherit.
+    fn get_affinity(pid: u32) -> Option<Vec<u32>> {
+        affinity::get_thread_affinity(pid)
+    }
+
+    fn set_affinity(pid: u32, cpus: &[u32]) -> bool {
+        affinity::set_process_affinity(Path::new("/proc"), pid, cpus)
+    }
+
+    fn get_thread_affinity(tid: u32) -> Option<Vec<u32>> {
+        affinity::get_thread_affinity(tid)
+    }
+
+    fn set_thread_affinity(tid: u32, cpus: &[u32]) -> bool {
+        affinity::set_thread_affinity(tid, cpus)
+    }
+
+    fn enter_efficiency_mode(pid: u32) -> Option<EfficiencyRestore> {
+        efficiency::enter(Path::new("/proc"), pid)
+    }
+
+    fn leave_efficiency_mode(pid: u32, restore: &EfficiencyRestore) -> bool {
+        efficiency::leave(Path::new("/proc"), pid, restore)
+    }
+
+    fn create_resource_group(pid: u32, pids: &[u32], limits: &ResourceLimits) -> Option<ResourceGroup> {
+        limits::create(Path::new("/proc"), pid, pids, limits)
+    }
+
+    fn apply_resource_limits(group: &ResourceGroup, limits: &ResourceLimits) -> bool {
+        limits::apply_limits(group, limits)
+    }
+
+    fn resource_usage(group: &ResourceGroup) -> ResourceUsage {
+        limits::usage(group)
+    }
+
+    fn release_resource_group(group: ResourceGroup) -> bool {
+        limits::release(&group)
+    }
+
+    fn process_identity(pid: u32) -> ProcessIdentity {
+        identity::process_identity(Path::new("/proc"), pid)
+    }
+
+    fn get_threads(pid: u32) -> Vec<ThreadInfo> {
+        threads::get_threads(Path::new("/proc"), pid)
+    }
+
+    fn kill_process_group(tid: u32) -> bool {
+        threads::kill_process_group(tid)
+    }
+
+    fn get_process_modules(pid: u32) -> Vec<ModuleInfo> {
+        memory_map::get_process_modules(Path::new("/proc"), pid)
+    }
+
+    fn get_process_memory_map(pid: u32) -> Vec<MemoryRegion> {
+        memory_map::get_process_memory_map(Path::new("/proc"), pid)
+    }
+
+    fn get_memory_breakdowns() -> HashMap<u32, MemoryBreakdown> {
+        memory_map::get_memory_breakdowns(Path::new("/proc"))
+    }
+
+    fn get_process_open_files(pid: u32) -> Vec<OpenFile> {
+        files::get_process_open_files(Path::new("/proc"), pid)
+    }
+
+    fn find_file_holders(path_or_prefix: &str) -> FileHolders {
+        files::find_file_holders(Path::new("/proc"), path_or_prefix)
+    }
+
+    fn create_process_dump(
+        pid: u32,
+        path: &Path,
+        options: &DumpOptions,
+        progress: &mut dyn FnMut(u64, u64),
+    ) -> Result<u64, String> {
+        dump::create_process_dump(Path::new("/proc"), pid, path, options, progress)
+    }
+
+    fn watch_processes(emit: &mut dyn FnMut(ProcessEvent)) {
+        lifecycle::watch_processes(Path::new("/proc"), emit)
+    }
+}
+
+fn send_signal(pid: u32, signal: libc::c_int) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // pid 0 and negative pids address process groups, never a single process.
+    pid > 0 && unsafe { libc::kill(pid, signal) } == 0
+}
+
+fn pid_dirs(proc_root: &Path) -> impl Iterator<Item = u32> {
+    std::fs::read_dir(proc_root)
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+}
+
+// Keeps going after a failure so one exiting thread does not leave the rest unchanged.
+fn for_each_thread(proc_root: &Path, pid: u32, mut f: impl FnMut(u32) -> bool) -> bool {
+    let tids: Vec<u32> = pid_dirs(&proc_root.join(pid.to_string()).join("task")).collect();
+    if tids.is_empty() {
+        return false;
+    }
+    let failed = tids.into_iter().filter(|tid| !f(*tid)).count();
+    failed == 0
+}
+
+fn read_ppid(proc_root: &Path, pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(proc_root.join(pid.to_string()).join("stat")).ok()?;
+    // The command name may contain spaces and parentheses, so split after the last ')'.
+    let rest = &stat[stat.rfind(')')? + 2..];
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+// There is no portable way to ask which processes own a window on Linux (X11 and the
+// various Wayland compositors all differ), so treat the top-level process of anything
+// that has an installed desktop entry as an app.
+fn get_app_pids() -> HashSet<u32> {
+    let proc_root = Path::new("/proc");
+    let Ok(own_uid) = std::fs::metadata("/proc/self").map(|m| m.uid()) else {
+        return HashSet::new();
+    };
+
+    let mut exe_names = HashMap::new();
+    for pid in pid_dirs(proc_root) {
+        let dir = proc_root.join(pid.to_string());
+        if std::fs::metadata(&dir).map(|m| m.uid()).ok() != Some(own_uid) {
+            continue;
+        }
+        if let Ok(exe) = std::fs::read_link(dir.join("exe")) {
+            if let Some(name) = exe.file_name().and_then(|n| n.to_str()) {
+                exe_names.insert(pid, name.to_string());
+            }
+        }
+    }
+
+    let entries = desktop::entries();
+    exe_names
+        .iter()
+        .filter(|(_, name)| entries.contains_key(name.as_str()))
+        .filter(|(pid, name)| {
+            read_ppid(proc_root, **pid)
+                .and_then(|ppid| exe_names.get(&ppid))
+                .is_none_or(|parent| parent != *name)
+        })
+        .map(|(pid, _)| *pid)
+        .collect()
+}
+
+fn form_factor_to_string(ff: u8) -> String {
+    // SMBIOS type 17 form factor codes, named the way Win32_PhysicalMemory reports them.
+    match ff {
+        0x01 => "Other",
+        0x03 => "SIMM",
+        0x04 => "SIP",
+        0x05 => "Chip",
+        0x06 => "DIP",
+        0x07 => "ZIP",
+        0x08 => "Proprietary",
+        0x09 => "DIMM",
+        0x0A => "TSOP",
+        0x0B => "Row of chips",
+        0x0C => "RIMM",
+        0x0D => "SODIMM",
+        0x0E => "SRIMM",
+        0x0F => "FB-DIMM",
+        0x10 => "Die",
+        _ => "Unknown",
+    }
+    .to_string()
+}
+
+fn read_u16(raw: &[u8], offset: usize) -> Option<u16> {
+    raw.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn read_u32(raw: &[u8], offset: usize) -> Option<u32> {
+    raw.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn dmi_entries(kind: u8) -> Vec<Vec<u8>> {
+    let prefix = format!("{}-", kind);
+    std::fs::read_dir("/sys/firmware/dmi/entries")
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with(&prefix))
+        .filter_map(|e| std::fs::read(e.path().join("raw")).ok())
+        .collect()
+}
+
+// The raw SMBIOS tables are only readable by root; without them we report what sysinfo knows.
+fn get_memory_config(total_memory: u64) -> MemoryConfigInfo {
+    let mut speed_mhz = 0u32;
+    let mut slots_used = 0u32;
+    let mut form_factor = "Unknown".to_string();
+    let mut total_installed: u64 = 0;
+
+    for raw in dmi_entries(17) {
+        let length = raw.get(1).copied().unwrap_or(0) as usize;
+        let size = read_u16(&raw, 0x0C).unwrap_or(0);
+        if size == 0 || size == 0xFFFF {
+            continue;
+        }
+        slots_used += 1;
+        total_installed += if size == 0x7FFF {
+            read_u32(&raw, 0x1C).unwrap_or(0) as u64 * 1024 * 1024
+        } else if size & 0x8000 != 0 {
+            (size & 0x7FFF) as u64 * 1024
+        } else {
+            size as u64 * 1024 * 1024
+        };
+        if let Some(ff) = raw.get(0x0E) {
+            if form_factor == "Unknown" || form_factor == "Other" {
+                form_factor = form_factor_to_string(*ff);
+            }
+        }
+        if length >= 0x17 {
+            if let Some(spd) = read_u16(&raw, 0x15) {
+                speed_mhz = speed_mhz.max(spd as u32);
+            }
+        }
+    }
+
+    let mut slots_total = slots_used;
+    if let Some(devices) = dmi_entries(16).iter().find_map(|raw| read_u16(raw, 0x0D)) {
+        slots_total = devices as u32;
+    }
+
+    let hardware_reserved = total_installed.saturating_sub(total_memory);
+
+    MemoryConfigInfo {
+        speed_mhz,
+        slots_used,
+        slots_total,
+        form_factor,
+        hardware_reserved,
+    }
+}
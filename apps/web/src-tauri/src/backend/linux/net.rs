@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::pid_dirs;
+use crate::backend::{NetworkCollector, NetworkUsage};
+
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const INET_DIAG_INFO: u16 = 2;
+const NLMSG_HEADER_LEN: usize = 16;
+const INET_DIAG_MSG_LEN: usize = 72;
+const INET_DIAG_MSG_INODE: usize = 68;
+// Offsets of tcpi_bytes_acked and tcpi_bytes_received in struct tcp_info.
+const TCP_INFO_BYTES_ACKED: usize = 120;
+const TCP_INFO_BYTES_RECEIVED: usize = 128;
+
+pub struct NetworkMonitor {
+    proc_root: PathBuf,
+    previous: HashMap<u64, (u64, u64)>,
+    last_refresh: Option<Instant>,
+}
+
+impl NetworkMonitor {
+    pub fn with_proc_root(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            previous: HashMap::new(),
+            last_refresh: None,
+        }
+    }
+}
+
+impl NetworkCollector for NetworkMonitor {
+    fn new() -> Self {
+        Self::with_proc_root("/proc")
+    }
+
+    // Only TCP carries per-socket byte counters; UDP traffic is not attributed.
+    fn refresh(&mut self) -> HashMap<u32, NetworkUsage> {
+        let mut usage_map: HashMap<u32, NetworkUsage> = HashMap::new();
+        let now = Instant::now();
+        let elapsed = self.last_refresh.map(|t| now.duration_since(t).as_secs_f64());
+        self.last_refresh = Some(now);
+
+        let counters = tcp_byte_counters().unwrap_or_default();
+        let owners = socket_owners(&self.proc_root);
+
+        if let Some(elapsed) = elapsed.filter(|e| *e > 0.0) {
+            for (inode, (acked, received)) in &counters {
+                // Sockets seen for the first time have no baseline, so their lifetime
+                // totals would show up as one huge spike.
+                let Some((prev_acked, prev_received)) = self.previous.get(inode) else {
+                    continue;
+                };
+                let Some(pid) = owners.get(inode) else {
+                    continue;
+                };
+                let usage = usage_map.entry(*pid).or_default();
+                usage.tx_bps += (acked.saturating_sub(*prev_acked) as f64 / elapsed) as u64;
+                usage.rx_bps += (received.saturating_sub(*prev_received) as f64 / elapsed) as u64;
+            }
+        }
+
+        self.previous = counters;
+        usage_map
+    }
+}
+
+// Socket inode -> owning pid, taken from the `socket:[inode]` links in /proc/<pid>/fd.
+// A socket shared between processes is attributed to the lowest pid.
+pub fn socket_owners(proc_root: &Path) -> HashMap<u64, u32> {
+    let mut owners = HashMap::new();
+    let mut pids: Vec<u32> = pid_dirs(proc_root).collect();
+    pids.sort_unstable();
+    for pid in pids {
+        let Ok(fds) = std::fs::read_dir(proc_root.join(pid.to_string()).join("fd")) else {
+            continue;
+        };
+        for fd in fds.filter_map(|e| e.ok()) {
+            if let Some(inode) = std::fs::read_link(fd.path())
+                .ok()
+                .and_then(|target| parse_socket_link(&target.to_string_lossy()))
+            {
+                owners.entry(inode).or_insert(pid);
+            }
+        }
+    }
+    owners
+}
+
+pub fn parse_socket_link(target: &str) -> Option<u64> {
+    target.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+struct NetlinkSocket(libc::c_int);
+
+impl Drop for NetlinkSocket {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> Option<u16> {
+    buf.get(offset..offset + 2).map(|b| u16::from_ne_bytes([b[0], b[1]]))
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    buf.get(offset..offset + 4).map(|b| u32::from_ne_bytes(b.try_into().unwrap()))
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> Option<u64> {
+    buf.get(offset..offset + 8).map(|b| u64::from_ne_bytes(b.try_into().unwrap()))
+}
+
+fn align4(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+// Socket inode -> (bytes_acked, bytes_received) for every TCP socket, via NETLINK_SOCK_DIAG.
+pub fn tcp_byte_counters() -> std::io::Result<HashMap<u64, (u64, u64)>> {
+    let fd = unsafe {
+        libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_SOCK_DIAG)
+    };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let socket = NetlinkSocket(fd);
+
+    let mut counters = HashMap::new();
+    for family in [libc::AF_INET, libc::AF_INET6] {
+        send_inet_diag_request(&socket, family as u8)?;
+        receive_inet_diag_dump(&socket, &mut counters)?;
+    }
+    Ok(counters)
+}
+
+fn send_inet_diag_request(socket: &NetlinkSocket, family: u8) -> std::io::Result<()> {
+    // struct nlmsghdr followed by struct inet_diag_req_v2 with an all-zero socket id.
+    let len = NLMSG_HEADER_LEN + 56;
+    let mut request = Vec::with_capacity(len);
+    request.extend_from_slice(&(len as u32).to_ne_bytes());
+    request.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    request.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    request.extend_from_slice(&1u32.to_ne_bytes());
+    request.extend_from_slice(&0u32.to_ne_bytes());
+    request.push(family);
+    request.push(libc::IPPROTO_TCP as u8);
+    request.push(1 << (INET_DIAG_INFO - 1));
+    request.push(0);
+    request.extend_from_slice(&u32::MAX.to_ne_bytes());
+    request.resize(len, 0);
+
+    let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    let sent = unsafe {
+        libc::sendto(
+            socket.0,
+            request.as_ptr() as *const libc::c_void,
+            request.len(),
+            0,
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if sent < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn receive_inet_diag_dump(socket: &NetlinkSocket, counters: &mut HashMap<u64, (u64, u64)>) -> std::io::Result<()> {
+    let mut buf = vec![0u8; 32 * 1024];
+    loop {
+        let received = unsafe { libc::recv(socket.0, buf.as_mut_ptr() as *mut libc::c_void, buf.len(), 0) };
+        if received < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let data = &buf[..received as usize];
+
+        let mut offset = 0;
+        while offset + NLMSG_HEADER_LEN <= data.len() {
+            let msg_len = read_u32(data, offset).unwrap_or(0) as usize;
+            let msg_type = read_u16(data, offset + 4).unwrap_or(0);
+            if msg_len < NLMSG_HEADER_LEN || offset + msg_len > data.len() {
+                return Ok(());
+            }
+            match msg_type as libc::c_int {
+                libc::NLMSG_DONE => return Ok(()),
+                libc::NLMSG_ERROR => {
+                    let errno = read_u32(data, offset + NLMSG_HEADER_LEN).unwrap_or(0) as i32;
+                    return Err(std::io::Error::from_raw_os_error(-errno));
+                }
+                _ => {
+                    let msg = &data[offset + NLMSG_HEADER_LEN..offset + msg_len];
+                    if let Some((inode, bytes)) = parse_inet_diag_msg(msg) {
+                        counters.insert(inode, bytes);
+                    }
+                }
+            }
+            offset += align4(msg_len);
+        }
+    }
+}
+
+// Parses one struct inet_diag_msg and its INET_DIAG_INFO attribute (struct tcp_info).
+pub fn parse_inet_diag_msg(msg: &[u8]) -> Option<(u64, (u64, u64))> {
+    let inode = read_u32(msg, INET_DIAG_MSG_INODE)? as u64;
+    if inode == 0 {
+        return None;
+    }
+
+    let mut offset = INET_DIAG_MSG_LEN;
+    while offset + 4 <= msg.len() {
+        let attr_len = read_u16(msg, offset)? as usize;
+        let attr_type = read_u16(msg, offset + 2)?;
+        if attr_len < 4 || offset + attr_len > msg.len() {
+            return None;
+        }
+        if attr_type == INET_DIAG_INFO {
+            let info = &msg[offset + 4..offset + attr_len];
+            let acked = read_u64(info, TCP_INFO_BYTES_ACKED)?;
+            let received = read_u64(info, TCP_INFO_BYTES_RECEIVED)?;
+            return Some((inode, (acked, received)));
+        }
+        offset += align4(attr_len);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An inet_diag_msg for `inode`, followed by the given (type, payload) attributes.
+    fn diag_msg(inode: u32, attributes: &[(u16, Vec<u8>)]) -> Vec<u8> {
+        let mut msg = vec![0u8; INET_DIAG_MSG_LEN];
+        msg[INET_DIAG_MSG_INODE..INET_DIAG_MSG_INODE + 4].copy_from_slice(&inode.to_ne_bytes());
+        for (kind, payload) in attributes {
+            msg.extend_from_slice(&((payload.len() + 4) as u16).to_ne_bytes());
+            msg.extend_from_slice(&kind.to_ne_bytes());
+            msg.extend_from_slice(payload);
+            msg.resize(align4(msg.len()), 0);
+        }
+        msg
+    }
+
+    fn tcp_info(acked: u64, received: u64) -> Vec<u8> {
+        let mut info = vec![0u8; 232];
+        info[TCP_INFO_BYTES_ACKED..TCP_INFO_BYTES_ACKED + 8].copy_from_slice(&acked.to_ne_bytes());
+        info[TCP_INFO_BYTES_RECEIVED..TCP_INFO_BYTES_RECEIVED + 8].copy_from_slice(&received.to_ne_bytes());
+        info
+    }
+
+    #[test]
+    fn socket_link_inode() {
+        assert_eq!(parse_socket_link("socket:[48213]"), Some(48213));
+        assert_eq!(parse_socket_link("pipe:[48213]"), None);
+        assert_eq!(parse_socket_link("/dev/null"), None);
+    }
+
+    #[test]
+    fn diag_msg_reads_byte_counters_after_other_attributes() {
+        // INET_DIAG_MEMINFO, with a length that needs padding, comes before the info.
+        let msg = diag_msg(48213, &[(1, vec![0; 13]), (INET_DIAG_INFO, tcp_info(5_000, 70_000))]);
+        assert_eq!(parse_inet_diag_msg(&msg), Some((48213, (5_000, 70_000))));
+    }
+
+    #[test]
+    fn diag_msg_without_inode_or_info_is_skipped() {
+        assert_eq!(parse_inet_diag_msg(&diag_msg(0, &[(INET_DIAG_INFO, tcp_info(1, 2))])), None);
+        assert_eq!(parse_inet_diag_msg(&diag_msg(48213, &[(1, vec![0; 16])])), None);
+        // tcp_info from a kernel too old to have the byte counters
+        assert_eq!(parse_inet_diag_msg(&diag_msg(48213, &[(INET_DIAG_INFO, vec![0; 104])])), None);
+    }
+
+    #[test]
+    fn diag_msg_with_truncated_attribute_is_rejected() {
+        let mut msg = diag_msg(48213, &[(INET_DIAG_INFO, tcp_info(1, 2))]);
+        msg.truncate(msg.len() - 8);
+        assert_eq!(parse_inet_diag_msg(&msg), None);
+    }
+}
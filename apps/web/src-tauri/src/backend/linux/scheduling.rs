@@ -0,0 +1,143 @@
+use std::path::Path;
+
+use crate::backend::{nice_priority_class, priority_class_nice};
+use crate::{ProcessScheduling, SchedulingUpdate};
+
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+const IOPRIO_CLASS_SHIFT: u32 = 13;
+const IOPRIO_CLASSES: [&str; 4] = ["none", "realtime", "best_effort", "idle"];
+
+// nice, rt_priority and policy are fields 19, 40 and 41 of /proc/<pid>/stat.
+pub fn parse_stat_scheduling(stat: &str) -> Option<(i32, u32, u32)> {
+    // The command name may contain spaces and parentheses, so split after the last ')'.
+    let fields: Vec<&str> = stat[stat.rfind(')')? + 2..].split_whitespace().collect();
+    let nice = fields.get(16)?.parse().ok()?;
+    let rt_priority = fields.get(37)?.parse().ok()?;
+    let policy = fields.get(38)?.parse().ok()?;
+    Some((nice, rt_priority, policy))
+}
+
+fn policy_name(policy: u32) -> &'static str {
+    match policy as libc::c_int {
+        libc::SCHED_OTHER => "other",
+        libc::SCHED_FIFO => "fifo",
+        libc::SCHED_RR => "rr",
+        libc::SCHED_BATCH => "batch",
+        libc::SCHED_IDLE => "idle",
+        // SCHED_DEADLINE
+        6 => "deadline",
+        _ => "unknown",
+    }
+}
+
+fn policy_value(name: &str) -> Option<libc::c_int> {
+    match name {
+        "other" => Some(libc::SCHED_OTHER),
+        "fifo" => Some(libc::SCHED_FIFO),
+        "rr" => Some(libc::SCHED_RR),
+        "batch" => Some(libc::SCHED_BATCH),
+        "idle" => Some(libc::SCHED_IDLE),
+        _ => None,
+    }
+}
+
+fn io_priority(pid: u32) -> Option<(u32, u32)> {
+    let value = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, pid as libc::c_int) };
+    if value < 0 {
+        return None;
+    }
+    let value = value as u32;
+    Some((value >> IOPRIO_CLASS_SHIFT, value & 0xFF))
+}
+
+pub fn get_process_scheduling(proc_root: &Path, pid: u32) -> Option<ProcessScheduling> {
+    let stat = std::fs::read_to_string(proc_root.join(pid.to_string()).join("stat")).ok()?;
+    let (nice, rt_priority, policy) = parse_stat_scheduling(&stat)?;
+    let policy = policy_name(policy);
+
+    // A process without an explicit I/O class gets best-effort with a level derived
+    // from its nice value, which is what the "none" class means.
+    let (io_class, io_priority) = io_priority(pid).unwrap_or((0, 0));
+    let (io_class, io_priority) = match io_class {
+        0 => (0, ((nice + 20) / 5).clamp(0, 7) as u32),
+        class => (class, io_priority),
+    };
+
+    let priority_class = match policy {
+        "fifo" | "rr" | "deadline" => "realtime",
+        "idle" => "idle",
+        _ => nice_priority_class(nice),
+    };
+
+    Some(ProcessScheduling {
+        nice,
+        priority_class: priority_class.to_string(),
+        policy: policy.to_string(),
+        rt_priority,
+        io_class: IOPRIO_CLASSES.get(io_class as usize).copied().unwrap_or("unknown").to_string(),
+        io_priority,
+    })
+}
+
+// Applies the policy first, since switching to a realtime policy needs the rt priority
+// and switching back to SCHED_OTHER is what makes the nice value meaningful again.
+pub fn set_process_scheduling(pid: u32, update: &SchedulingUpdate) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+
+    let mut policy = update.policy.as_deref();
+    let mut nice = update.nice;
+    if let Some(class) = update.priority_class.as_deref() {
+        match class {
+            "realtime" => policy = policy.or(Some("rr")),
+            other => match priority_class_nice(other) {
+                Some(value) => {
+                    nice = Some(value);
+                    policy = policy.or(Some("other"));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    if let Some(name) = policy {
+        let Some(policy) = policy_value(name) else {
+            return false;
+        };
+        let realtime = policy == libc::SCHED_FIFO || policy == libc::SCHED_RR;
+        let param = libc::sched_param {
+            sched_priority: if realtime { update.rt_priority.unwrap_or(1).clamp(1, 99) as libc::c_int } else { 0 },
+        };
+        if unsafe { libc::sched_setscheduler(pid, policy, &param) } != 0 {
+            return false;
+        }
+    }
+
+    if let Some(nice) = nice {
+        if unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as libc::id_t, nice.clamp(-20, 19)) } != 0 {
+            return false;
+        }
+    }
+
+    if update.io_class.is_some() || update.io_priority.is_some() {
+        let class = match update.io_class.as_deref() {
+            Some(name) => match IOPRIO_CLASSES.iter().position(|c| *c == name) {
+                Some(class) => class as u32,
+                None => return false,
+            },
+            // Keep the current class, except that "none" cannot carry a level.
+            None => io_priority(pid as u32).map(|(class, _)| class).filter(|c| *c != 0).unwrap_or(2),
+        };
+        let level = if class == 0 { 0 } else { update.io_priority.unwrap_or(4).min(7) };
+        let value = (class << IOPRIO_CLASS_SHIFT) | level;
+        if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, pid, value as libc::c_int) } != 0 {
+            return false;
+        }
+    }
+
+    true
+}
@@ -0,0 +1,69 @@
+use std::process::Command;
+
+use crate::ServiceInfo;
+
+const PROPERTIES: &str = "Id,MainPID,Description,ActiveState";
+
+pub fn get_services() -> Vec<ServiceInfo> {
+    let output = Command::new("systemctl")
+        .args(["show", "--no-pager", "--property", PROPERTIES, "*.service"])
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => parse_systemctl_show(&String::from_utf8_lossy(&output.stdout)),
+        _ => Vec::new(),
+    }
+}
+
+// `systemctl show` prints one block of key=value lines per unit, separated by blank lines.
+pub fn parse_systemctl_show(output: &str) -> Vec<ServiceInfo> {
+    let mut services: Vec<ServiceInfo> = output
+        .split("\n\n")
+        .filter_map(|block| {
+            let mut name = None;
+            let mut pid = None;
+            let mut description = String::new();
+            let mut status = String::new();
+            for line in block.lines() {
+                match line.split_once('=') {
+                    Some(("Id", value)) => name = Some(value.trim_end_matches(".service").to_string()),
+                    Some(("MainPID", value)) => pid = value.parse::<u32>().ok().filter(|pid| *pid != 0),
+                    Some(("Description", value)) => description = value.to_string(),
+                    Some(("ActiveState", value)) => status = service_status(value).to_string(),
+                    _ => {}
+                }
+            }
+            Some(ServiceInfo {
+                name: name?,
+                pid,
+                description,
+                status,
+            })
+        })
+        .collect();
+    services.sort_by(|a, b| a.name.cmp(&b.name));
+    services
+}
+
+// Reported with the Win32_Service state names the services page already understands.
+fn service_status(active_state: &str) -> &str {
+    match active_state {
+        "active" | "reloading" => "Running",
+        "inactive" | "failed" => "Stopped",
+        "activating" => "Start Pending",
+        "deactivating" => "Stop Pending",
+        other => other,
+    }
+}
+
+pub fn manage_service(name: &str, action: &str) -> bool {
+    if !matches!(action, "start" | "stop" | "restart") {
+        return false;
+    }
+
+    // "--" so a name starting with a dash is never read as an option
+    Command::new("systemctl")
+        .args([action, "--no-ask-password", "--", name])
+        .status()
+        .is_ok_and(|status| status.success())
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::backend::SystemMetricsCollector;
+
+pub struct SystemMetricsMonitor {
+    proc_root: PathBuf,
+    last_context_switches: Option<(u64, Instant)>,
+}
+
+impl SystemMetricsMonitor {
+    pub fn with_proc_root(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            last_context_switches: None,
+        }
+    }
+}
+
+impl SystemMetricsCollector for SystemMetricsMonitor {
+    fn new() -> Self {
+        Self::with_proc_root("/proc")
+    }
+
+    // Closest Linux equivalents of the PDH counters the Windows backend reads:
+    // committed = Committed_AS, cached = Cached, pool_paged = SReclaimable (slab the
+    // kernel can give back) and pool_nonpaged = SUnreclaim (slab it cannot).
+    fn refresh(&mut self) -> HashMap<String, f64> {
+        let mut results = HashMap::new();
+
+        if let Some(meminfo) = read_meminfo(&self.proc_root) {
+            for (key, field) in [
+                ("committed", "Committed_AS"),
+                ("cached", "Cached"),
+                ("pool_paged", "SReclaimable"),
+                ("pool_nonpaged", "SUnreclaim"),
+            ] {
+                if let Some(value) = meminfo.get(field) {
+                    results.insert(key.to_string(), *value as f64);
+                }
+            }
+        }
+
+        if let Some(handles) = read_open_files(&self.proc_root) {
+            results.insert("handles".to_string(), handles as f64);
+        }
+
+        if let Some(threads) = read_thread_count(&self.proc_root) {
+            results.insert("threads".to_string(), threads as f64);
+        }
+
+        if let Some(stat) = read_stat(&self.proc_root) {
+            if let Some(ctxt) = stat.get("ctxt").copied() {
+                let now = Instant::now();
+                if let Some((prev, at)) = self.last_context_switches {
+                    let secs = now.duration_since(at).as_secs_f64();
+                    if secs > 0.0 && ctxt >= prev {
+                        results.insert("context_switches".to_string(), (ctxt - prev) as f64 / secs);
+                    }
+                }
+                self.last_context_switches = Some((ctxt, now));
+            }
+        }
+
+        results
+    }
+}
+
+// Values are converted to bytes for every line that carries a `kB` unit.
+pub fn parse_meminfo(content: &str) -> HashMap<String, u64> {
+    let mut values = HashMap::new();
+    for line in content.lines() {
+        let Some((key, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let mut parts = rest.split_whitespace();
+        let Some(Ok(value)) = parts.next().map(str::parse::<u64>) else {
+            continue;
+        };
+        let value = match parts.next() {
+            Some("kB") => value * 1024,
+            _ => value,
+        };
+        values.insert(key.trim().to_string(), value);
+    }
+    values
+}
+
+// `/proc/sys/fs/file-nr` is "allocated free max"; the free column is always 0 on
+// modern kernels but is still subtracted for older ones.
+pub fn parse_file_nr(content: &str) -> Option<u64> {
+    let mut parts = content.split_whitespace().map(str::parse::<u64>);
+    let allocated = parts.next()?.ok()?;
+    let free = parts.next().and_then(|v| v.ok()).unwrap_or(0);
+    Some(allocated.saturating_sub(free))
+}
+
+// The fourth field of `/proc/loadavg` is "runnable/total" scheduling entities,
+// which is the number of threads on the system.
+pub fn parse_loadavg_threads(content: &str) -> Option<u64> {
+    let field = content.split_whitespace().nth(3)?;
+    field.split_once('/')?.1.parse().ok()
+}
+
+// Single-value lines of `/proc/stat` such as ctxt, btime, processes and procs_running.
+pub fn parse_stat(content: &str) -> HashMap<String, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let key = parts.next()?;
+            let value = parts.next()?.parse().ok()?;
+            parts.next().is_none().then(|| (key.to_string(), value))
+        })
+        .collect()
+}
+
+pub fn read_meminfo(proc_root: &Path) -> Option<HashMap<String, u64>> {
+    std::fs::read_to_string(proc_root.join("meminfo"))
+        .ok()
+        .map(|content| parse_meminfo(&content))
+}
+
+pub fn read_open_files(proc_root: &Path) -> Option<u64> {
+    parse_file_nr(&std::fs::read_to_string(proc_root.join("sys/fs/file-nr")).ok()?)
+}
+
+pub fn read_thread_count(proc_root: &Path) -> Option<u64> {
+    parse_loadavg_threads(&std::fs::read_to_string(proc_root.join("loadavg")).ok()?)
+}
+
+pub fn read_stat(proc_root: &Path) -> Option<HashMap<String, u64>> {
+    std::fs::read_to_string(proc_root.join("stat"))
+        .ok()
+        .map(|content| parse_stat(&content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMINFO: &str = "\
+MemTotal:       16303428 kB
+MemFree:         8012344 kB
+Cached:          4120516 kB
+SReclaimable:     312420 kB
+SUnreclaim:       140872 kB
+Committed_AS:   11234876 kB
+HugePages_Total:       0
+Hugepagesize:       2048 kB
+";
+
+    const STAT: &str = "\
+cpu  10132153 290696 3084719 46828483 16683 0 25195 0 0 0
+cpu0 1393280 32966 572056 13343292 6130 0 17875 0 0 0
+intr 199292237 26 9 0 0 0 0 3 0 1 0 0 0 0 0 0 0
+ctxt 1990473
+btime 1062191376
+processes 2915
+procs_running 1
+procs_blocked 0
+softirq 183433 0 21755 12 39 1137 231 21459 2263
+";
+
+    #[test]
+    fn meminfo_values_are_converted_to_bytes() {
+        let values = parse_meminfo(MEMINFO);
+        assert_eq!(values.get("Committed_AS"), Some(&(11234876 * 1024)));
+        assert_eq!(values.get("SUnreclaim"), Some(&(140872 * 1024)));
+        // Lines without a unit are counts, not sizes.
+        assert_eq!(values.get("HugePages_Total"), Some(&0));
+    }
+
+    #[test]
+    fn file_nr_subtracts_free_handles() {
+        assert_eq!(parse_file_nr("12416\t0\t9223372036854775807\n"), Some(12416));
+        assert_eq!(parse_file_nr("4096 512 811213\n"), Some(3584));
+        assert_eq!(parse_file_nr(""), None);
+    }
+
+    #[test]
+    fn loadavg_threads_is_the_total_after_the_slash() {
+        assert_eq!(parse_loadavg_threads("0.52 0.58 0.59 2/1473 38941\n"), Some(1473));
+        assert_eq!(parse_loadavg_threads("0.52 0.58 0.59\n"), None);
+    }
+
+    #[test]
+    fn stat_keeps_only_single_value_lines() {
+        let values = parse_stat(STAT);
+        assert_eq!(values.get("ctxt"), Some(&1990473));
+        assert_eq!(values.get("processes"), Some(&2915));
+        assert!(!values.contains_key("cpu"));
+        assert!(!values.contains_key("intr"));
+    }
+
+    #[test]
+    fn refresh_reads_from_proc_root() {
+        let root = std::env::temp_dir().join(format!("system-metrics-test-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sys/fs")).unwrap();
+        std::fs::write(root.join("meminfo"), MEMINFO).unwrap();
+        std::fs::write(root.join("sys/fs/file-nr"), "12416\t0\t9223372036854775807\n").unwrap();
+        std::fs::write(root.join("loadavg"), "0.52 0.58 0.59 2/1473 38941\n").unwrap();
+        std::fs::write(root.join("stat"), STAT).unwrap();
+
+        let mut monitor = SystemMetricsMonitor::with_proc_root(&root);
+        let first = monitor.refresh();
+        assert_eq!(first.get("committed"), Some(&(11234876.0 * 1024.0)));
+        assert_eq!(first.get("cached"), Some(&(4120516.0 * 1024.0)));
+        assert_eq!(first.get("pool_paged"), Some(&(312420.0 * 1024.0)));
+        assert_eq!(first.get("pool_nonpaged"), Some(&(140872.0 * 1024.0)));
+        assert_eq!(first.get("handles"), Some(&12416.0));
+        assert_eq!(first.get("threads"), Some(&1473.0));
+        // A rate needs two samples.
+        assert!(!first.contains_key("context_switches"));
+
+        std::fs::write(root.join("stat"), STAT.replace("ctxt 1990473", "ctxt 1991473")).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let second = monitor.refresh();
+        let _ = std::fs::remove_dir_all(&root);
+        assert!(second.get("context_switches").is_some_and(|rate| *rate > 0.0));
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::Path;
+
+use super::pid_dirs;
+use crate::ThreadInfo;
+
+pub fn clock_ticks() -> u64 {
+    match unsafe { libc::sysconf(libc::_SC_CLK_TCK) } {
+        ticks if ticks > 0 => ticks as u64,
+        _ => 100,
+    }
+}
+
+// Same vocabulary as process statuses; "I" is an idle kernel thread, which is asleep.
+fn thread_state(code: &str) -> &'static str {
+    match code {
+        "R" => "running",
+        "D" => "uninterruptible",
+        "T" | "t" => "stopped",
+        "Z" | "X" => "zombie",
+        _ => "sleeping",
+    }
+}
+
+pub struct ThreadStat {
+    pub name: String,
+    pub state: &'static str,
+    // utime + stime, in clock ticks
+    pub cpu_ticks: u64,
+    pub priority: i32,
+    pub nice: i32,
+    pub last_cpu: Option<u32>,
+}
+
+pub fn parse_thread_stat(stat: &str) -> Option<ThreadStat> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let name = stat.get(open + 1..close)?.to_string();
+    let fields: Vec<&str> = stat.get(close + 2..)?.split_whitespace().collect();
+    let field = |index: usize| fields.get(index).and_then(|v| v.parse::<i64>().ok());
+    Some(ThreadStat {
+        name,
+        state: thread_state(fields.first()?),
+        cpu_ticks: (field(11)? + field(12)?) as u64,
+        priority: field(15)? as i32,
+        nice: field(16)? as i32,
+        last_cpu: field(36).map(|cpu| cpu as u32),
+    })
+}
+
+// Returns (voluntary, involuntary) from the ctxt_switches lines of a status file.
+pub fn parse_context_switches(status: &str) -> (Option<u64>, Option<u64>) {
+    let value = |key: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .and_then(|v| v.trim().parse().ok())
+    };
+    (value("voluntary_ctxt_switches:"), value("nonvoluntary_ctxt_switches:"))
+}
+
+pub fn get_threads(proc_root: &Path, pid: u32) -> Vec<ThreadInfo> {
+    let task_root = proc_root.join(pid.to_string()).join("task");
+    let ticks = clock_ticks();
+    let mut threads: Vec<ThreadInfo> = pid_dirs(&task_root)
+        .filter_map(|tid| {
+            let dir = task_root.join(tid.to_string());
+            let stat = parse_thread_stat(&std::fs::read_to_string(dir.join("stat")).ok()?)?;
+            let (voluntary, involuntary) = std::fs::read_to_string(dir.join("status"))
+                .map(|status| parse_context_switches(&status))
+                .unwrap_or_default();
+            // "0" means the thread is not blocked in the kernel; reading it needs
+            // ptrace access to the process, so other users' threads report nothing.
+            let wait_channel = std::fs::read_to_string(dir.join("wchan"))
+                .ok()
+                .map(|w| w.trim().to_string())
+                .filter(|w| !w.is_empty() && w != "0");
+            Some(ThreadInfo {
+                tid,
+                name: stat.name,
+                state: stat.state.to_string(),
+                cpu_usage: 0.0,
+                cpu_time: stat.cpu_ticks * 1000 / ticks,
+                priority: stat.priority,
+                nice: stat.nice,
+                last_cpu: stat.last_cpu,
+                context_switches: voluntary.unwrap_or(0) + involuntary.unwrap_or(0),
+                voluntary_switches: voluntary,
+                involuntary_switches: involuntary,
+                wait_channel,
+            })
+        })
+        .collect();
+    threads.sort_by_key(|t| t.tid);
+    threads
+}
+
+// The thread's process group, which is usually its whole process or a shell job; the
+// app's own group is refused so a stray tid cannot take the task manager down with it.
+pub fn kill_process_group(tid: u32) -> bool {
+    let Some(tid) = libc::pid_t::try_from(tid).ok().filter(|t| *t > 0) else {
+        return false;
+    };
+    unsafe {
+        let pgid = libc::getpgid(tid);
+        if pgid <= 1 || pgid == libc::getpgid(0) {
+            return false;
+        }
+        libc::killpg(pgid, libc::SIGKILL) == 0
+    }
+}
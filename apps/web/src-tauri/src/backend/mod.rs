@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use crate::icons::IconImage;
+use crate::{
+    ConnectionInfo, DumpOptions, FileHolders, GpuAdapterInfo, GpuAdapterUsage, GpuEngineUsage, LogicalProcessor,
+    MemoryBreakdown, MemoryConfigInfo, MemoryRegion, ModuleInfo, OpenFile, ProcessEvent, ProcessScheduling,
+    ResourceLimits, ResourceUsage, SchedulingUpdate, ServiceInfo, ThreadInfo,
+};
+
+#[cfg(target_os = "windows")]
+mod windows;
+#[cfg(target_os = "windows")]
+pub use self::windows::WindowsBackend as Platform;
+
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "linux")]
+pub use self::linux::LinuxBackend as Platform;
+
+// Utilisation of one engine type on one adapter by one process. Engine types use the
+// Windows names (3D, Copy, VideoDecode, VideoEncode, Compute, ...).
+pub struct GpuEngineSample {
+    pub pid: u32,
+    pub adapter_index: u32,
+    pub engine_type: String,
+    pub utilization: f32,
+}
+
+#[derive(Default)]
+pub struct GpuSample {
+    pub engines: Vec<GpuEngineSample>,
+    // (dedicated, shared) memory in use, keyed by adapter index
+    pub adapter_memory: HashMap<u32, (u64, u64)>,
+    pub dedicated_memory: u64,
+    pub shared_memory: u64,
+}
+
+impl GpuSample {
+    // A process is as busy as its busiest engine, reported as (usage, "GPU n - type").
+    // Idle processes get no engine label, matching Task Manager's blank column.
+    pub fn process_usage(&self) -> HashMap<u32, (f32, Option<String>)> {
+        let mut usage: HashMap<u32, (f32, Option<String>)> = HashMap::new();
+        for sample in &self.engines {
+            let entry = usage.entry(sample.pid).or_insert((0.0, None));
+            if sample.utilization > entry.0 {
+                *entry = (
+                    sample.utilization.min(100.0),
+                    Some(format!("GPU {} - {}", sample.adapter_index, sample.engine_type)),
+                );
+            }
+        }
+        usage
+    }
+
+    // Engine utilisation is summed over processes, and an adapter is as busy as its
+    // busiest engine, so video decode next to a 3D load no longer adds up past 100%.
+    pub fn adapter_usage(&self) -> Vec<GpuAdapterUsage> {
+        let mut adapters: HashMap<u32, HashMap<&str, f32>> = HashMap::new();
+        for sample in &self.engines {
+            *adapters
+                .entry(sample.adapter_index)
+                .or_default()
+                .entry(&sample.engine_type)
+                .or_insert(0.0) += sample.utilization;
+        }
+
+        let mut result: Vec<GpuAdapterUsage> = adapters
+            .into_iter()
+            .map(|(index, engines)| {
+                let mut engines: Vec<GpuEngineUsage> = engines
+                    .into_iter()
+                    .map(|(engine_type, utilization)| GpuEngineUsage {
+                        engine_type: engine_type.to_string(),
+                        utilization: utilization.min(100.0),
+                    })
+                    .collect();
+                engines.sort_by(|a, b| a.engine_type.cmp(&b.engine_type));
+                let utilization = engines.iter().map(|e| e.utilization).fold(0.0, f32::max);
+                GpuAdapterUsage { index, utilization, engines }
+            })
+            .collect();
+        result.sort_by_key(|a| a.index);
+        result
+    }
+}
+
+pub trait GpuCollector: Send {
+    fn new() -> Self;
+    fn refresh(&mut self) -> GpuSample;
+}
+
+#[derive(Default, Clone, Copy)]
+pub struct NetworkUsage {
+    pub rx_bps: u64,
+    pub tx_bps: u64,
+}
+
+pub trait NetworkCollector: Send {
+    fn new() -> Self;
+    fn refresh(&mut self) -> HashMap<u32, NetworkUsage>;
+}
+
+pub trait SystemMetricsCollector: Send {
+    fn new() -> Self;
+    // Keys: threads, handles, committed, cached, pool_paged, pool_nonpaged, context_switches
+    fn refresh(&mut self) -> HashMap<String, f64>;
+}
+
+// Facts about a process that cannot change while it runs, so callers may cache them
+// for as long as the pid keeps the same start time.
+#[derive(Default, Clone)]
+pub struct ProcessIdentity {
+    pub architecture: Option<String>,
+    pub elevated: Option<bool>,
+    pub session_id: Option<u32>,
+}
+
+pub trait MetricsBackend {
+    type GpuMonitor: GpuCollector;
+    type NetworkMonitor: NetworkCollector;
+    type SystemMetricsMonitor: SystemMetricsCollector;
+    // Whatever is needed to undo efficiency mode for one process.
+    type EfficiencyRestore: Send;
+    // A cgroup on Linux, a job object on Windows.
+    type ResourceGroup: Send;
+
+    fn get_app_pids() -> HashSet<u32>;
+    fn extract_icon(path: &str) -> Option<IconImage>;
+    fn get_gpu_info() -> Vec<GpuAdapterInfo>;
+    fn get_memory_config(total_memory: u64) -> MemoryConfigInfo;
+    // Every socket on the system; process_name is left for the caller to fill in.
+    fn get_connections() -> Vec<ConnectionInfo>;
+    fn get_services() -> Vec<ServiceInfo>;
+    // action is one of start, stop, restart or open_msc
+    fn manage_service(name: &str, action: &str) -> bool;
+    fn suspend_process(pid: u32) -> bool;
+    fn resume_process(pid: u32) -> bool;
+    fn get_process_scheduling(pid: u32) -> Option<ProcessScheduling>;
+    fn set_process_scheduling(pid: u32, update: &SchedulingUpdate) -> bool;
+    fn get_cpu_topology() -> Vec<LogicalProcessor>;
+    fn get_affinity(pid: u32) -> Option<Vec<u32>>;
+    fn set_affinity(pid: u32, cpus: &[u32]) -> bool;
+    fn get_thread_affinity(tid: u32) -> Option<Vec<u32>>;
+    fn set_thread_affinity(tid: u32, cpus: &[u32]) -> bool;
+    fn enter_efficiency_mode(pid: u32) -> Option<Self::EfficiencyRestore>;
+    fn leave_efficiency_mode(pid: u32, restore: &Self::EfficiencyRestore) -> bool;
+    fn create_resource_group(pid: u32, pids: &[u32], limits: &ResourceLimits) -> Option<Self::ResourceGroup>;
+    fn apply_resource_limits(group: &Self::ResourceGroup, limits: &ResourceLimits) -> bool;
+    fn resource_usage(group: &Self::ResourceGroup) -> ResourceUsage;
+    fn release_resource_group(group: Self::ResourceGroup) -> bool;
+    fn process_identity(pid: u32) -> ProcessIdentity;
+    // cpu_usage is left at zero; it needs two samples, which the caller keeps.
+    fn get_threads(pid: u32) -> Vec<ThreadInfo>;
+    fn kill_process_group(tid: u32) -> bool;
+    fn get_process_modules(pid: u32) -> Vec<ModuleInfo>;
+    fn get_process_memory_map(pid: u32) -> Vec<MemoryRegion>;
+    // Every process the app can read, in one pass.
+    fn get_memory_breakdowns() -> HashMap<u32, MemoryBreakdown>;
+    fn get_process_open_files(pid: u32) -> Vec<OpenFile>;
+    // process_name is left for the caller to fill in.
+    fn find_file_holders(path_or_prefix: &str) -> FileHolders;
+    // The process is paused while its memory is written and resumed however the dump
+    // ends. progress gets (bytes written, bytes to write).
+    fn create_process_dump(
+        pid: u32,
+        path: &Path,
+        options: &DumpOptions,
+        progress: &mut dyn FnMut(u64, u64),
+    ) -> Result<u64, String>;
+    // Blocks for the life of the app, calling emit for every process start and exit; ids
+    // are assigned by the caller.
+    fn watch_processes(emit: &mut dyn FnMut(ProcessEvent));
+}
+
+// Windows priority classes and the nice values they are treated as equivalent to.
+const PRIORITY_CLASSES: [(&str, i32); 6] = [
+    ("idle", 19),
+    ("below_normal", 10),
+    ("normal", 0),
+    ("above_normal", -5),
+    ("high", -10),
+    ("realtime", -20),
+];
+
+pub fn priority_class_nice(class: &str) -> Option<i32> {
+    PRIORITY_CLASSES.iter().find(|(name, _)| *name == class).map(|(_, nice)| *nice)
+}
+
+// Realtime is never inferred from a nice value alone; it needs a realtime policy.
+pub fn nice_priority_class(nice: i32) -> &'static str {
+    match nice {
+        15.. => "idle",
+        5..=14 => "below_normal",
+        -4..=4 => "normal",
+        -9..=-5 => "above_normal",
+        _ => "high",
+    }
+}
+
+pub fn pci_vendor_name(vendor_id: u32) -> String {
+    match vendor_id {
+        0x10DE => "NVIDIA".to_string(),
+        0x1002 | 0x1022 => "AMD".to_string(),
+        0x8086 => "Intel".to_string(),
+        0x1414 => "Microsoft".to_string(),
+        0x5143 => "Qualcomm".to_string(),
+        0x13B5 => "ARM".to_string(),
+        0x1AF4 => "Red Hat".to_string(),
+        0x15AD => "VMware".to_string(),
+        0x80EE => "VirtualBox".to_string(),
+        0 => "Unknown".to_string(),
+        _ => format!("0x{:04X}", vendor_id),
+    }
+}
+
+pub type GpuMonitor = <Platform as MetricsBackend>::GpuMonitor;
+pub type NetworkMonitor = <Platform as MetricsBackend>::NetworkMonitor;
+pub type SystemMetricsMonitor = <Platform as MetricsBackend>::SystemMetricsMonitor;
+pub type EfficiencyRestore = <Platform as MetricsBackend>::EfficiencyRestore;
+pub type ResourceGroup = <Platform as MetricsBackend>::ResourceGroup;
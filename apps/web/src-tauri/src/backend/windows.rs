@@ -0,0 +1,2432 @@
+use std::collections::{HashSet, HashMap};
+use windows::Win32::Foundation::{CloseHandle, DuplicateHandle, LocalFree, BOOL, DUPLICATE_SAME_ACCESS, FILETIME, HANDLE, HLOCAL, HMODULE, HWND, LPARAM, LUID, NTSTATUS, RECT, WAIT_OBJECT_0};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetWindowThreadProcessId, IsWindowVisible, GetWindowRect,
+    GetIconInfo, DestroyIcon,
+};
+use windows::Win32::UI::Shell::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON};
+use windows::Win32::Graphics::Gdi::{
+    GetDC, ReleaseDC, CreateCompatibleDC, DeleteDC, GetObjectW,
+    GetDIBits, DeleteObject, BITMAP, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+};
+use windows::Win32::System::Performance::*;
+use windows::Win32::Graphics::Dxgi::{CreateDXGIFactory1, IDXGIFactory1, DXGI_ADAPTER_FLAG_SOFTWARE};
+use windows::core::PCWSTR;
+use windows::Win32::Storage::FileSystem::{
+    GetFileType, GetFinalPathNameByHandleW, SetFilePointerEx, FILE_CURRENT, FILE_FLAGS_AND_ATTRIBUTES, FILE_NAME_NORMALIZED,
+    FILE_TYPE_CHAR, FILE_TYPE_DISK, FILE_TYPE_PIPE,
+};
+use windows::Win32::NetworkManagement::IpHelper::{
+    GetExtendedTcpTable, GetExtendedUdpTable, MIB_TCP6TABLE_OWNER_PID, MIB_TCPTABLE_OWNER_PID,
+    MIB_UDP6TABLE_OWNER_PID, MIB_UDPTABLE_OWNER_PID, TCP_TABLE_OWNER_PID_ALL, UDP_TABLE_OWNER_PID,
+};
+use windows::Win32::Networking::WinSock::{AF_INET, AF_INET6};
+use windows::Win32::System::Threading::{
+    GetActiveProcessorCount, GetCurrentProcess, GetExitCodeProcess, GetPriorityClass, GetProcessTimes, GetProcessAffinityMask, GetProcessIdOfThread, GetThreadDescription, IsWow64Process2, OpenProcess, OpenProcessToken, OpenThread, ProcessPowerThrottling, SetPriorityClass,
+    SetProcessAffinityMask, SetProcessInformation, SetThreadAffinityMask, PROCESS_POWER_THROTTLING_CURRENT_VERSION,
+    PROCESS_POWER_THROTTLING_EXECUTION_SPEED, PROCESS_POWER_THROTTLING_STATE, ABOVE_NORMAL_PRIORITY_CLASS, BELOW_NORMAL_PRIORITY_CLASS, HIGH_PRIORITY_CLASS,
+    IDLE_PRIORITY_CLASS, NORMAL_PRIORITY_CLASS, PROCESS_CREATION_FLAGS, PROCESS_DUP_HANDLE, PROCESS_NAME_WIN32, PROCESS_QUERY_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION,
+    PROCESS_SET_INFORMATION, PROCESS_SET_QUOTA, PROCESS_SUSPEND_RESUME, PROCESS_TERMINATE, PROCESS_VM_READ, REALTIME_PRIORITY_CLASS,
+    QueryFullProcessImageNameW, TerminateProcess, WaitForSingleObject, ALL_PROCESSOR_GROUPS, PROCESS_SYNCHRONIZE, THREAD_QUERY_LIMITED_INFORMATION,
+    THREAD_SET_LIMITED_INFORMATION,
+};
+use windows::Win32::System::Diagnostics::Debug::{
+    MiniDumpWithDataSegs, MiniDumpWithFullMemory, MiniDumpWithFullMemoryInfo, MiniDumpWithHandleData,
+    MiniDumpWithPrivateReadWriteMemory, MiniDumpWithThreadInfo, MiniDumpWithUnloadedModules, MiniDumpWriteDump,
+    MINIDUMP_TYPE,
+};
+use windows::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JobObjectBasicAccountingInformation, JobObjectBasicProcessIdList,
+    JobObjectCpuRateControlInformation, JobObjectExtendedLimitInformation, QueryInformationJobObject,
+    SetInformationJobObject, JOBOBJECTINFOCLASS, JOBOBJECT_BASIC_ACCOUNTING_INFORMATION,
+    JOBOBJECT_BASIC_PROCESS_ID_LIST, JOBOBJECT_CPU_RATE_CONTROL_INFORMATION, JOBOBJECT_EXTENDED_LIMIT_INFORMATION,
+    JOB_OBJECT_CPU_RATE_CONTROL, JOB_OBJECT_CPU_RATE_CONTROL_ENABLE, JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP,
+    JOB_OBJECT_LIMIT, JOB_OBJECT_LIMIT_JOB_MEMORY,
+};
+use windows::Win32::System::Memory::{
+    VirtualQueryEx, MEMORY_BASIC_INFORMATION, MEM_COMMIT, MEM_PRIVATE, PAGE_EXECUTE, PAGE_EXECUTE_READ,
+    PAGE_EXECUTE_READWRITE, PAGE_EXECUTE_WRITECOPY, PAGE_PROTECTION_FLAGS, PAGE_READONLY, PAGE_READWRITE, PAGE_WRITECOPY,
+};
+use windows::Win32::System::ProcessStatus::{
+    K32EnumProcessModulesEx, K32GetMappedFileNameW, K32GetModuleFileNameExW, K32GetModuleInformation,
+    K32GetProcessMemoryInfo, LIST_MODULES_ALL, MODULEINFO, PROCESS_MEMORY_COUNTERS,
+};
+use windows::Win32::System::RemoteDesktop::ProcessIdToSessionId;
+use windows::Win32::Security::{GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY};
+use windows::Win32::System::SystemInformation::{
+    GetLogicalProcessorInformationEx, RelationAll, RelationCache, RelationNumaNode, RelationProcessorCore,
+    RelationProcessorPackage, CacheInstruction, GROUP_AFFINITY, SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX,
+    IMAGE_FILE_MACHINE, IMAGE_FILE_MACHINE_AMD64, IMAGE_FILE_MACHINE_ARM64, IMAGE_FILE_MACHINE_ARMNT,
+    IMAGE_FILE_MACHINE_I386, IMAGE_FILE_MACHINE_UNKNOWN,
+};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::io::Cursor;
+use image::{RgbaImage, ImageOutputFormat};
+
+use super::{nice_priority_class, pci_vendor_name, priority_class_nice, GpuCollector, GpuEngineSample, GpuSample, MetricsBackend, NetworkCollector, NetworkUsage, ProcessIdentity, SystemMetricsCollector};
+use crate::icons::IconImage;
+use crate::{
+    epoch_ms, ConnectionInfo, DumpOptions, FileHolder, FileHolders, ProcessEvent, GpuAdapterInfo, LogicalProcessor, MemoryBreakdown, MemoryConfigInfo,
+    MemoryRegion, ModuleInfo, OpenFile, ProcessScheduling, ResourceLimits, ResourceUsage, SchedulingUpdate, ServiceInfo,
+    ThreadInfo,
+};
+
+pub struct WindowsBackend;
+
+impl MetricsBackend for WindowsBackend {
+    type GpuMonitor = GpuMonitor;
+    type NetworkMonitor = NetworkMonitor;
+    type SystemMetricsMonitor = SystemMetricsMonitor;
+    type EfficiencyRestore = EfficiencyRestore;
+    type ResourceGroup = ResourceGroup;
+
+    fn get_app_pids() -> HashSet<u32> {
+        get_app_pids()
+    }
+
+    fn extract_icon(path: &str) -> Option<IconImage> {
+        extract_icon(path)
+    }
+
+    fn get_gpu_info() -> Vec<GpuAdapterInfo> {
+        get_gpu_info()
+    }
+
+    fn get_memory_config(total_memory: u64) -> MemoryConfigInfo {
+        get_memory_config(total_memory)
+    }
+
+    fn get_connections() -> Vec<ConnectionInfo> {
+        get_connections()
+    }
+
+    fn get_services() -> Vec<ServiceInfo> {
+        get_services()
+    }
+
+    fn manage_service(name: &str, action: &str) -> bool {
+        manage_service(name, action)
+    }
+
+    fn suspend_process(pid: u32) -> bool {
+        with_process_handle(pid, |handle| unsafe { NtSuspendProcess(handle) })
+    }
+
+    fn resume_process(pid: u32) -> bool {
+        with_process_handle(pid, |handle| unsafe { NtResumeProcess(handle) })
+    }
+
+    fn get_process_scheduling(pid: u32) -> Option<ProcessScheduling> {
+        get_process_scheduling(pid)
+    }
+
+    fn set_process_scheduling(pid: u32, update: &SchedulingUpdate) -> bool {
+        set_process_scheduling(pid, update)
+    }
+
+    fn get_cpu_topology() -> Vec<LogicalProcessor> {
+        get_cpu_topology()
+    }
+
+    fn get_affinity(pid: u32) -> Option<Vec<u32>> {
+        get_affinity(pid)
+    }
+
+    fn set_affinity(pid: u32, cpus: &[u32]) -> bool {
+        set_affinity(pid, cpus)
+    }
+
+    fn get_thread_affinity(tid: u32) -> Option<Vec<u32>> {
+        get_thread_affinity(tid)
+    }
+
+    fn set_thread_affinity(tid: u32, cpus: &[u32]) -> bool {
+        set_thread_affinity(tid, cpus)
+    }
+
+    fn enter_efficiency_mode(pid: u32) -> Option<EfficiencyRestore> {
+        enter_efficiency_mode(pid)
+    }
+
+    fn leave_efficiency_mode(pid: u32, restore: &EfficiencyRestore) -> bool {
+        leave_efficiency_mode(pid, restore)
+    }
+
+    fn create_resource_group(_pid: u32, pids: &[u32], limits: &ResourceLimits) -> Option<ResourceGroup> {
+        create_resource_group(pids, limits)
+    }
+
+    fn apply_resource_limits(group: &ResourceGroup, limits: &ResourceLimits) -> bool {
+        apply_resource_limits(group, limits)
+    }
+
+    fn resource_usage(group: &ResourceGroup) -> ResourceUsage {
+        resource_usage(group)
+    }
+
+    fn release_resource_group(group: ResourceGroup) -> bool {
+        release_resource_group(group)
+    }
+
+    fn process_identity(pid: u32) -> ProcessIdentity {
+        process_identity(pid)
+    }
+
+    fn get_threads(pid: u32) -> Vec<ThreadInfo> {
+        get_threads(pid)
+    }
+
+    fn kill_process_group(tid: u32) -> bool {
+        kill_process_group(tid)
+    }
+
+    fn get_process_modules(pid: u32) -> Vec<ModuleInfo> {
+        get_process_modules(pid)
+    }
+
+    fn get_process_memory_map(pid: u32) -> Vec<MemoryRegion> {
+        get_process_memory_map(pid)
+    }
+
+    fn get_memory_breakdowns() -> HashMap<u32, MemoryBreakdown> {
+        get_memory_breakdowns()
+    }
+
+    fn get_process_open_files(pid: u32) -> Vec<OpenFile> {
+        get_process_open_files(pid)
+    }
+
+    fn find_file_holders(path_or_prefix: &str) -> FileHolders {
+        find_file_holders(path_or_prefix)
+    }
+
+    fn create_process_dump(
+        pid: u32,
+        path: &std::path::Path,
+        options: &DumpOptions,
+        progress: &mut dyn FnMut(u64, u64),
+    ) -> Result<u64, String> {
+        create_process_dump(pid, path, options, progress)
+    }
+
+    fn watch_processes(emit: &mut dyn FnMut(ProcessEvent)) {
+        watch_processes(emit)
+    }
+}
+
+pub struct GpuMonitor {
+    query: isize,
+    utilization_counter: isize,
+    memory_counter: isize,
+    shared_memory_counter: isize,
+    adapter_luids: Vec<String>,
+    initialized: bool,
+}
+
+unsafe impl Send for GpuMonitor {}
+
+impl GpuCollector for GpuMonitor {
+    fn new() -> Self {
+        Self {
+            query: 0,
+            utilization_counter: 0,
+            memory_counter: 0,
+            shared_memory_counter: 0,
+            adapter_luids: Vec::new(),
+            initialized: false,
+        }
+    }
+
+    fn refresh(&mut self) -> GpuSample {
+        let mut engine_usage: HashMap<(u32, String, String), f32> = HashMap::new();
+        let mut adapter_memory: HashMap<String, (u64, u64)> = HashMap::new();
+        let mut total_memory = 0;
+        let mut total_shared_memory = 0;
+
+        unsafe {
+            if !self.initialized {
+                let open_res = PdhOpenQueryW(None, 0, &mut self.query);
+                if open_res == 0 {
+                    let util_path = to_wstring("\\GPU Engine(*)\\Utilization Percentage");
+                    let mem_path = to_wstring("\\GPU Adapter Memory(*)\\Dedicated Usage");
+                    let shared_mem_path = to_wstring("\\GPU Adapter Memory(*)\\Shared Usage");
+                    
+                    let add_util = PdhAddEnglishCounterW(self.query, PCWSTR(util_path.as_ptr()), 0, &mut self.utilization_counter);
+                    let add_mem = PdhAddEnglishCounterW(self.query, PCWSTR(mem_path.as_ptr()), 0, &mut self.memory_counter);
+                    let add_shared = PdhAddEnglishCounterW(self.query, PCWSTR(shared_mem_path.as_ptr()), 0, &mut self.shared_memory_counter);
+                    
+                    if add_util == 0 && add_mem == 0 && add_shared == 0 {
+                        self.adapter_luids = dxgi_adapters().into_iter().map(|a| a.luid).collect();
+                        self.initialized = true;
+                        println!("PDH GPU Monitor Initialized Successfully");
+                    }
+                }
+            }
+
+            if self.initialized {
+                if PdhCollectQueryData(self.query) == 0 {
+                    let mut buffer_size = 0;
+                    let mut item_count = 0;
+                    let _ = PdhGetFormattedCounterArrayW(self.utilization_counter, PDH_FMT_DOUBLE, &mut buffer_size, &mut item_count, None);
+
+                    if item_count > 0 {
+                        let mut buffer = vec![0u8; buffer_size as usize];
+                        let items_ptr = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
+                        if PdhGetFormattedCounterArrayW(self.utilization_counter, PDH_FMT_DOUBLE, &mut buffer_size, &mut item_count, Some(items_ptr)) == 0 {
+                            let items = std::slice::from_raw_parts(items_ptr, item_count as usize);
+                            for item in items {
+                                if !item.szName.is_null() {
+                                    let name = item.szName.to_string().unwrap_or_default();
+                                    if let Some(key) = parse_engine_instance(&name) {
+                                        let val = item.FmtValue.Anonymous.doubleValue;
+                                        *engine_usage.entry(key).or_insert(0.0) += val as f32;
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    let mut mem_buffer_size = 0;
+                    let mut mem_item_count = 0;
+                    let _ = PdhGetFormattedCounterArrayW(self.memory_counter, PDH_FMT_LARGE, &mut mem_buffer_size, &mut mem_item_count, None);
+
+                    if mem_item_count > 0 {
+                        let mut buffer = vec![0u8; mem_buffer_size as usize];
+                        let items_ptr = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
+                        if PdhGetFormattedCounterArrayW(self.memory_counter, PDH_FMT_LARGE, &mut mem_buffer_size, &mut mem_item_count, Some(items_ptr)) == 0 {
+                            let items = std::slice::from_raw_parts(items_ptr, mem_item_count as usize);
+                            for item in items {
+                                let val = item.FmtValue.Anonymous.largeValue;
+                                total_memory += val as u64;
+                                if !item.szName.is_null() {
+                                    let name = item.szName.to_string().unwrap_or_default();
+                                    if let Some(luid) = parse_luid(&name) {
+                                        adapter_memory.entry(luid).or_default().0 += val as u64;
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    let mut shared_buffer_size = 0;
+                    let mut shared_item_count = 0;
+                    let _ = PdhGetFormattedCounterArrayW(self.shared_memory_counter, PDH_FMT_LARGE, &mut shared_buffer_size, &mut shared_item_count, None);
+
+                    if shared_item_count > 0 {
+                        let mut buffer = vec![0u8; shared_buffer_size as usize];
+                        let items_ptr = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
+                        if PdhGetFormattedCounterArrayW(self.shared_memory_counter, PDH_FMT_LARGE, &mut shared_buffer_size, &mut shared_item_count, Some(items_ptr)) == 0 {
+                            let items = std::slice::from_raw_parts(items_ptr, shared_item_count as usize);
+                            for item in items {
+                                let val = item.FmtValue.Anonymous.largeValue;
+                                total_shared_memory += val as u64;
+                                if !item.szName.is_null() {
+                                    let name = item.szName.to_string().unwrap_or_default();
+                                    if let Some(luid) = parse_luid(&name) {
+                                        adapter_memory.entry(luid).or_default().1 += val as u64;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // Adapters are numbered in DXGI enumeration order, the same way Task Manager
+        // numbers "GPU n" and get_gpu_info lists them. Adapters DXGI did not report
+        // (e.g. the basic render driver) are numbered after the real ones.
+        for luid in engine_usage.keys().map(|(_, luid, _)| luid).chain(adapter_memory.keys()) {
+            if !self.adapter_luids.contains(luid) {
+                self.adapter_luids.push(luid.clone());
+            }
+        }
+        let adapter_index = |luid: &str| -> u32 {
+            self.adapter_luids.iter().position(|l| l == luid).unwrap_or(0) as u32
+        };
+
+        let engines = engine_usage
+            .into_iter()
+            .map(|((pid, luid, engine_type), utilization)| GpuEngineSample {
+                pid,
+                adapter_index: adapter_index(&luid),
+                engine_type,
+                utilization: utilization.min(100.0),
+            })
+            .collect();
+
+        GpuSample {
+            engines,
+            adapter_memory: adapter_memory
+                .into_iter()
+                .map(|(luid, memory)| (adapter_index(&luid), memory))
+                .collect(),
+            dedicated_memory: total_memory,
+            shared_memory: total_shared_memory,
+        }
+    }
+}
+
+// Instance names look like pid_1234_luid_0x00000000_0x0000D1F0_phys_0_eng_3_engtype_VideoDecode.
+fn parse_engine_instance(name: &str) -> Option<(u32, String, String)> {
+    let start = name.find("pid_")? + 4;
+    let end = name[start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(name.len(), |e| start + e);
+    let pid = name[start..end].parse().ok()?;
+    let luid = parse_luid(name)?;
+    let engine_type = &name[name.find("engtype_")? + 8..];
+    Some((pid, luid, engine_type.to_string()))
+}
+
+fn parse_luid(name: &str) -> Option<String> {
+    let start = name.find("luid_")? + 5;
+    let end = name[start..].find("_phys").map_or(name.len(), |e| start + e);
+    Some(name[start..end].to_string())
+}
+
+pub struct NetworkMonitor {
+    query: isize,
+    io_counter: isize,
+    pid_counter: isize,
+    initialized: bool,
+}
+
+unsafe impl Send for NetworkMonitor {}
+
+impl NetworkCollector for NetworkMonitor {
+    fn new() -> Self {
+        Self {
+            query: 0,
+            io_counter: 0,
+            pid_counter: 0,
+            initialized: false,
+        }
+    }
+
+    // PDH has no per-process network counter. IO Other Bytes/sec (I/O that is neither a
+    // file read nor a write, which includes socket traffic) is the closest approximation
+    // and cannot be split by direction, so it is all reported as received.
+    fn refresh(&mut self) -> HashMap<u32, NetworkUsage> {
+        let mut usage_map: HashMap<u32, NetworkUsage> = HashMap::new();
+        unsafe {
+            if !self.initialized {
+                if PdhOpenQueryW(None, 0, &mut self.query) == 0 {
+                    let io_path = to_wstring("\\Process(*)\\IO Other Bytes/sec");
+                    let pid_path = to_wstring("\\Process(*)\\ID Process");
+                    let res1 = PdhAddEnglishCounterW(self.query, PCWSTR(io_path.as_ptr()), 0, &mut self.io_counter);
+                    let res2 = PdhAddEnglishCounterW(self.query, PCWSTR(pid_path.as_ptr()), 0, &mut self.pid_counter);
+                    if res1 == 0 && res2 == 0 {
+                        self.initialized = true;
+                        println!("PDH Network Monitor Initialized Successfully");
+                    }
+                }
+            }
+
+            if self.initialized {
+                if PdhCollectQueryData(self.query) == 0 {
+                    let mut io_buffer_size = 0;
+                    let mut io_item_count = 0;
+                    let _ = PdhGetFormattedCounterArrayW(self.io_counter, PDH_FMT_DOUBLE, &mut io_buffer_size, &mut io_item_count, None);
+
+                    let mut pid_buffer_size = 0;
+                    let mut pid_item_count = 0;
+                    let _ = PdhGetFormattedCounterArrayW(self.pid_counter, PDH_FMT_DOUBLE, &mut pid_buffer_size, &mut pid_item_count, None);
+
+                    if io_item_count > 0 && pid_item_count > 0 {
+                        let mut io_buffer = vec![0u8; io_buffer_size as usize];
+                        let mut pid_buffer = vec![0u8; pid_buffer_size as usize];
+                        let io_items_ptr = io_buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
+                        let pid_items_ptr = pid_buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
+
+                        if PdhGetFormattedCounterArrayW(self.io_counter, PDH_FMT_DOUBLE, &mut io_buffer_size, &mut io_item_count, Some(io_items_ptr)) == 0 &&
+                           PdhGetFormattedCounterArrayW(self.pid_counter, PDH_FMT_DOUBLE, &mut pid_buffer_size, &mut pid_item_count, Some(pid_items_ptr)) == 0 {
+                            
+                            let io_items = std::slice::from_raw_parts(io_items_ptr, io_item_count as usize);
+                            let pid_items = std::slice::from_raw_parts(pid_items_ptr, pid_item_count as usize);
+
+                            let mut name_to_pid = HashMap::new();
+                            for item in pid_items {
+                                if !item.szName.is_null() {
+                                    let name = item.szName.to_string().unwrap_or_default();
+                                    let pid = item.FmtValue.Anonymous.doubleValue as u32;
+                                    name_to_pid.insert(name, pid);
+                                }
+                            }
+
+                            for item in io_items {
+                                if !item.szName.is_null() {
+                                    let name = item.szName.to_string().unwrap_or_default();
+                                    if let Some(&pid) = name_to_pid.get(&name) {
+                                        let val = item.FmtValue.Anonymous.doubleValue;
+                                        if val > 0.0 {
+                                            usage_map.entry(pid).or_default().rx_bps += val as u64;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        usage_map
+    }
+}
+
+pub struct SystemMetricsMonitor {
+    query: isize,
+    counters: HashMap<String, isize>,
+    initialized: bool,
+}
+
+unsafe impl Send for SystemMetricsMonitor {}
+
+impl SystemMetricsCollector for SystemMetricsMonitor {
+    fn new() -> Self {
+        Self {
+            query: 0,
+            counters: HashMap::new(),
+            initialized: false,
+        }
+    }
+
+    fn refresh(&mut self) -> HashMap<String, f64> {
+        let mut results = HashMap::new();
+        unsafe {
+            if !self.initialized {
+                if PdhOpenQueryW(None, 0, &mut self.query) == 0 {
+                    let counters_to_add = vec![
+                        ("threads", "\\System\\Threads"),
+                        ("handles", "\\Process(_Total)\\Handle Count"),
+                        ("committed", "\\Memory\\Committed Bytes"),
+                        ("cached", "\\Memory\\Cache Bytes"),
+                        ("pool_paged", "\\Memory\\Pool Paged Bytes"),
+                        ("pool_nonpaged", "\\Memory\\Pool Nonpaged Bytes"),
+                        ("context_switches", "\\System\\Context Switches/sec"),
+                    ];
+
+                    for (key, path) in counters_to_add {
+                        let mut counter_handle = 0;
+                        let wide_path = to_wstring(path);
+                        if PdhAddEnglishCounterW(self.query, PCWSTR(wide_path.as_ptr()), 0, &mut counter_handle) == 0 {
+                            self.counters.insert(key.to_string(), counter_handle);
+                        }
+                    }
+                    self.initialized = true;
+                    println!("PDH System Metrics Initialized");
+                }
+            }
+
+            if self.initialized {
+                if PdhCollectQueryData(self.query) == 0 {
+                    for (key, handle) in &self.counters {
+                        let mut type_ = 0;
+                        let mut value = std::mem::zeroed();
+                        if PdhGetFormattedCounterValue(*handle, PDH_FMT_DOUBLE, Some(&mut type_), &mut value) == 0 {
+                            results.insert(key.clone(), value.Anonymous.doubleValue);
+                        }
+                    }
+                }
+            }
+        }
+        results
+    }
+}
+
+unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    if IsWindowVisible(hwnd).as_bool() {
+        let mut rect = RECT::default();
+        let _ = GetWindowRect(hwnd, &mut rect);
+        if (rect.right - rect.left) > 0 && (rect.bottom - rect.top) > 0 {
+            let mut pid = 0;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            if pid != 0 {
+                let pids = &mut *(lparam.0 as *mut HashSet<u32>);
+                pids.insert(pid);
+            }
+        }
+    }
+    BOOL(1) 
+}
+
+fn get_app_pids() -> HashSet<u32> {
+    let mut pids = HashSet::new();
+    unsafe {
+        let _ = EnumWindows(
+            Some(enum_windows_proc), 
+            LPARAM(&mut pids as *mut _ as isize)
+        );
+    }
+    pids
+}
+
+fn to_wstring(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+fn extract_icon(path: &str) -> Option<IconImage> {
+    unsafe {
+        let wide_path = to_wstring(path);
+        let mut sh_file_info = SHFILEINFOW::default();
+        
+        let result = SHGetFileInfoW(
+            windows::core::PCWSTR(wide_path.as_ptr()),
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            Some(&mut sh_file_info),
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            SHGFI_ICON | SHGFI_LARGEICON,
+        );
+
+        if result == 0 || sh_file_info.hIcon.is_invalid() {
+            return None;
+        }
+
+        let h_icon = sh_file_info.hIcon;
+        let mut icon_info = std::mem::zeroed();
+        if GetIconInfo(h_icon, &mut icon_info).is_err() {
+            let _ = DestroyIcon(h_icon);
+            return None;
+        }
+
+        let dc = GetDC(None);
+        let mem_dc = CreateCompatibleDC(dc);
+        let _ = ReleaseDC(None, dc);
+
+        let mut bitmap: BITMAP = std::mem::zeroed();
+        let h_bitmap = if !icon_info.hbmColor.is_invalid() {
+            icon_info.hbmColor
+        } else {
+            icon_info.hbmMask
+        };
+
+        if GetObjectW(
+            windows::Win32::Graphics::Gdi::HGDIOBJ(h_bitmap.0),
+            std::mem::size_of::<BITMAP>() as i32,
+            Some(&mut bitmap as *mut _ as *mut _),
+        ) == 0 {
+            let _ = DeleteDC(mem_dc);
+            let _ = DeleteObject(icon_info.hbmColor);
+            let _ = DeleteObject(icon_info.hbmMask);
+            let _ = DestroyIcon(h_icon);
+            return None;
+        }
+
+        let width = bitmap.bmWidth;
+        let height = bitmap.bmHeight;
+        let size = (width * height * 4) as usize;
+        let mut pixels: Vec<u8> = vec![0; size];
+
+        let mut bi = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height, 
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0, 
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        if GetDIBits(
+            mem_dc,
+            h_bitmap,
+            0,
+            height.abs() as u32,
+            Some(pixels.as_mut_ptr() as *mut _),
+            &mut bi,
+            DIB_RGB_COLORS,
+        ) == 0 {
+            let _ = DeleteDC(mem_dc);
+            let _ = DeleteObject(icon_info.hbmColor);
+            let _ = DeleteObject(icon_info.hbmMask);
+            let _ = DestroyIcon(h_icon);
+            return None;
+        }
+        
+        let _ = DeleteDC(mem_dc);
+        let _ = DeleteObject(icon_info.hbmColor);
+        let _ = DeleteObject(icon_info.hbmMask);
+        let _ = DestroyIcon(h_icon);
+
+        for chunk in pixels.chunks_mut(4) {
+            let b = chunk[0];
+            let r = chunk[2];
+            chunk[0] = r;
+            chunk[2] = b;
+        }
+
+        if let Some(img_buffer) = RgbaImage::from_raw(width as u32, height.abs() as u32, pixels) {
+             let mut png_data = Vec::new();
+             if img_buffer.write_to(&mut Cursor::new(&mut png_data), ImageOutputFormat::Png).is_ok() {
+                 return Some(IconImage { bytes: png_data, extension: "png" });
+             }
+        }
+        
+        None
+    }
+}
+
+struct DxgiAdapter {
+    luid: String,
+    name: String,
+    vendor_id: u32,
+    device_id: u32,
+    dedicated_memory: u64,
+}
+
+// Formats LUIDs the way PDH prints them in GPU counter instance names.
+fn format_luid(luid: LUID) -> String {
+    format!("0x{:08X}_0x{:08X}", luid.HighPart as u32, luid.LowPart)
+}
+
+fn dxgi_adapters() -> Vec<DxgiAdapter> {
+    let mut adapters = Vec::new();
+    unsafe {
+        let Ok(factory) = CreateDXGIFactory1::<IDXGIFactory1>() else {
+            return adapters;
+        };
+        let mut i = 0;
+        while let Ok(adapter) = factory.EnumAdapters1(i) {
+            i += 1;
+            let Ok(desc) = adapter.GetDesc1() else {
+                continue;
+            };
+            if desc.Flags & DXGI_ADAPTER_FLAG_SOFTWARE.0 as u32 != 0 {
+                continue;
+            }
+            let name_len = desc.Description.iter().position(|&c| c == 0).unwrap_or(desc.Description.len());
+            adapters.push(DxgiAdapter {
+                luid: format_luid(desc.AdapterLuid),
+                name: String::from_utf16_lossy(&desc.Description[..name_len]),
+                vendor_id: desc.VendorId,
+                device_id: desc.DeviceId,
+                dedicated_memory: desc.DedicatedVideoMemory as u64,
+            });
+        }
+    }
+    adapters
+}
+
+// Reads the hex value following `key` in a PNP device id such as PCI\VEN_10DE&DEV_2204&...
+fn pnp_id_field(pnp_id: &str, key: &str) -> Option<u32> {
+    let start = pnp_id.find(key)? + key.len();
+    let hex: String = pnp_id[start..].chars().take_while(|c| c.is_ascii_hexdigit()).collect();
+    u32::from_str_radix(&hex, 16).ok()
+}
+
+fn get_gpu_info() -> Vec<GpuAdapterInfo> {
+    use std::process::Command;
+    use std::os::windows::process::CommandExt;
+    
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile", 
+            "-Command", 
+            "Get-CimInstance Win32_VideoController | Select-Object Name, AdapterRAM, DriverVersion, DriverDate, PNPDeviceID | ConvertTo-Json -Compress"
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+
+    #[derive(serde::Deserialize)]
+    struct GpuInfoRaw {
+        Name: Option<String>,
+        AdapterRAM: Option<u64>,
+        DriverVersion: Option<String>,
+        DriverDate: Option<String>,
+        PNPDeviceID: Option<String>,
+    }
+
+    let mut controllers: Vec<GpuInfoRaw> = Vec::new();
+    if let Ok(output) = output {
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        if let Ok(gpus) = serde_json::from_str::<Vec<GpuInfoRaw>>(&output_str) {
+            controllers = gpus;
+        } else if let Ok(gpu) = serde_json::from_str::<GpuInfoRaw>(&output_str) {
+            controllers.push(gpu);
+        }
+    }
+
+    let get_location = |pnp_id: &str| -> String {
+        let escaped_id = pnp_id.replace("\\", "\\\\");
+        let loc_output = Command::new("powershell")
+            .args([
+                "-NoProfile", 
+                "-Command", 
+                &format!(
+                    "Get-CimInstance Win32_PnPEntity -Filter \"DeviceID='{}'\" | Select-Object -ExpandProperty Location", 
+                    escaped_id
+                )
+            ])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output();
+
+        if let Ok(l_out) = loc_output {
+            let l_str = String::from_utf8_lossy(&l_out.stdout).trim().to_string();
+            if !l_str.is_empty() {
+                return l_str;
+            }
+        }
+        "PCI bus".to_string()
+    };
+
+    let to_info = |index: usize, controller: Option<&GpuInfoRaw>, dxgi: Option<&DxgiAdapter>| -> GpuAdapterInfo {
+        let pnp_id = controller.and_then(|c| c.PNPDeviceID.clone()).unwrap_or_default();
+        let vendor_id = dxgi
+            .map(|d| d.vendor_id)
+            .or_else(|| pnp_id_field(&pnp_id, "VEN_"))
+            .unwrap_or(0);
+        GpuAdapterInfo {
+            index: index as u32,
+            name: dxgi
+                .map(|d| d.name.clone())
+                .or_else(|| controller.and_then(|c| c.Name.clone()))
+                .unwrap_or("Unknown GPU".to_string()),
+            vendor: pci_vendor_name(vendor_id),
+            // AdapterRAM is a 32-bit field and caps out at 4 GB, so prefer DXGI's value.
+            memory_total: dxgi
+                .map(|d| d.dedicated_memory)
+                .or_else(|| controller.and_then(|c| c.AdapterRAM))
+                .unwrap_or(0),
+            driver_version: controller.and_then(|c| c.DriverVersion.clone()).unwrap_or("Unknown".to_string()),
+            driver_date: controller.and_then(|c| c.DriverDate.clone()).unwrap_or("Unknown".to_string()),
+            location: if pnp_id.is_empty() { "PCI bus".to_string() } else { get_location(&pnp_id) },
+            dedicated_memory_used: 0,
+            shared_memory_used: 0,
+        }
+    };
+
+    let adapters = dxgi_adapters();
+    if adapters.is_empty() {
+        return controllers
+            .iter()
+            .enumerate()
+            .map(|(index, controller)| to_info(index, Some(controller), None))
+            .collect();
+    }
+
+    adapters
+        .iter()
+        .enumerate()
+        .map(|(index, adapter)| {
+            let controller = controllers.iter().find(|c| {
+                let pnp_id = c.PNPDeviceID.as_deref().unwrap_or_default();
+                pnp_id_field(pnp_id, "VEN_") == Some(adapter.vendor_id)
+                    && pnp_id_field(pnp_id, "DEV_") == Some(adapter.device_id)
+            });
+            to_info(index, controller, Some(adapter))
+        })
+        .collect()
+}
+
+fn get_memory_config(total_memory: u64) -> MemoryConfigInfo {
+    use std::process::Command;
+    use std::os::windows::process::CommandExt;
+    
+    const CREATE_NO_WINDOW: u32 = 0x08000000;
+
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile", 
+            "-Command", 
+            "Get-CimInstance Win32_PhysicalMemory | Select-Object Speed, FormFactor, Capacity | ConvertTo-Json -Compress"
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+    
+    let mut speed_mhz = 0u32;
+    let mut slots_used = 0u32;
+    let mut form_factor = "Unknown".to_string();
+    let mut total_installed: u64 = 0;
+
+    if let Ok(output) = output {
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        
+        #[derive(serde::Deserialize)]
+        struct MemModule {
+            Speed: Option<u32>,
+            FormFactor: Option<u32>,
+            Capacity: Option<u64>,
+        }
+
+        fn form_factor_to_string(ff: u32) -> String {
+            match ff {
+                0 => "Unknown".to_string(),
+                1 => "Other".to_string(),
+                2 => "SIP".to_string(),
+                3 => "DIP".to_string(),
+                4 => "ZIP".to_string(),
+                5 => "SOJ".to_string(),
+                6 => "Proprietary".to_string(),
+                7 => "SIMM".to_string(),
+                8 => "DIMM".to_string(),
+                9 => "TSOP".to_string(),
+                10 => "PGA".to_string(),
+                11 => "RIMM".to_string(),
+                12 => "SODIMM".to_string(),
+                13 => "SRIMM".to_string(),
+                14 => "SMD".to_string(),
+                15 => "SSMP".to_string(),
+                16 => "QFP".to_string(),
+                17 => "TQFP".to_string(),
+                18 => "SOIC".to_string(),
+                19 => "LCC".to_string(),
+                20 => "PLCC".to_string(),
+                21 => "BGA".to_string(),
+                22 => "FPBGA".to_string(),
+                23 => "LGA".to_string(),
+                _ => "Unknown".to_string(),
+            }
+        }
+
+        if let Ok(modules) = serde_json::from_str::<Vec<MemModule>>(&output_str) {
+            slots_used = modules.len() as u32;
+            for module in &modules {
+                if let Some(spd) = module.Speed {
+                    if spd > speed_mhz {
+                        speed_mhz = spd;
+                    }
+                }
+                if let Some(ff) = module.FormFactor {
+                    if form_factor == "Unknown" || form_factor == "Other" {
+                        form_factor = form_factor_to_string(ff);
+                    }
+                }
+                if let Some(cap) = module.Capacity {
+                    total_installed += cap;
+                }
+            }
+        } else if let Ok(module) = serde_json::from_str::<MemModule>(&output_str) {
+            slots_used = 1;
+            if let Some(spd) = module.Speed {
+                speed_mhz = spd;
+            }
+            if let Some(ff) = module.FormFactor {
+                form_factor = form_factor_to_string(ff);
+            }
+            if let Some(cap) = module.Capacity {
+                total_installed = cap;
+            }
+        }
+    }
+
+    let slots_output = Command::new("powershell")
+        .args([
+            "-NoProfile", 
+            "-Command", 
+            "Get-CimInstance Win32_PhysicalMemoryArray | Select-Object MemoryDevices | ConvertTo-Json -Compress"
+        ])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output();
+    
+    let mut slots_total = slots_used;
+
+    if let Ok(output) = slots_output {
+        let output_str = String::from_utf8_lossy(&output.stdout);
+        
+        #[derive(serde::Deserialize)]
+        struct MemArray {
+            MemoryDevices: Option<u32>,
+        }
+
+        if let Ok(arr) = serde_json::from_str::<MemArray>(&output_str) {
+            if let Some(devices) = arr.MemoryDevices {
+                slots_total = devices;
+            }
+        } else if let Ok(arrays) = serde_json::from_str::<Vec<MemArray>>(&output_str) {
+            for arr in arrays {
+                if let Some(devices) = arr.MemoryDevices {
+                    slots_total = devices;
+                    break;
+                }
+            }
+        }
+    }
+
+    let hardware_reserved = if total_installed > total_memory {
+        total_installed - total_memory
+    } else {
+        0
+    };
+
+    MemoryConfigInfo {
+        speed_mhz,
+        slots_used,
+        slots_total,
+        form_factor,
+        hardware_reserved,
+    }
+}
+
+// Calls an IP Helper table function twice: once for the size, then to fill a buffer.
+// The buffer is u32-backed so the MIB_*TABLE structs read from it are aligned.
+fn ip_helper_table(fetch: impl Fn(*mut std::ffi::c_void, &mut u32) -> u32) -> Option<Vec<u32>> {
+    let mut size = 0u32;
+    fetch(std::ptr::null_mut(), &mut size);
+    for _ in 0..3 {
+        let mut buffer = vec![0u32; (size as usize).div_ceil(4)];
+        match fetch(buffer.as_mut_ptr() as *mut std::ffi::c_void, &mut size) {
+            0 => return Some(buffer),
+            // ERROR_INSUFFICIENT_BUFFER: the table grew between the two calls
+            122 => continue,
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn tcp_state_name(state: u32) -> &'static str {
+    // MIB_TCP_STATE values, named like the Linux backend reports them.
+    match state {
+        1 => "CLOSE",
+        2 => "LISTEN",
+        3 => "SYN_SENT",
+        4 => "SYN_RECV",
+        5 => "ESTABLISHED",
+        6 => "FIN_WAIT1",
+        7 => "FIN_WAIT2",
+        8 => "CLOSE_WAIT",
+        9 => "CLOSING",
+        10 => "LAST_ACK",
+        11 => "TIME_WAIT",
+        12 => "DELETE_TCB",
+        _ => "UNKNOWN",
+    }
+}
+
+// Ports are stored in network byte order in the low 16 bits.
+fn port(raw: u32) -> u16 {
+    u16::from_be(raw as u16)
+}
+
+fn connection(protocol: &str, local: (String, u16), remote: Option<(String, u16)>, state: &str, pid: u32) -> ConnectionInfo {
+    let (remote_address, remote_port) = match remote {
+        Some((address, port)) => (address, Some(port)),
+        None => (String::new(), None),
+    };
+    ConnectionInfo {
+        protocol: protocol.to_string(),
+        local_address: local.0,
+        local_port: Some(local.1),
+        remote_address,
+        remote_port,
+        state: state.to_string(),
+        pid: Some(pid),
+        process_name: None,
+    }
+}
+
+fn get_connections() -> Vec<ConnectionInfo> {
+    let mut connections = Vec::new();
+
+    unsafe {
+        if let Some(buffer) = ip_helper_table(|ptr, size| {
+            GetExtendedTcpTable(Some(ptr), size, false, AF_INET.0 as u32, TCP_TABLE_OWNER_PID_ALL, 0)
+        }) {
+            let table = &*(buffer.as_ptr() as *const MIB_TCPTABLE_OWNER_PID);
+            let rows = std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize);
+            for row in rows {
+                connections.push(connection(
+                    "TCP",
+                    (Ipv4Addr::from(row.dwLocalAddr.to_ne_bytes()).to_string(), port(row.dwLocalPort)),
+                    Some((Ipv4Addr::from(row.dwRemoteAddr.to_ne_bytes()).to_string(), port(row.dwRemotePort))),
+                    tcp_state_name(row.dwState),
+                    row.dwOwningPid,
+                ));
+            }
+        }
+
+        if let Some(buffer) = ip_helper_table(|ptr, size| {
+            GetExtendedTcpTable(Some(ptr), size, false, AF_INET6.0 as u32, TCP_TABLE_OWNER_PID_ALL, 0)
+        }) {
+            let table = &*(buffer.as_ptr() as *const MIB_TCP6TABLE_OWNER_PID);
+            let rows = std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize);
+            for row in rows {
+                connections.push(connection(
+                    "TCPv6",
+                    (Ipv6Addr::from(row.ucLocalAddr).to_string(), port(row.dwLocalPort)),
+                    Some((Ipv6Addr::from(row.ucRemoteAddr).to_string(), port(row.dwRemotePort))),
+                    tcp_state_name(row.dwState),
+                    row.dwOwningPid,
+                ));
+            }
+        }
+
+        if let Some(buffer) = ip_helper_table(|ptr, size| {
+            GetExtendedUdpTable(Some(ptr), size, false, AF_INET.0 as u32, UDP_TABLE_OWNER_PID, 0)
+        }) {
+            let table = &*(buffer.as_ptr() as *const MIB_UDPTABLE_OWNER_PID);
+            let rows = std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize);
+            for row in rows {
+                connections.push(connection(
+                    "UDP",
+                    (Ipv4Addr::from(row.dwLocalAddr.to_ne_bytes()).to_string(), port(row.dwLocalPort)),
+                    None,
+                    "",
+                    row.dwOwningPid,
+                ));
+            }
+        }
+
+        if let Some(buffer) = ip_helper_table(|ptr, size| {
+            GetExtendedUdpTable(Some(ptr), size, false, AF_INET6.0 as u32, UDP_TABLE_OWNER_PID, 0)
+        }) {
+            let table = &*(buffer.as_ptr() as *const MIB_UDP6TABLE_OWNER_PID);
+            let rows = std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize);
+            for row in rows {
+                connections.push(connection(
+                    "UDPv6",
+                    (Ipv6Addr::from(row.ucLocalAddr).to_string(), port(row.dwLocalPort)),
+                    None,
+                    "",
+                    row.dwOwningPid,
+                ));
+            }
+        }
+    }
+
+    connections
+}
+
+fn get_services() -> Vec<ServiceInfo> {
+    use std::process::Command;
+    
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            r#"Get-CimInstance -ClassName Win32_Service | Select-Object @{N='name';E={$_.Name}}, @{N='pid';E={$_.ProcessId}}, @{N='description';E={$_.DisplayName}}, @{N='status';E={$_.State}} | ConvertTo-Json -Compress"#
+        ])
+        .output();
+    
+    if let Ok(output) = output {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if let Ok(services) = serde_json::from_str::<Vec<ServiceInfo>>(&stdout) {
+            return services;
+        } else if let Ok(service) = serde_json::from_str::<ServiceInfo>(&stdout) {
+            return vec![service];
+        }
+    }
+    
+    Vec::new()
+}
+
+fn manage_service(name: &str, action: &str) -> bool {
+    use std::process::Command;
+    
+    let _cmd = match action {
+        "start" => "start",
+        "stop" => "stop",
+        "restart" => "restart",
+        _ => return false,
+    };
+    
+    let shell_cmd = match action {
+        "restart" => format!("Restart-Service -Name '{}' -Force", name),
+        "open_msc" => "services.msc".to_string(),
+        _ => format!("{}-Service -Name '{}' -Force", action.to_uppercase(), name),
+    };
+
+    let output = if action == "open_msc" {
+        Command::new("cmd")
+            .args(["/c", "start", "services.msc"])
+            .output()
+    } else {
+        Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                &shell_cmd
+            ])
+            .output()
+    };
+    
+    output.is_ok() && output.unwrap().status.success()
+}
+
+// Undocumented but stable ntdll exports; they suspend or resume every thread of the
+// process in one call, which is what Resource Monitor's "Suspend process" does.
+#[link(name = "ntdll")]
+extern "system" {
+    fn NtSuspendProcess(handle: HANDLE) -> NTSTATUS;
+    fn NtResumeProcess(handle: HANDLE) -> NTSTATUS;
+    fn NtQueryInformationThread(
+        handle: HANDLE,
+        class: u32,
+        information: *mut std::ffi::c_void,
+        length: u32,
+        return_length: *mut u32,
+    ) -> NTSTATUS;
+    fn NtQuerySystemInformation(
+        class: u32,
+        information: *mut std::ffi::c_void,
+        length: u32,
+        return_length: *mut u32,
+    ) -> NTSTATUS;
+    fn NtQueryInformationProcess(
+        handle: HANDLE,
+        class: u32,
+        information: *mut std::ffi::c_void,
+        length: u32,
+        return_length: *mut u32,
+    ) -> NTSTATUS;
+    fn NtQueryObject(
+        handle: HANDLE,
+        class: u32,
+        information: *mut std::ffi::c_void,
+        length: u32,
+        return_length: *mut u32,
+    ) -> NTSTATUS;
+}
+
+fn with_process_handle(pid: u32, f: impl FnOnce(HANDLE) -> NTSTATUS) -> bool {
+    unsafe {
+        let Ok(handle) = OpenProcess(PROCESS_SUSPEND_RESUME, false, pid) else {
+            return false;
+        };
+        let status = f(handle);
+        let _ = CloseHandle(handle);
+        status.is_ok()
+    }
+}
+
+const PRIORITY_CLASS_FLAGS: [(&str, PROCESS_CREATION_FLAGS); 6] = [
+    ("idle", IDLE_PRIORITY_CLASS),
+    ("below_normal", BELOW_NORMAL_PRIORITY_CLASS),
+    ("normal", NORMAL_PRIORITY_CLASS),
+    ("above_normal", ABOVE_NORMAL_PRIORITY_CLASS),
+    ("high", HIGH_PRIORITY_CLASS),
+    ("realtime", REALTIME_PRIORITY_CLASS),
+];
+
+// Windows has one priority class per process and no separate scheduling policy; I/O
+// priority follows the class, so it is reported as "none" like an untouched Linux process.
+fn get_process_scheduling(pid: u32) -> Option<ProcessScheduling> {
+    let class = unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let class = GetPriorityClass(handle);
+        let _ = CloseHandle(handle);
+        class
+    };
+    let (name, _) = PRIORITY_CLASS_FLAGS.iter().find(|(_, flag)| flag.0 == class)?;
+    let nice = priority_class_nice(name).unwrap_or(0);
+    Some(ProcessScheduling {
+        nice,
+        priority_class: name.to_string(),
+        policy: if *name == "realtime" { "rr" } else { "other" }.to_string(),
+        rt_priority: 0,
+        io_class: "none".to_string(),
+        io_priority: 0,
+    })
+}
+
+fn set_process_scheduling(pid: u32, update: &SchedulingUpdate) -> bool {
+    let class = match (&update.priority_class, update.nice, update.policy.as_deref()) {
+        (Some(class), _, _) => class.as_str(),
+        (None, _, Some("fifo" | "rr")) => "realtime",
+        (None, Some(nice), _) => nice_priority_class(nice),
+        (None, None, Some("idle")) => "idle",
+        // I/O priority and the remaining policies have no Windows equivalent.
+        _ => return false,
+    };
+    let Some((_, flag)) = PRIORITY_CLASS_FLAGS.iter().find(|(name, _)| *name == class) else {
+        return false;
+    };
+    unsafe {
+        let Ok(handle) = OpenProcess(PROCESS_SET_INFORMATION, false, pid) else {
+            return false;
+        };
+        let result = SetPriorityClass(handle, *flag);
+        let _ = CloseHandle(handle);
+        result.is_ok()
+    }
+}
+
+// Affinity masks only cover processor group 0, i.e. the first 64 logical processors,
+// which is also all the classic affinity APIs below can address.
+fn mask_to_cpus(mask: usize) -> Vec<u32> {
+    (0..usize::BITS).filter(|bit| mask & (1 << bit) != 0).collect()
+}
+
+fn cpus_to_mask(cpus: &[u32]) -> Option<usize> {
+    if cpus.is_empty() || cpus.iter().any(|cpu| *cpu >= usize::BITS) {
+        return None;
+    }
+    Some(cpus.iter().fold(0, |mask, cpu| mask | (1 << cpu)))
+}
+
+fn group_zero_mask(groups: &[GROUP_AFFINITY]) -> usize {
+    groups.iter().filter(|g| g.Group == 0).fold(0, |mask, g| mask | g.Mask)
+}
+
+fn get_cpu_topology() -> Vec<LogicalProcessor> {
+    let mut size = 0u32;
+    unsafe {
+        let _ = GetLogicalProcessorInformationEx(RelationAll, None, &mut size);
+    }
+    if size == 0 {
+        return Vec::new();
+    }
+    // u64-backed so the variable-length records are suitably aligned.
+    let mut buffer = vec![0u64; (size as usize).div_ceil(8)];
+    let base = buffer.as_mut_ptr() as *mut u8;
+    if unsafe { GetLogicalProcessorInformationEx(RelationAll, Some(base as *mut SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX), &mut size) }.is_err() {
+        return Vec::new();
+    }
+
+    let mut cores: Vec<usize> = Vec::new();
+    let mut packages: Vec<usize> = Vec::new();
+    let mut numa_nodes: Vec<(u32, usize)> = Vec::new();
+    // (level, mask) of every data or unified cache
+    let mut caches: Vec<(u8, usize)> = Vec::new();
+
+    let mut offset = 0usize;
+    while offset < size as usize {
+        let info = unsafe { &*(base.add(offset) as *const SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX) };
+        if info.Size == 0 {
+            break;
+        }
+        unsafe {
+            match info.Relationship {
+                RelationProcessorCore | RelationProcessorPackage => {
+                    let processor = &info.Anonymous.Processor;
+                    let groups = std::slice::from_raw_parts(processor.GroupMask.as_ptr(), processor.GroupCount as usize);
+                    let mask = group_zero_mask(groups);
+                    if info.Relationship == RelationProcessorCore {
+                        cores.push(mask);
+                    } else {
+                        packages.push(mask);
+                    }
+                }
+                RelationNumaNode => {
+                    let node = &info.Anonymous.NumaNode;
+                    let groups = std::slice::from_raw_parts(&node.Anonymous.GroupMask, node.GroupCount.max(1) as usize);
+                    numa_nodes.push((node.NodeNumber, group_zero_mask(groups)));
+                }
+                RelationCache => {
+                    let cache = &info.Anonymous.Cache;
+                    if cache.Type != CacheInstruction {
+                        let groups = std::slice::from_raw_parts(&cache.Anonymous.GroupMask, cache.GroupCount.max(1) as usize);
+                        caches.push((cache.Level, group_zero_mask(groups)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        offset += info.Size as usize;
+    }
+
+    let mut processors: Vec<LogicalProcessor> = Vec::new();
+    for (core_index, core_mask) in cores.iter().enumerate() {
+        let siblings = mask_to_cpus(*core_mask);
+        for id in &siblings {
+            let bit = 1usize << id;
+            let llc = caches
+                .iter()
+                .filter(|(_, mask)| mask & bit != 0)
+                .max_by_key(|(level, _)| *level)
+                .and_then(|(_, mask)| mask_to_cpus(*mask).into_iter().min());
+            processors.push(LogicalProcessor {
+                id: *id,
+                socket: packages.iter().position(|mask| mask & bit != 0).unwrap_or(0) as u32,
+                core: core_index as u32,
+                siblings: siblings.clone(),
+                numa_node: numa_nodes.iter().find(|(_, mask)| mask & bit != 0).map(|(node, _)| *node),
+                cache_domain: llc,
+            });
+        }
+    }
+    processors.sort_by_key(|p| p.id);
+    processors
+}
+
+fn get_affinity(pid: u32) -> Option<Vec<u32>> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut process_mask = 0usize;
+        let mut system_mask = 0usize;
+        let result = GetProcessAffinityMask(handle, &mut process_mask, &mut system_mask);
+        let _ = CloseHandle(handle);
+        result.ok()?;
+        Some(mask_to_cpus(process_mask))
+    }
+}
+
+fn set_affinity(pid: u32, cpus: &[u32]) -> bool {
+    let Some(mask) = cpus_to_mask(cpus) else {
+        return false;
+    };
+    unsafe {
+        let Ok(handle) = OpenProcess(PROCESS_SET_INFORMATION, false, pid) else {
+            return false;
+        };
+        let result = SetProcessAffinityMask(handle, mask);
+        let _ = CloseHandle(handle);
+        result.is_ok()
+    }
+}
+
+// THREAD_BASIC_INFORMATION as returned by NtQueryInformationThread(ThreadBasicInformation).
+#[repr(C)]
+struct ThreadBasicInformation {
+    exit_status: NTSTATUS,
+    teb_base_address: *mut std::ffi::c_void,
+    unique_process: HANDLE,
+    unique_thread: HANDLE,
+    affinity_mask: usize,
+    priority: i32,
+    base_priority: i32,
+}
+
+fn get_thread_affinity(tid: u32) -> Option<Vec<u32>> {
+    unsafe {
+        let handle = OpenThread(THREAD_QUERY_LIMITED_INFORMATION, false, tid).ok()?;
+        let mut info: ThreadBasicInformation = std::mem::zeroed();
+        let status = NtQueryInformationThread(
+            handle,
+            0,
+            &mut info as *mut ThreadBasicInformation as *mut std::ffi::c_void,
+            std::mem::size_of::<ThreadBasicInformation>() as u32,
+            std::ptr::null_mut(),
+        );
+        let _ = CloseHandle(handle);
+        status.is_ok().then(|| mask_to_cpus(info.affinity_mask))
+    }
+}
+
+fn set_thread_affinity(tid: u32, cpus: &[u32]) -> bool {
+    let Some(mask) = cpus_to_mask(cpus) else {
+        return false;
+    };
+    unsafe {
+        let Ok(handle) = OpenThread(THREAD_SET_LIMITED_INFORMATION | THREAD_QUERY_LIMITED_INFORMATION, false, tid) else {
+            return false;
+        };
+        let previous = SetThreadAffinityMask(handle, mask);
+        let _ = CloseHandle(handle);
+        previous != 0
+    }
+}
+
+pub struct EfficiencyRestore {
+    priority_class: PROCESS_CREATION_FLAGS,
+}
+
+// A control mask of EXECUTION_SPEED with the same state bit turns EcoQoS on; clearing
+// both masks hands the decision back to the system, which is the default.
+fn set_power_throttling(handle: HANDLE, enabled: bool) -> bool {
+    let mask = if enabled { PROCESS_POWER_THROTTLING_EXECUTION_SPEED } else { 0 };
+    let state = PROCESS_POWER_THROTTLING_STATE {
+        Version: PROCESS_POWER_THROTTLING_CURRENT_VERSION,
+        ControlMask: mask,
+        StateMask: mask,
+    };
+    unsafe {
+        SetProcessInformation(
+            handle,
+            ProcessPowerThrottling,
+            &state as *const PROCESS_POWER_THROTTLING_STATE as *const std::ffi::c_void,
+            std::mem::size_of::<PROCESS_POWER_THROTTLING_STATE>() as u32,
+        )
+        .is_ok()
+    }
+}
+
+// Efficiency mode in Task Manager is EcoQoS plus the idle priority class.
+fn enter_efficiency_mode(pid: u32) -> Option<EfficiencyRestore> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_SET_INFORMATION, false, pid).ok()?;
+        let previous = GetPriorityClass(handle);
+        let ok = previous != 0 && set_power_throttling(handle, true) && SetPriorityClass(handle, IDLE_PRIORITY_CLASS).is_ok();
+        let _ = CloseHandle(handle);
+        ok.then_some(EfficiencyRestore {
+            priority_class: PROCESS_CREATION_FLAGS(previous),
+        })
+    }
+}
+
+fn leave_efficiency_mode(pid: u32, restore: &EfficiencyRestore) -> bool {
+    unsafe {
+        let Ok(handle) = OpenProcess(PROCESS_SET_INFORMATION, false, pid) else {
+            return false;
+        };
+        let ok = set_power_throttling(handle, false) && SetPriorityClass(handle, restore.priority_class).is_ok();
+        let _ = CloseHandle(handle);
+        ok
+    }
+}
+
+pub struct ResourceGroup {
+    job: HANDLE,
+}
+
+fn set_job_information<T>(job: HANDLE, class: JOBOBJECTINFOCLASS, info: &T) -> bool {
+    unsafe {
+        SetInformationJobObject(job, class, info as *const T as *const std::ffi::c_void, std::mem::size_of::<T>() as u32)
+            .is_ok()
+    }
+}
+
+fn query_job_information<T>(job: HANDLE, class: JOBOBJECTINFOCLASS, info: &mut T) -> bool {
+    unsafe {
+        QueryInformationJobObject(job, class, info as *mut T as *mut std::ffi::c_void, std::mem::size_of::<T>() as u32, None)
+            .is_ok()
+    }
+}
+
+// Job objects have no soft memory limit, so memory_high is ignored, and I/O rate
+// control needs a different API that only works on server SKUs, so the I/O limits
+// are ignored too.
+fn apply_resource_limits(group: &ResourceGroup, limits: &ResourceLimits) -> bool {
+    let mut extended = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+    if let Some(memory_max) = limits.memory_max {
+        extended.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_JOB_MEMORY;
+        extended.JobMemoryLimit = memory_max as usize;
+    } else {
+        extended.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT(0);
+    }
+    let mut ok = set_job_information(group.job, JobObjectExtendedLimitInformation, &extended);
+
+    let mut cpu = JOBOBJECT_CPU_RATE_CONTROL_INFORMATION::default();
+    if let Some(percent) = limits.cpu_max_percent {
+        // CpuRate is in hundredths of a percent of the whole machine.
+        let processors = unsafe { GetActiveProcessorCount(ALL_PROCESSOR_GROUPS) }.max(1) as f64;
+        cpu.ControlFlags = JOB_OBJECT_CPU_RATE_CONTROL_ENABLE | JOB_OBJECT_CPU_RATE_CONTROL_HARD_CAP;
+        cpu.Anonymous.CpuRate = ((percent * 100.0 / processors) as u32).clamp(1, 10_000);
+    } else {
+        cpu.ControlFlags = JOB_OBJECT_CPU_RATE_CONTROL(0);
+    }
+    ok &= set_job_information(group.job, JobObjectCpuRateControlInformation, &cpu);
+    ok
+}
+
+fn create_resource_group(pids: &[u32], limits: &ResourceLimits) -> Option<ResourceGroup> {
+    let job = unsafe { CreateJobObjectW(None, PCWSTR::null()) }.ok()?;
+    let group = ResourceGroup { job };
+    if !apply_resource_limits(&group, limits) {
+        release_resource_group(group);
+        return None;
+    }
+
+    let mut assigned = 0;
+    for pid in pids {
+        unsafe {
+            let Ok(process) = OpenProcess(PROCESS_SET_QUOTA | PROCESS_TERMINATE, false, *pid) else {
+                continue;
+            };
+            if AssignProcessToJobObject(group.job, process).is_ok() {
+                assigned += 1;
+            }
+            let _ = CloseHandle(process);
+        }
+    }
+    if assigned == 0 {
+        release_resource_group(group);
+        return None;
+    }
+    Some(group)
+}
+
+fn job_pids(job: HANDLE) -> Vec<u32> {
+    // Room for 1024 ids after the two count fields.
+    let mut buffer = vec![0usize; 1024 + 2];
+    let ok = unsafe {
+        QueryInformationJobObject(
+            job,
+            JobObjectBasicProcessIdList,
+            buffer.as_mut_ptr() as *mut std::ffi::c_void,
+            (buffer.len() * std::mem::size_of::<usize>()) as u32,
+            None,
+        )
+        .is_ok()
+    };
+    if !ok {
+        return Vec::new();
+    }
+    let list = unsafe { &*(buffer.as_ptr() as *const JOBOBJECT_BASIC_PROCESS_ID_LIST) };
+    let ids = unsafe { std::slice::from_raw_parts(list.ProcessIdList.as_ptr(), list.NumberOfProcessIdsInList as usize) };
+    ids.iter().map(|id| *id as u32).collect()
+}
+
+// The job memory limit counts committed private memory, so that is what is summed here.
+fn committed_memory(pid: u32) -> u64 {
+    unsafe {
+        let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+            return 0;
+        };
+        let mut counters = PROCESS_MEMORY_COUNTERS::default();
+        let ok = K32GetProcessMemoryInfo(process, &mut counters, std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32)
+            .as_bool();
+        let _ = CloseHandle(process);
+        if ok { counters.PagefileUsage as u64 } else { 0 }
+    }
+}
+
+// Job objects only report limit violations through a completion port, so the event
+// counters stay at zero on Windows.
+fn resource_usage(group: &ResourceGroup) -> ResourceUsage {
+    let pids = job_pids(group.job);
+    let mut extended = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+    let peak = query_job_information(group.job, JobObjectExtendedLimitInformation, &mut extended)
+        .then_some(extended.PeakJobMemoryUsed as u64);
+    let mut accounting = JOBOBJECT_BASIC_ACCOUNTING_INFORMATION::default();
+    query_job_information(group.job, JobObjectBasicAccountingInformation, &mut accounting);
+
+    ResourceUsage {
+        memory_current: pids.iter().map(|pid| committed_memory(*pid)).sum(),
+        pids,
+        memory_peak: peak,
+        // Accounting times are in 100ns units.
+        cpu_usage_usec: ((accounting.TotalUserTime + accounting.TotalKernelTime) / 10) as u64,
+        cpu_nr_throttled: 0,
+        cpu_throttled_usec: 0,
+        memory_high_events: 0,
+        memory_max_events: 0,
+        oom_events: 0,
+        oom_kill_events: 0,
+    }
+}
+
+// A process cannot leave a job object, so releasing lifts every limit and closes the
+// job; the processes carry on as if they had never been limited.
+fn release_resource_group(group: ResourceGroup) -> bool {
+    let lifted = apply_resource_limits(&group, &ResourceLimits::default());
+    unsafe {
+        let _ = CloseHandle(group.job);
+    }
+    lifted
+}
+
+fn machine_name(machine: IMAGE_FILE_MACHINE) -> Option<&'static str> {
+    match machine {
+        IMAGE_FILE_MACHINE_I386 => Some("x86"),
+        IMAGE_FILE_MACHINE_AMD64 => Some("x86_64"),
+        IMAGE_FILE_MACHINE_ARMNT => Some("arm"),
+        IMAGE_FILE_MACHINE_ARM64 => Some("aarch64"),
+        _ => None,
+    }
+}
+
+fn process_identity(pid: u32) -> ProcessIdentity {
+    let mut session = 0u32;
+    let session_id = unsafe { ProcessIdToSessionId(pid, &mut session) }.is_ok().then_some(session);
+
+    let Ok(handle) = (unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }) else {
+        return ProcessIdentity {
+            session_id,
+            ..Default::default()
+        };
+    };
+
+    // The process machine is UNKNOWN unless the process runs under WOW64, in which
+    // case it is the emulated architecture.
+    let mut process_machine = IMAGE_FILE_MACHINE_UNKNOWN;
+    let mut native_machine = IMAGE_FILE_MACHINE_UNKNOWN;
+    let architecture = unsafe { IsWow64Process2(handle, &mut process_machine, Some(&mut native_machine)) }
+        .ok()
+        .and_then(|_| {
+            machine_name(if process_machine == IMAGE_FILE_MACHINE_UNKNOWN { native_machine } else { process_machine })
+        })
+        .map(str::to_string);
+
+    let elevated = unsafe {
+        let mut token = HANDLE::default();
+        if OpenProcessToken(handle, TOKEN_QUERY, &mut token).is_ok() {
+            let mut elevation = TOKEN_ELEVATION::default();
+            let mut length = 0u32;
+            let result = GetTokenInformation(
+                token,
+                TokenElevation,
+                Some(&mut elevation as *mut TOKEN_ELEVATION as *mut std::ffi::c_void),
+                std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+                &mut length,
+            );
+            let _ = CloseHandle(token);
+            result.ok().map(|_| elevation.TokenIsElevated != 0)
+        } else {
+            None
+        }
+    };
+
+    unsafe {
+        let _ = CloseHandle(handle);
+    }
+
+    ProcessIdentity {
+        architecture,
+        elevated,
+        session_id,
+    }
+}
+
+// SYSTEM_PROCESS_INFORMATION as returned by NtQuerySystemInformation(SystemProcessInformation);
+// each entry is followed by one SystemThreadInformation per thread.
+#[repr(C)]
+#[allow(dead_code)]
+struct SystemProcessInformation {
+    next_entry_offset: u32,
+    number_of_threads: u32,
+    working_set_private_size: i64,
+    hard_fault_count: u32,
+    number_of_threads_high_watermark: u32,
+    cycle_time: u64,
+    create_time: i64,
+    user_time: i64,
+    kernel_time: i64,
+    image_name_length: u16,
+    image_name_maximum_length: u16,
+    image_name_buffer: *const u16,
+    base_priority: i32,
+    unique_process_id: usize,
+    inherited_from_unique_process_id: usize,
+    handle_count: u32,
+    session_id: u32,
+    unique_process_key: usize,
+    peak_virtual_size: usize,
+    virtual_size: usize,
+    page_fault_count: u32,
+    peak_working_set_size: usize,
+    working_set_size: usize,
+    quota_peak_paged_pool_usage: usize,
+    quota_paged_pool_usage: usize,
+    quota_peak_non_paged_pool_usage: usize,
+    quota_non_paged_pool_usage: usize,
+    pagefile_usage: usize,
+    peak_pagefile_usage: usize,
+    private_page_count: usize,
+    transfer_counts: [i64; 6],
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct SystemThreadInformation {
+    kernel_time: i64,
+    user_time: i64,
+    create_time: i64,
+    wait_time: u32,
+    start_address: *const std::ffi::c_void,
+    unique_process: HANDLE,
+    unique_thread: HANDLE,
+    priority: i32,
+    base_priority: i32,
+    context_switches: u32,
+    thread_state: u32,
+    wait_reason: u32,
+}
+
+const SYSTEM_PROCESS_INFORMATION_CLASS: u32 = 5;
+const STATUS_INFO_LENGTH_MISMATCH: i32 = 0xC000_0004_u32 as i32;
+
+// KWAIT_REASON, in order.
+const WAIT_REASONS: [&str; 39] = [
+    "Executive", "FreePage", "PageIn", "PoolAllocation", "DelayExecution", "Suspended", "UserRequest",
+    "WrExecutive", "WrFreePage", "WrPageIn", "WrPoolAllocation", "WrDelayExecution", "WrSuspended",
+    "WrUserRequest", "WrEventPair", "WrQueue", "WrLpcReceive", "WrLpcReply", "WrVirtualMemory", "WrPageOut",
+    "WrRendezvous", "WrKeyedEvent", "WrTerminated", "WrProcessInSwap", "WrCpuRateControl", "WrCalloutStack",
+    "WrKernel", "WrResource", "WrPushLock", "WrMutex", "WrQuantumEnd", "WrDispatchInt", "WrPreempted",
+    "WrYieldExecution", "WrFastMutex", "WrGuardedMutex", "WrRundown", "WrAlertByThreadId", "WrDeferredPreempt",
+];
+
+// The buffer is u64s so the entries inside it are suitably aligned.
+fn query_system_information(class: u32) -> Option<Vec<u64>> {
+    let mut buffer: Vec<u64> = vec![0; 1 << 16];
+    loop {
+        let mut needed = 0u32;
+        let status = unsafe {
+            NtQuerySystemInformation(
+                class,
+                buffer.as_mut_ptr() as *mut std::ffi::c_void,
+                (buffer.len() * 8) as u32,
+                &mut needed,
+            )
+        };
+        if status.0 != STATUS_INFO_LENGTH_MISMATCH {
+            return status.is_ok().then_some(buffer);
+        }
+        // Processes can start between the two calls, so leave some headroom.
+        buffer.resize((needed as usize / 8 + 4096).max(buffer.len() * 2), 0);
+    }
+}
+
+fn system_process_information() -> Option<Vec<u64>> {
+    query_system_information(SYSTEM_PROCESS_INFORMATION_CLASS)
+}
+
+// KTHREAD_STATE mapped onto the process status vocabulary.
+fn thread_state(state: u32, wait_reason: u32) -> &'static str {
+    match state {
+        1 | 2 | 3 | 7 => "running",
+        4 => "zombie",
+        // Waiting; Suspended and WrSuspended are what SuspendThread leaves behind.
+        5 if wait_reason == 5 || wait_reason == 12 => "stopped",
+        _ => "sleeping",
+    }
+}
+
+fn thread_description(tid: u32) -> Option<String> {
+    unsafe {
+        let handle = OpenThread(THREAD_QUERY_LIMITED_INFORMATION, false, tid).ok()?;
+        let description = GetThreadDescription(handle);
+        let _ = CloseHandle(handle);
+        let description = description.ok()?;
+        let name = description.to_string().ok();
+        let _ = LocalFree(HLOCAL(description.0 as *mut std::ffi::c_void));
+        name.filter(|n| !n.is_empty())
+    }
+}
+
+// Every entry of a SystemProcessInformation buffer.
+fn process_entries(buffer: &[u64]) -> Vec<&SystemProcessInformation> {
+    let base = buffer.as_ptr() as *const u8;
+    let mut entries = Vec::new();
+    let mut offset = 0usize;
+    loop {
+        let entry = unsafe { &*(base.add(offset) as *const SystemProcessInformation) };
+        entries.push(entry);
+        if entry.next_entry_offset == 0 {
+            return entries;
+        }
+        offset += entry.next_entry_offset as usize;
+    }
+}
+
+// Threads without a description are named after their process, like an unnamed thread
+// on Linux inherits the process comm.
+fn get_threads(pid: u32) -> Vec<ThreadInfo> {
+    let Some(buffer) = system_process_information() else {
+        return Vec::new();
+    };
+    let Some(process) = process_entries(&buffer).into_iter().find(|p| p.unique_process_id == pid as usize) else {
+        return Vec::new();
+    };
+    let nice = get_process_scheduling(pid).map(|s| s.nice).unwrap_or(0);
+    unsafe {
+        let image_name = if process.image_name_buffer.is_null() {
+            String::new()
+        } else {
+            String::from_utf16_lossy(std::slice::from_raw_parts(
+                process.image_name_buffer,
+                process.image_name_length as usize / 2,
+            ))
+        };
+        let first = (process as *const SystemProcessInformation).add(1) as *const SystemThreadInformation;
+        let mut threads: Vec<ThreadInfo> = std::slice::from_raw_parts(first, process.number_of_threads as usize)
+            .iter()
+            .map(|thread| {
+                let tid = thread.unique_thread.0 as usize as u32;
+                ThreadInfo {
+                    tid,
+                    name: thread_description(tid).unwrap_or_else(|| image_name.clone()),
+                    state: thread_state(thread.thread_state, thread.wait_reason).to_string(),
+                    cpu_usage: 0.0,
+                    // kernel and user time are in 100ns units
+                    cpu_time: ((thread.kernel_time + thread.user_time) / 10_000) as u64,
+                    priority: thread.priority,
+                    nice,
+                    // Windows does not record the processor a thread last ran on.
+                    last_cpu: None,
+                    context_switches: thread.context_switches as u64,
+                    voluntary_switches: None,
+                    involuntary_switches: None,
+                    wait_channel: WAIT_REASONS
+                        .get(thread.wait_reason as usize)
+                        .filter(|_| thread.thread_state == 5)
+                        .map(|r| r.to_string()),
+                }
+            })
+            .collect();
+        threads.sort_by_key(|t| t.tid);
+        threads
+    }
+}
+
+// Windows has no process groups, so this ends the process the thread belongs to.
+fn kill_process_group(tid: u32) -> bool {
+    unsafe {
+        let Ok(thread) = OpenThread(THREAD_QUERY_LIMITED_INFORMATION, false, tid) else {
+            return false;
+        };
+        let pid = GetProcessIdOfThread(thread);
+        let _ = CloseHandle(thread);
+        if pid == 0 || pid == std::process::id() {
+            return false;
+        }
+        let Ok(handle) = OpenProcess(PROCESS_TERMINATE, false, pid) else {
+            return false;
+        };
+        let result = TerminateProcess(handle, 1);
+        let _ = CloseHandle(handle);
+        result.is_ok()
+    }
+}
+
+// Loaded images are locked against deletion and overwriting, so a module can only be
+// missing (renamed or deleted through a rename) and is never reported as replaced.
+fn get_process_modules(pid: u32) -> Vec<ModuleInfo> {
+    unsafe {
+        let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_VM_READ, false, pid) else {
+            return Vec::new();
+        };
+
+        let mut handles: Vec<HMODULE> = vec![HMODULE::default(); 256];
+        loop {
+            let mut needed = 0u32;
+            let size = (handles.len() * std::mem::size_of::<HMODULE>()) as u32;
+            if !K32EnumProcessModulesEx(process, handles.as_mut_ptr(), size, &mut needed, LIST_MODULES_ALL).as_bool() {
+                handles.clear();
+                break;
+            }
+            let count = needed as usize / std::mem::size_of::<HMODULE>();
+            if count <= handles.len() {
+                handles.truncate(count);
+                break;
+            }
+            handles.resize(count, HMODULE::default());
+        }
+
+        let mut modules: Vec<ModuleInfo> = handles
+            .into_iter()
+            .filter_map(|module| {
+                let mut name = [0u16; 1024];
+                let len = K32GetModuleFileNameExW(process, module, &mut name) as usize;
+                if len == 0 {
+                    return None;
+                }
+                let mut info = MODULEINFO::default();
+                if !K32GetModuleInformation(process, module, &mut info, std::mem::size_of::<MODULEINFO>() as u32).as_bool() {
+                    return None;
+                }
+                let path = String::from_utf16_lossy(&name[..len]);
+                Some(ModuleInfo {
+                    deleted: !std::path::Path::new(&path).exists(),
+                    path,
+                    base_address: info.lpBaseOfDll as u64,
+                    size: info.SizeOfImage as u64,
+                    replaced: false,
+                })
+            })
+            .collect();
+        let _ = CloseHandle(process);
+        modules.sort_by_key(|m| m.base_address);
+        modules
+    }
+}
+
+fn protection_string(protect: PAGE_PROTECTION_FLAGS, private: bool) -> String {
+    // The low byte is the access; guard, no-cache and write-combine live above it.
+    let access = PAGE_PROTECTION_FLAGS(protect.0 & 0xFF);
+    let (rwx, copy_on_write) = match access {
+        PAGE_READONLY => ("r--", false),
+        PAGE_READWRITE => ("rw-", false),
+        PAGE_WRITECOPY => ("rw-", true),
+        PAGE_EXECUTE => ("--x", false),
+        PAGE_EXECUTE_READ => ("r-x", false),
+        PAGE_EXECUTE_READWRITE => ("rwx", false),
+        PAGE_EXECUTE_WRITECOPY => ("rwx", true),
+        _ => ("---", false),
+    };
+    format!("{}{}", rwx, if private || copy_on_write { 'p' } else { 's' })
+}
+
+// Committed regions only; reserved address space has no backing to report. Paths of
+// mapped files are NT device paths such as \Device\HarddiskVolume3\Windows\...
+fn get_process_memory_map(pid: u32) -> Vec<MemoryRegion> {
+    unsafe {
+        let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_VM_READ, false, pid) else {
+            return Vec::new();
+        };
+
+        let mut regions = Vec::new();
+        let mut address = 0usize;
+        loop {
+            let mut info = MEMORY_BASIC_INFORMATION::default();
+            let written = VirtualQueryEx(
+                process,
+                Some(address as *const std::ffi::c_void),
+                &mut info,
+                std::mem::size_of::<MEMORY_BASIC_INFORMATION>(),
+            );
+            if written == 0 || info.RegionSize == 0 {
+                break;
+            }
+            let start = info.BaseAddress as usize;
+            if info.State == MEM_COMMIT {
+                let private = info.Type == MEM_PRIVATE;
+                let path = if private {
+                    None
+                } else {
+                    let mut name = [0u16; 1024];
+                    let len = K32GetMappedFileNameW(process, info.BaseAddress, &mut name) as usize;
+                    (len > 0).then(|| String::from_utf16_lossy(&name[..len]))
+                };
+                regions.push(MemoryRegion {
+                    start: start as u64,
+                    end: (start + info.RegionSize) as u64,
+                    permissions: protection_string(info.Protect, private),
+                    offset: (start - info.AllocationBase as usize) as u64,
+                    backing: if path.is_some() { "file" } else { "anonymous" }.to_string(),
+                    path,
+                    rss: None,
+                    pss: None,
+                    swap: None,
+                });
+            }
+            match start.checked_add(info.RegionSize) {
+                Some(next) => address = next,
+                None => break,
+            }
+        }
+        let _ = CloseHandle(process);
+        regions
+    }
+}
+
+// The working set split comes from the same snapshot as the thread list. Windows has no
+// proportional set size, and paged-out memory is part of the commit charge rather than
+// a separate figure.
+fn get_memory_breakdowns() -> HashMap<u32, MemoryBreakdown> {
+    let Some(buffer) = system_process_information() else {
+        return HashMap::new();
+    };
+    process_entries(&buffer)
+        .into_iter()
+        .map(|process| {
+            let private = process.working_set_private_size.max(0) as u64;
+            (
+                process.unique_process_id as u32,
+                MemoryBreakdown {
+                    private,
+                    shared: (process.working_set_size as u64).saturating_sub(private),
+                    pss: None,
+                    swap: None,
+                    virtual_size: process.virtual_size as u64,
+                    commit: process.private_page_count as u64,
+                },
+            )
+        })
+        .collect()
+}
+
+// SYSTEM_HANDLE_TABLE_ENTRY_INFO_EX from NtQuerySystemInformation(SystemExtendedHandleInformation);
+// the table starts with a handle count and a reserved word.
+#[repr(C)]
+#[allow(dead_code)]
+struct SystemHandleEntry {
+    object: *const std::ffi::c_void,
+    unique_process_id: usize,
+    handle_value: usize,
+    granted_access: u32,
+    creator_back_trace_index: u16,
+    object_type_index: u16,
+    handle_attributes: u32,
+    reserved: u32,
+}
+
+#[repr(C)]
+#[allow(dead_code)]
+struct UnicodeString {
+    length: u16,
+    maximum_length: u16,
+    buffer: *const u16,
+}
+
+const SYSTEM_EXTENDED_HANDLE_INFORMATION_CLASS: u32 = 64;
+const OBJECT_TYPE_INFORMATION_CLASS: u32 = 2;
+
+// Synchronous named pipes opened with this access can block GetFileType and friends
+// until the other end writes; Process Explorer skips them for the same reason.
+const HANGING_PIPE_ACCESS: u32 = 0x0012_019F;
+
+fn handle_entries(buffer: &[u64]) -> &[SystemHandleEntry] {
+    let count = buffer[0] as usize;
+    unsafe { std::slice::from_raw_parts(buffer[2..].as_ptr() as *const SystemHandleEntry, count) }
+}
+
+// Type indexes are per boot, so the name only has to be looked up once per index.
+fn is_file_object(handle: HANDLE, type_index: u16, types: &mut HashMap<u16, bool>) -> bool {
+    *types.entry(type_index).or_insert_with(|| unsafe {
+        let mut buffer: Vec<u64> = vec![0; 128];
+        let status = NtQueryObject(
+            handle,
+            OBJECT_TYPE_INFORMATION_CLASS,
+            buffer.as_mut_ptr() as *mut std::ffi::c_void,
+            (buffer.len() * 8) as u32,
+            std::ptr::null_mut(),
+        );
+        let name = &*(buffer.as_ptr() as *const UnicodeString);
+        status.is_ok()
+            && !name.buffer.is_null()
+            && String::from_utf16_lossy(std::slice::from_raw_parts(name.buffer, name.length as usize / 2)) == "File"
+    })
+}
+
+// "\\?\C:\..." and "\\?\UNC\server\..." back to the paths Explorer shows.
+fn final_path(handle: HANDLE) -> Option<String> {
+    let mut name = [0u16; 1024];
+    let len = unsafe { GetFinalPathNameByHandleW(handle, &mut name, FILE_NAME_NORMALIZED) } as usize;
+    if len == 0 || len >= name.len() {
+        return None;
+    }
+    let path = String::from_utf16_lossy(&name[..len]);
+    Some(match path.strip_prefix(r"\\?\UNC\") {
+        Some(rest) => format!(r"\\{}", rest),
+        None => path.strip_prefix(r"\\?\").map(str::to_string).unwrap_or(path),
+    })
+}
+
+fn access_flags(access: u32) -> Vec<String> {
+    const FILE_READ_DATA: u32 = 0x1;
+    const FILE_WRITE_DATA: u32 = 0x2;
+    const FILE_APPEND_DATA: u32 = 0x4;
+    const DELETE: u32 = 0x1_0000;
+    [(FILE_READ_DATA, "read"), (FILE_WRITE_DATA, "write"), (FILE_APPEND_DATA, "append"), (DELETE, "delete")]
+        .into_iter()
+        .filter(|(flag, _)| access & flag != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+// Handles are duplicated into this process to be inspected, which needs PROCESS_DUP_HANDLE
+// on the owner; protected processes and other users' processes without elevation are skipped.
+fn open_files(only_pid: Option<u32>) -> Vec<(u32, OpenFile)> {
+    let Some(buffer) = query_system_information(SYSTEM_EXTENDED_HANDLE_INFORMATION_CLASS) else {
+        return Vec::new();
+    };
+    let mut processes: HashMap<u32, Option<HANDLE>> = HashMap::new();
+    let mut types: HashMap<u16, bool> = HashMap::new();
+    let mut files = Vec::new();
+
+    unsafe {
+        let current = GetCurrentProcess();
+        for entry in handle_entries(&buffer) {
+            let pid = entry.unique_process_id as u32;
+            if only_pid.is_some_and(|p| p != pid) || entry.granted_access == HANGING_PIPE_ACCESS {
+                continue;
+            }
+            let Some(process) = *processes.entry(pid).or_insert_with(|| OpenProcess(PROCESS_DUP_HANDLE, false, pid).ok())
+            else {
+                continue;
+            };
+            let mut handle = HANDLE::default();
+            if DuplicateHandle(process, HANDLE(entry.handle_value as isize), current, &mut handle, 0, false, DUPLICATE_SAME_ACCESS)
+                .is_err()
+            {
+                continue;
+            }
+
+            if is_file_object(handle, entry.object_type_index, &mut types) {
+                let file_type = GetFileType(handle);
+                let (kind, path, position) = if file_type == FILE_TYPE_DISK {
+                    let path = final_path(handle);
+                    let is_dir = path.as_ref().is_some_and(|p| std::path::Path::new(p).is_dir());
+                    let mut position = 0i64;
+                    let position = (!is_dir && SetFilePointerEx(handle, 0, Some(&mut position as *mut i64), FILE_CURRENT).is_ok())
+                        .then_some(position as u64);
+                    (if is_dir { "directory" } else { "file" }, path, position)
+                } else if file_type == FILE_TYPE_PIPE {
+                    ("pipe", None, None)
+                } else if file_type == FILE_TYPE_CHAR {
+                    ("device", None, None)
+                } else {
+                    ("file", None, None)
+                };
+                files.push((
+                    pid,
+                    OpenFile {
+                        fd: Some(entry.handle_value as u64),
+                        kind: kind.to_string(),
+                        path: path.unwrap_or_default(),
+                        flags: access_flags(entry.granted_access),
+                        position,
+                    },
+                ));
+            }
+            let _ = CloseHandle(handle);
+        }
+        for process in processes.into_values().flatten() {
+            let _ = CloseHandle(process);
+        }
+    }
+    files
+}
+
+fn get_process_open_files(pid: u32) -> Vec<OpenFile> {
+    open_files(Some(pid)).into_iter().map(|(_, file)| file).collect()
+}
+
+fn matches_prefix(path: &str, prefix: &str) -> bool {
+    let path = path.to_lowercase();
+    let prefix = prefix.replace('/', r"\").to_lowercase();
+    let prefix = prefix.trim_end_matches('\\');
+    if path.is_empty() || prefix.is_empty() {
+        return false;
+    }
+    path == prefix || path.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('\\'))
+}
+
+// Loaded images keep their files busy as well, so they are reported next to handles.
+// Windows has no way to enumerate byte-range locks held by other processes, so the lock
+// list stays empty; a sharing violation on open is the only sign of one.
+fn find_file_holders(prefix: &str) -> FileHolders {
+    let mut holders: Vec<FileHolder> = open_files(None)
+        .into_iter()
+        .filter(|(_, file)| matches_prefix(&file.path, prefix))
+        .map(|(pid, file)| FileHolder { pid, process_name: None, file })
+        .collect();
+
+    if let Some(buffer) = system_process_information() {
+        for process in process_entries(&buffer) {
+            let pid = process.unique_process_id as u32;
+            for module in get_process_modules(pid) {
+                if matches_prefix(&module.path, prefix) {
+                    holders.push(FileHolder {
+                        pid,
+                        process_name: None,
+                        file: OpenFile {
+                            fd: None,
+                            kind: "mapped".to_string(),
+                            path: module.path,
+                            flags: Vec::new(),
+                            position: None,
+                        },
+                    });
+                }
+            }
+        }
+    }
+    holders.sort_by_key(|h| (h.pid, h.file.fd));
+
+    FileHolders { holders, locks: Vec::new() }
+}
+
+// MiniDumpWriteDump does not pause the target by itself, so the process is suspended for
+// the duration as Task Manager does. dbghelp reports no byte counts, so progress is only
+// sent when the dump starts and once the file is complete.
+fn create_process_dump(
+    pid: u32,
+    path: &std::path::Path,
+    options: &DumpOptions,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<u64, String> {
+    use std::os::windows::io::AsRawHandle;
+
+    if pid == std::process::id() {
+        return Err("cannot dump the task manager itself".to_string());
+    }
+    // Without full memory the dump keeps stacks, heaps and writable data and leaves out
+    // images and mapped files, which the debugger loads from disk instead.
+    let contents = if options.exclude_file_backed {
+        [MiniDumpWithPrivateReadWriteMemory, MiniDumpWithDataSegs]
+    } else {
+        [MiniDumpWithFullMemory, MiniDumpWithDataSegs]
+    };
+    let dump_type = contents
+        .into_iter()
+        .chain([MiniDumpWithHandleData, MiniDumpWithThreadInfo, MiniDumpWithUnloadedModules, MiniDumpWithFullMemoryInfo])
+        .fold(MINIDUMP_TYPE(0), |all, flag| MINIDUMP_TYPE(all.0 | flag.0));
+
+    let file = std::fs::File::create(path).map_err(|e| format!("cannot create {}: {}", path.display(), e))?;
+    let result = unsafe {
+        let access = PROCESS_QUERY_INFORMATION | PROCESS_VM_READ | PROCESS_DUP_HANDLE | PROCESS_SUSPEND_RESUME;
+        match OpenProcess(access, false, pid) {
+            Ok(process) => {
+                progress(0, 0);
+                let suspended = NtSuspendProcess(process).is_ok();
+                let result = MiniDumpWriteDump(
+                    process,
+                    pid,
+                    HANDLE(file.as_raw_handle() as isize),
+                    dump_type,
+                    None,
+                    None,
+                    None,
+                );
+                if suspended {
+                    let _ = NtResumeProcess(process);
+                }
+                let _ = CloseHandle(process);
+                result.map_err(|e| e.to_string())
+            }
+            Err(e) => Err(format!("cannot open process {}: {}", pid, e)),
+        }
+    };
+
+    let size = file.metadata().map(|m| m.len());
+    drop(file);
+    match (result, size) {
+        (Ok(()), Ok(size)) => {
+            progress(size, size);
+            Ok(size)
+        }
+        (Err(e), _) => {
+            let _ = std::fs::remove_file(path);
+            Err(e)
+        }
+        (_, Err(e)) => Err(e.to_string()),
+    }
+}
+
+const PROCESS_COMMAND_LINE_INFORMATION_CLASS: u32 = 60;
+const PROCESS_WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+fn image_path(process: HANDLE) -> Option<String> {
+    let mut name = [0u16; 1024];
+    let mut len = name.len() as u32;
+    unsafe { QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, windows::core::PWSTR(name.as_mut_ptr()), &mut len) }
+        .ok()?;
+    Some(String::from_utf16_lossy(&name[..len as usize]))
+}
+
+// ProcessCommandLineInformation returns a UNICODE_STRING pointing just past itself.
+fn process_command_line(process: HANDLE) -> String {
+    let mut buffer: Vec<u64> = vec![0; 512];
+    for _ in 0..2 {
+        let mut needed = 0u32;
+        let status = unsafe {
+            NtQueryInformationProcess(
+                process,
+                PROCESS_COMMAND_LINE_INFORMATION_CLASS,
+                buffer.as_mut_ptr() as *mut std::ffi::c_void,
+                (buffer.len() * 8) as u32,
+                &mut needed,
+            )
+        };
+        if status.is_ok() {
+            let text = unsafe { &*(buffer.as_ptr() as *const UnicodeString) };
+            if text.buffer.is_null() {
+                return String::new();
+            }
+            let chars = unsafe { std::slice::from_raw_parts(text.buffer, text.length as usize / 2) };
+            return String::from_utf16_lossy(chars);
+        }
+        buffer.resize(needed as usize / 8 + 1, 0);
+    }
+    String::new()
+}
+
+fn filetime_ms(time: FILETIME) -> u64 {
+    ((time.dwHighDateTime as u64) << 32 | time.dwLowDateTime as u64) / 10_000
+}
+
+// An open handle keeps the process object, its exit code, times and memory counters
+// around after it exits, and stops the pid from being reused while we watch it.
+fn exit_event(pid: u32, process: Option<HANDLE>) -> ProcessEvent {
+    let mut event = ProcessEvent {
+        id: 0,
+        timestamp: epoch_ms(),
+        kind: "exit".to_string(),
+        pid,
+        parent_pid: None,
+        exe_path: None,
+        command_line: String::new(),
+        exit_code: None,
+        signal: None,
+        run_time: None,
+        peak_memory: None,
+    };
+    let Some(process) = process else {
+        return event;
+    };
+    unsafe {
+        let mut code = 0u32;
+        if GetExitCodeProcess(process, &mut code).is_ok() {
+            event.exit_code = Some(code as i32);
+        }
+        let (mut created, mut exited, mut kernel, mut user) =
+            (FILETIME::default(), FILETIME::default(), FILETIME::default(), FILETIME::default());
+        if GetProcessTimes(process, &mut created, &mut exited, &mut kernel, &mut user).is_ok() {
+            event.run_time = Some(filetime_ms(exited).saturating_sub(filetime_ms(created)));
+        }
+        let mut counters = PROCESS_MEMORY_COUNTERS::default();
+        let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+        if K32GetProcessMemoryInfo(process, &mut counters, size).as_bool() {
+            event.peak_memory = Some(counters.PeakWorkingSetSize as u64);
+        }
+        let _ = CloseHandle(process);
+    }
+    event
+}
+
+fn has_exited(process: HANDLE) -> bool {
+    unsafe { WaitForSingleObject(process, 0) == WAIT_OBJECT_0 }
+}
+
+// Without administrator rights there is no event source for process creation (ETW and
+// WMI traces both need it), so the process list is diffed every PROCESS_WATCH_INTERVAL and
+// processes that live shorter than that can be missed. Windows has no signals; a process
+// that was terminated reports the code passed to TerminateProcess.
+fn watch_processes(emit: &mut dyn FnMut(ProcessEvent)) {
+    let open = |pid: u32| unsafe {
+        OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_SYNCHRONIZE, false, pid).ok()
+    };
+    let mut known: HashMap<u32, Option<HANDLE>> = HashMap::new();
+    // Exited processes stay listed while anything still holds a handle to them; they must
+    // not come back as new ones.
+    let mut ended: HashSet<u32> = HashSet::new();
+    let mut first = true;
+
+    loop {
+        let Some(buffer) = system_process_information() else {
+            std::thread::sleep(PROCESS_WATCH_INTERVAL);
+            continue;
+        };
+        let current: HashMap<u32, u32> = process_entries(&buffer)
+            .into_iter()
+            .map(|p| (p.unique_process_id as u32, p.inherited_from_unique_process_id as u32))
+            .collect();
+        ended.retain(|pid| current.contains_key(pid));
+
+        // Starts first, so a process that came and went since the last look still gets
+        // both events.
+        let mut started: Vec<(u32, u32)> = current
+            .iter()
+            .filter(|(pid, _)| !known.contains_key(pid) && !ended.contains(pid))
+            .map(|(pid, parent_pid)| (*pid, *parent_pid))
+            .collect();
+        started.sort_unstable();
+        for (pid, parent_pid) in started {
+            let process = open(pid);
+            // Everything running when the watcher starts is only tracked, not reported.
+            if !first {
+                emit(ProcessEvent {
+                    id: 0,
+                    timestamp: epoch_ms(),
+                    kind: "start".to_string(),
+                    pid,
+                    parent_pid: (parent_pid != 0).then_some(parent_pid),
+                    exe_path: process.and_then(image_path),
+                    command_line: process.map(process_command_line).unwrap_or_default(),
+                    exit_code: None,
+                    signal: None,
+                    run_time: None,
+                    peak_memory: None,
+                });
+            }
+            known.insert(pid, process);
+        }
+
+        let mut exited: Vec<u32> = known
+            .iter()
+            .filter(|(pid, process)| match process {
+                Some(process) => has_exited(*process),
+                None => !current.contains_key(pid),
+            })
+            .map(|(pid, _)| *pid)
+            .collect();
+        exited.sort_unstable();
+        for pid in exited {
+            let process = known.remove(&pid).flatten();
+            if current.contains_key(&pid) {
+                ended.insert(pid);
+            }
+            emit(exit_event(pid, process));
+        }
+
+        first = false;
+        std::thread::sleep(PROCESS_WATCH_INTERVAL);
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::{HashMap, VecDeque};
+
+// (step in seconds, points kept): 1 s for 10 minutes, 1 min for 24 hours and 15 min for
+// 30 days. Every sample goes into each tier, averaged over the tier's step.
+const TIERS: [(u64, usize); 3] = [(1, 600), (60, 1440), (900, 2880)];
+// Series that stop being sampled (an unmounted disk, a removed interface) are dropped
+// once they have nothing left in any tier.
+const RETENTION_MS: u64 = 900 * 2880 * 1000;
+
+#[derive(serde::Serialize, Clone, Copy)]
+pub struct MetricPoint {
+    // unix time in ms of the start of the step the value averages
+    pub timestamp: u64,
+    pub value: f64,
+}
+
+#[derive(serde::Serialize)]
+pub struct MetricHistory {
+    pub metric_id: String,
+    // seconds between points
+    pub resolution: u64,
+    pub points: Vec<MetricPoint>,
+}
+
+struct Bucket {
+    start: u64,
+    sum: f64,
+    count: u32,
+}
+
+impl Bucket {
+    fn point(&self) -> MetricPoint {
+        MetricPoint { timestamp: self.start, value: self.sum / f64::from(self.count) }
+    }
+}
+
+struct Tier {
+    step_ms: u64,
+    capacity: usize,
+    points: VecDeque<MetricPoint>,
+    open: Option<Bucket>,
+}
+
+impl Tier {
+    fn new((step, capacity): (u64, usize)) -> Self {
+        Self { step_ms: step * 1000, capacity, points: VecDeque::new(), open: None }
+    }
+
+    fn span_ms(&self) -> u64 {
+        self.step_ms * self.capacity as u64
+    }
+
+    fn add(&mut self, timestamp: u64, value: f64) {
+        let start = timestamp - timestamp % self.step_ms;
+        match &mut self.open {
+            Some(bucket) if bucket.start == start => {
+                bucket.sum += value;
+                bucket.count += 1;
+                return;
+            }
+            Some(bucket) if bucket.start > start => return,
+            _ => {}
+        }
+        if let Some(closed) = self.open.replace(Bucket { start, sum: value, count: 1 }) {
+            if self.points.len() == self.capacity {
+                self.points.pop_front();
+            }
+            self.points.push_back(closed.point());
+        }
+    }
+
+    // Closed points plus the step still being filled, oldest first.
+    fn points_since(&self, since: u64) -> impl Iterator<Item = MetricPoint> + '_ {
+        self.points
+            .iter()
+            .copied()
+            .chain(self.open.as_ref().map(Bucket::point))
+            .filter(move |p| p.timestamp >= since)
+    }
+}
+
+struct Series {
+    tiers: [Tier; 3],
+    last_sample: u64,
+}
+
+#[derive(Default)]
+pub struct MetricStore {
+    series: HashMap<String, Series>,
+}
+
+impl MetricStore {
+    pub fn record(&mut self, timestamp: u64, samples: impl IntoIterator<Item = (String, f64)>) {
+        for (metric_id, value) in samples {
+            if !value.is_finite() {
+                continue;
+            }
+            let series = self.series.entry(metric_id).or_insert_with(|| Series {
+                tiers: TIERS.map(Tier::new),
+                last_sample: timestamp,
+            });
+            series.last_sample = timestamp;
+            for tier in &mut series.tiers {
+                tier.add(timestamp, value);
+            }
+        }
+        self.series.retain(|_, series| timestamp.saturating_sub(series.last_sample) <= RETENTION_MS);
+    }
+
+    // The last `range` seconds of a metric from the finest tier that reaches back that far,
+    // averaged further when `resolution` is coarser than that tier's step.
+    pub fn query(&self, metric_id: &str, now: u64, range: u64, resolution: Option<u64>) -> Option<MetricHistory> {
+        let series = self.series.get(metric_id)?;
+        let range_ms = range.saturating_mul(1000);
+        let tier = series
+            .tiers
+            .iter()
+            .find(|tier| tier.span_ms() >= range_ms)
+            .unwrap_or(&series.tiers[TIERS.len() - 1]);
+        let step_ms = resolution
+            .map(|r| r.saturating_mul(1000))
+            .filter(|r| *r > tier.step_ms)
+            .unwrap_or(tier.step_ms);
+        let since = now.saturating_sub(range_ms);
+
+        let mut points: Vec<MetricPoint> = Vec::new();
+        let mut bucket: Option<Bucket> = None;
+        for point in tier.points_since(since) {
+            let start = point.timestamp - point.timestamp % step_ms;
+            match &mut bucket {
+                Some(open) if open.start == start => {
+                    open.sum += point.value;
+                    open.count += 1;
+                }
+                _ => {
+                    points.extend(bucket.replace(Bucket { start, sum: point.value, count: 1 }).map(|b| b.point()));
+                }
+            }
+        }
+        points.extend(bucket.map(|b| b.point()));
+
+        Some(MetricHistory {
+            metric_id: metric_id.to_string(),
+            resolution: step_ms / 1000,
+            points,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the start of a 15 minute step, so every tier's buckets line up with it
+    const BASE: u64 = 1_699_999_200_000;
+
+    fn sample(value: f64) -> [(String, f64); 1] {
+        [("cpu".to_string(), value)]
+    }
+
+    fn timestamps(history: &MetricHistory) -> Vec<u64> {
+        history.points.iter().map(|p| (p.timestamp - BASE) / 1000).collect()
+    }
+
+    #[test]
+    fn samples_in_one_second_are_averaged() {
+        let mut store = MetricStore::default();
+        store.record(BASE, sample(10.0));
+        store.record(BASE + 500, sample(20.0));
+        store.record(BASE + 1000, sample(40.0));
+        let history = store.query("cpu", BASE + 1000, 10, None).unwrap();
+        assert_eq!(history.resolution, 1);
+        assert_eq!(timestamps(&history), [0, 1]);
+        assert_eq!(history.points[0].value, 15.0);
+        assert_eq!(history.points[1].value, 40.0);
+    }
+
+    #[test]
+    fn finest_tier_keeps_its_capacity() {
+        let mut store = MetricStore::default();
+        for second in 0..700 {
+            store.record(BASE + second * 1000, sample(second as f64));
+        }
+        let history = store.query("cpu", BASE + 700_000, 600, None).unwrap();
+        assert_eq!(history.resolution, 1);
+        assert_eq!(history.points.len(), 600);
+        assert_eq!(history.points[0].timestamp, BASE + 100_000);
+        assert_eq!(history.points[599].value, 699.0);
+
+        // Further back than ten minutes comes from the minute tier.
+        let history = store.query("cpu", BASE + 700_000, 3600, None).unwrap();
+        assert_eq!(history.resolution, 60);
+        assert_eq!(history.points.len(), 12);
+        assert_eq!(history.points[0].value, 29.5);
+    }
+
+    #[test]
+    fn coarser_resolution_averages_points() {
+        let mut store = MetricStore::default();
+        for second in 0..10 {
+            store.record(BASE + second * 1000, sample(second as f64));
+        }
+        let history = store.query("cpu", BASE + 9000, 10, Some(5)).unwrap();
+        assert_eq!(history.resolution, 5);
+        assert_eq!(timestamps(&history), [0, 5]);
+        assert_eq!(history.points[0].value, 2.0);
+        assert_eq!(history.points[1].value, 7.0);
+    }
+
+    #[test]
+    fn non_finite_samples_are_skipped() {
+        let mut store = MetricStore::default();
+        store.record(BASE, sample(f64::NAN));
+        assert!(store.query("cpu", BASE, 10, None).is_none());
+        store.record(BASE, sample(30.0));
+        store.record(BASE + 200, sample(f64::INFINITY));
+        let history = store.query("cpu", BASE, 10, None).unwrap();
+        assert_eq!(history.points[0].value, 30.0);
+    }
+
+    #[test]
+    fn series_no_longer_sampled_are_dropped() {
+        let mut store = MetricStore::default();
+        store.record(BASE, [("disk:sdb".to_string(), 1.0)]);
+        store.record(BASE + RETENTION_MS, sample(1.0));
+        assert!(store.query("disk:sdb", BASE + RETENTION_MS, 10, None).is_some());
+        store.record(BASE + RETENTION_MS + 1000, sample(1.0));
+        assert!(store.query("disk:sdb", BASE + RETENTION_MS + 1000, 10, None).is_none());
+    }
+}
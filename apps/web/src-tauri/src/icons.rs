@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+
+// Extracted icons live in the app cache dir as <key>.<png|svg>, where the key hashes the
+// executable's path and mtime, and are served from there by the icon:// protocol.
+const CACHE_LIMIT: u64 = 32 * 1024 * 1024;
+
+// Custom protocols are reached as http://<scheme>.localhost on Windows.
+#[cfg(target_os = "windows")]
+const URL_PREFIX: &str = "http://icon.localhost/";
+#[cfg(not(target_os = "windows"))]
+const URL_PREFIX: &str = "icon://localhost/";
+
+pub struct IconImage {
+    pub bytes: Vec<u8>,
+    // png or svg
+    pub extension: &'static str,
+}
+
+type Extractor = fn(&str) -> Option<IconImage>;
+
+struct CachedIcon {
+    extension: &'static str,
+    size: u64,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Index {
+    icons: HashMap<String, CachedIcon>,
+    // keys of executables without an icon, and of ones queued for the worker
+    missing: HashSet<String>,
+    pending: HashSet<String>,
+    total_size: u64,
+    clock: u64,
+}
+
+impl Index {
+    fn touch(&mut self, key: &str) -> Option<&'static str> {
+        self.clock += 1;
+        let icon = self.icons.get_mut(key)?;
+        icon.last_used = self.clock;
+        Some(icon.extension)
+    }
+
+    fn insert(&mut self, dir: &Path, key: String, extension: &'static str, size: u64) {
+        self.clock += 1;
+        self.total_size += size;
+        let old = self.icons.insert(key, CachedIcon { extension, size, last_used: self.clock });
+        if let Some(old) = old {
+            self.total_size -= old.size;
+        }
+        while self.total_size > CACHE_LIMIT && self.icons.len() > 1 {
+            let Some(oldest) = self.icons.iter().min_by_key(|(_, icon)| icon.last_used).map(|(key, _)| key.clone()) else {
+                break;
+            };
+            self.remove(dir, &oldest);
+        }
+    }
+
+    fn remove(&mut self, dir: &Path, key: &str) {
+        if let Some(icon) = self.icons.remove(key) {
+            self.total_size -= icon.size;
+            let _ = std::fs::remove_file(dir.join(format!("{}.{}", key, icon.extension)));
+        }
+    }
+}
+
+pub struct IconCache {
+    dir: PathBuf,
+    index: Arc<Mutex<Index>>,
+    // url_now is only used by the startup apps page, which is Windows only
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    extract: Extractor,
+    requests: mpsc::Sender<(String, String)>,
+}
+
+impl IconCache {
+    // Picks up the icons already in `dir` and starts the worker that extracts new ones.
+    pub fn open(dir: PathBuf, extract: Extractor) -> Self {
+        let _ = std::fs::create_dir_all(&dir);
+        let index = Arc::new(Mutex::new(load_index(&dir)));
+        let (requests, queue) = mpsc::channel::<(String, String)>();
+        let worker_dir = dir.clone();
+        let worker_index = index.clone();
+        std::thread::spawn(move || {
+            for (exe_path, key) in queue {
+                let icon = extract(&exe_path);
+                store(&worker_dir, &worker_index, key, icon);
+            }
+        });
+        Self { dir, index, extract, requests }
+    }
+
+    // The icon URL for an executable; None if it has no icon or the worker has not got to
+    // it yet, in which case it is queued.
+    pub fn url(&self, exe_path: &str) -> Option<String> {
+        let key = cache_key(exe_path)?;
+        let mut index = self.index.lock().unwrap();
+        if let Some(extension) = index.touch(&key) {
+            return Some(format!("{}{}.{}", URL_PREFIX, key, extension));
+        }
+        if !index.missing.contains(&key) && index.pending.insert(key.clone()) {
+            let _ = self.requests.send((exe_path.to_string(), key));
+        }
+        None
+    }
+
+    // Like url, but extracts on the calling thread when the icon is not cached yet.
+    #[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+    pub fn url_now(&self, exe_path: &str) -> Option<String> {
+        let key = cache_key(exe_path)?;
+        {
+            let mut index = self.index.lock().unwrap();
+            if let Some(extension) = index.touch(&key) {
+                return Some(format!("{}{}.{}", URL_PREFIX, key, extension));
+            }
+            if index.missing.contains(&key) {
+                return None;
+            }
+        }
+        let icon = (self.extract)(exe_path);
+        let extension = icon.as_ref().map(|icon| icon.extension);
+        store(&self.dir, &self.index, key.clone(), icon);
+        self.index.lock().unwrap().touch(&key)?;
+        extension.map(|extension| format!("{}{}.{}", URL_PREFIX, key, extension))
+    }
+
+    // The file behind an icon URL path, with its mime type.
+    pub fn read(&self, name: &str) -> Option<(Vec<u8>, &'static str)> {
+        let (key, extension) = name.rsplit_once('.')?;
+        let mut index = self.index.lock().unwrap();
+        if index.touch(key)? != extension {
+            return None;
+        }
+        match std::fs::read(self.dir.join(name)) {
+            Ok(bytes) => Some((bytes, mime_type(extension))),
+            Err(_) => {
+                index.remove(&self.dir, key);
+                None
+            }
+        }
+    }
+}
+
+fn mime_type(extension: &str) -> &'static str {
+    match extension {
+        "svg" => "image/svg+xml",
+        _ => "image/png",
+    }
+}
+
+fn store(dir: &Path, index: &Mutex<Index>, key: String, icon: Option<IconImage>) {
+    let written = icon.and_then(|icon| {
+        // Write under a temporary name so a half-written file is never served.
+        let path = dir.join(format!("{}.{}", key, icon.extension));
+        let partial = dir.join(format!("{}.partial", key));
+        std::fs::write(&partial, &icon.bytes).ok()?;
+        if std::fs::rename(&partial, &path).is_err() {
+            let _ = std::fs::remove_file(&partial);
+            return None;
+        }
+        Some((icon.extension, icon.bytes.len() as u64))
+    });
+    let mut index = index.lock().unwrap();
+    index.pending.remove(&key);
+    match written {
+        Some((extension, size)) => index.insert(dir, key, extension, size),
+        None => {
+            index.missing.insert(key);
+        }
+    }
+}
+
+fn load_index(dir: &Path) -> Index {
+    let mut found = Vec::new();
+    for entry in std::fs::read_dir(dir).into_iter().flatten().filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let Some((key, extension)) = path.file_name().and_then(|n| n.to_str()).and_then(|n| n.rsplit_once('.')) else {
+            continue;
+        };
+        let extension = match extension {
+            "png" => "png",
+            "svg" => "svg",
+            _ => {
+                // left over from an extraction that was cut short
+                let _ = std::fs::remove_file(&path);
+                continue;
+            }
+        };
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let modified = metadata.modified().unwrap_or(std::time::UNIX_EPOCH);
+        found.push((modified, key.to_string(), extension, metadata.len()));
+    }
+    // Files written longest ago count as least recently used.
+    found.sort_by_key(|(modified, ..)| *modified);
+    let mut index = Index::default();
+    for (_, key, extension, size) in found {
+        index.insert(dir, key, extension, size);
+    }
+    index
+}
+
+// FNV-1a over the path and mtime, so the key stays the same across runs and Rust versions.
+fn cache_key(exe_path: &str) -> Option<String> {
+    let modified = std::fs::metadata(exe_path).ok()?.modified().ok()?;
+    let nanos = modified.duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in exe_path.as_bytes().iter().chain(&[0]).chain(&nanos.to_le_bytes()) {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    Some(format!("{:016x}", hash))
+}
@@ -1,24 +1,19 @@
 use std::sync::Mutex;
-use std::collections::{HashSet, HashMap};
-use tauri::State;
-use sysinfo::{System, Pid, Networks, Disks};
-use windows::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
-use windows::Win32::UI::WindowsAndMessaging::{
-    EnumWindows, GetWindowThreadProcessId, IsWindowVisible, GetWindowRect,
-    GetIconInfo, DestroyIcon,
-};
-use windows::Win32::UI::Shell::{SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON};
-use windows::Win32::Graphics::Gdi::{
-    GetDC, ReleaseDC, CreateCompatibleDC, DeleteDC, GetObjectW,
-    GetDIBits, DeleteObject, BITMAP, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+use std::collections::{HashMap, HashSet};
+use tauri::{Emitter, State};
+use sysinfo::{System, Pid, Networks, Disks, ProcessStatus, ThreadKind, Users};
+
+mod backend;
+mod history;
+mod icons;
+
+use backend::{
+    EfficiencyRestore, GpuCollector, GpuMonitor, ProcessIdentity, ResourceGroup, MetricsBackend, NetworkCollector, NetworkMonitor, Platform,
+    SystemMetricsCollector, SystemMetricsMonitor,
 };
-use windows::Win32::System::Performance::*;
-use windows::core::PCWSTR;
-use windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES;
-use std::io::Cursor;
-use base64::Engine as _;
-use base64::engine::general_purpose::STANDARD as BASE64;
-use image::{RgbaImage, ImageOutputFormat};
+
+// When the threads of a process were last sampled, and each thread's cpu time in ms then.
+type ThreadSample = (std::time::Instant, HashMap<u32, u64>);
 
 struct AppState {
     sys: Mutex<System>,
@@ -26,11 +21,33 @@ struct AppState {
     gpu_monitor: Mutex<GpuMonitor>,
     network_monitor: Mutex<NetworkMonitor>,
     system_metrics_monitor: Mutex<SystemMetricsMonitor>,
-    icon_cache: Mutex<HashMap<String, String>>,
+    // set up once the app cache dir is known
+    icons: std::sync::OnceLock<icons::IconCache>,
+    users: Mutex<Users>,
+    // pid -> (start time, identity); the identity of a running process never changes
+    identity_cache: Mutex<HashMap<u32, (u64, ProcessIdentity)>>,
+    thread_samples: Mutex<HashMap<u32, ThreadSample>>,
+    // pid -> start time of processes suspended from here; Windows has no per-process
+    // suspended state to read back, so this is how they are reported as stopped.
+    suspended: Mutex<HashMap<u32, u64>>,
+    // pid -> (start time, how to undo it) for processes in efficiency mode
+    efficiency: Mutex<HashMap<u32, (u64, EfficiencyRestore)>>,
+    // root pid -> processes moved into an app-managed cgroup / job object
+    managed: Mutex<HashMap<u32, ManagedProcess>>,
     app_history: Mutex<HashMap<String, AppHistoryEntry>>,
-
-    gpu_info_cache: Mutex<Option<(String, u64, String, String, String)>>,
+    // which of MEMORY_METRICS ProcessInfo.memory and app history report
+    memory_metric: Mutex<String>,
+    memory_breakdowns: Mutex<MemoryBreakdownCache>,
+    process_events: Mutex<ProcessEventLog>,
+    // none while updates are paused
+    update_interval: Mutex<Option<std::time::Duration>>,
+    snapshot: Mutex<Option<ProcessesResponse>>,
+    process_feed: Mutex<ProcessFeed>,
+    metric_history: Mutex<history::MetricStore>,
+
+    gpu_info_cache: Mutex<Option<Vec<GpuAdapterInfo>>>,
     memory_info_cache: Mutex<Option<MemoryConfigInfo>>,
+    cpu_topology_cache: Mutex<Option<Vec<LogicalProcessor>>>,
     last_update: Mutex<std::time::Instant>,
 }
  
@@ -39,282 +56,327 @@ struct AppHistoryEntry {
     cpu_time_ms: u64,      
     network_bytes: u64,    
     disk_bytes: u64,       
+    peak_memory: u64,
     icon: Option<String>,  
 }
 
-struct GpuMonitor {
-    query: isize,
-    utilization_counter: isize,
-    memory_counter: isize,
-    shared_memory_counter: isize,
-    initialized: bool,
+#[derive(serde::Serialize, Clone)]
+struct ProcessInfo {
+    pid: u32,
+    parent_pid: Option<u32>,
+    name: String,
+    // running, sleeping, stopped, zombie or uninterruptible
+    status: String,
+    nice: i32,
+    priority_class: String,
+    sched_policy: String,
+    io_class: String,
+    efficiency_mode: bool,
+    user: Option<String>,
+    command_line: String,
+    exe_path: Option<String>,
+    start_time: u64,
+    session_id: Option<u32>,
+    architecture: Option<String>,
+    elevated: Option<bool>,
+    cpu_usage: f32,
+    // the selected memory metric; rss when the breakdown is not readable
+    memory: u64,
+    rss: u64,
+    memory_breakdown: Option<MemoryBreakdown>,
+    disk_usage: u64,
+    net_rx_bps: u64,
+    net_tx_bps: u64,
+    gpu_usage: f32,
+    gpu_engine: Option<String>,
+    is_app: bool,
+    icon: Option<String>,
 }
 
-unsafe impl Send for GpuMonitor {}
+#[derive(serde::Serialize, Clone, Default)]
+struct ProcessScheduling {
+    // Linux nice value; on Windows the nice value equivalent to the priority class
+    nice: i32,
+    // idle, below_normal, normal, above_normal, high or realtime
+    priority_class: String,
+    // other, batch, idle, fifo or rr
+    policy: String,
+    rt_priority: u32,
+    // none, realtime, best_effort or idle
+    io_class: String,
+    io_priority: u32,
+}
 
-impl GpuMonitor {
-    fn new() -> Self {
-        Self {
-            query: 0,
-            utilization_counter: 0,
-            memory_counter: 0,
-            shared_memory_counter: 0,
-            initialized: false,
-        }
-    }
+// Fields left out are not changed. priority_class wins over nice where both are given.
+#[derive(serde::Deserialize, Default)]
+struct SchedulingUpdate {
+    nice: Option<i32>,
+    priority_class: Option<String>,
+    policy: Option<String>,
+    rt_priority: Option<u32>,
+    io_class: Option<String>,
+    io_priority: Option<u32>,
+}
 
-    fn refresh(&mut self) -> (HashMap<u32, f32>, u64, u64) {
-        let mut usage_map = HashMap::new();
-        let mut total_memory = 0;
-        let mut total_shared_memory = 0;
-
-        unsafe {
-            if !self.initialized {
-                let open_res = PdhOpenQueryW(None, 0, &mut self.query);
-                if open_res == 0 {
-                    let util_path = to_wstring("\\GPU Engine(*)\\Utilization Percentage");
-                    let mem_path = to_wstring("\\GPU Adapter Memory(*)\\Dedicated Usage");
-                    let shared_mem_path = to_wstring("\\GPU Adapter Memory(*)\\Shared Usage");
-                    
-                    let add_util = PdhAddEnglishCounterW(self.query, PCWSTR(util_path.as_ptr()), 0, &mut self.utilization_counter);
-                    let add_mem = PdhAddEnglishCounterW(self.query, PCWSTR(mem_path.as_ptr()), 0, &mut self.memory_counter);
-                    let add_shared = PdhAddEnglishCounterW(self.query, PCWSTR(shared_mem_path.as_ptr()), 0, &mut self.shared_memory_counter);
-                    
-                    if add_util == 0 && add_mem == 0 && add_shared == 0 {
-                        self.initialized = true;
-                        println!("PDH GPU Monitor Initialized Successfully");
-                    }
-                }
-            }
+// None means unlimited. cpu_max_percent is relative to one CPU, so 200 allows two.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Default)]
+struct ResourceLimits {
+    memory_max: Option<u64>,
+    memory_high: Option<u64>,
+    cpu_max_percent: Option<f64>,
+    io_read_bps: Option<u64>,
+    io_write_bps: Option<u64>,
+}
 
-            if self.initialized {
-                if PdhCollectQueryData(self.query) == 0 {
-                    let mut buffer_size = 0;
-                    let mut item_count = 0;
-                    let _ = PdhGetFormattedCounterArrayW(self.utilization_counter, PDH_FMT_DOUBLE, &mut buffer_size, &mut item_count, None);
-
-                    if item_count > 0 {
-                        let mut buffer = vec![0u8; buffer_size as usize];
-                        let items_ptr = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
-                        if PdhGetFormattedCounterArrayW(self.utilization_counter, PDH_FMT_DOUBLE, &mut buffer_size, &mut item_count, Some(items_ptr)) == 0 {
-                            let items = std::slice::from_raw_parts(items_ptr, item_count as usize);
-                            for item in items {
-                                if !item.szName.is_null() {
-                                    let name = item.szName.to_string().unwrap_or_default();
-                                    if let Some(pos) = name.find("pid_") {
-                                        let start = pos + 4;
-                                        let mut end = start;
-                                        let bytes = name.as_bytes();
-                                        while end < bytes.len() && (bytes[end] as char).is_digit(10) {
-                                            end += 1;
-                                        }
-                                        if let Ok(pid) = name[start..end].parse::<u32>() {
-                                            let val = item.FmtValue.Anonymous.doubleValue;
-                                            *usage_map.entry(pid).or_insert(0.0) += val as f32;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    let mut mem_buffer_size = 0;
-                    let mut mem_item_count = 0;
-                    let _ = PdhGetFormattedCounterArrayW(self.memory_counter, PDH_FMT_LARGE, &mut mem_buffer_size, &mut mem_item_count, None);
-
-                    if mem_item_count > 0 {
-                        let mut buffer = vec![0u8; mem_buffer_size as usize];
-                        let items_ptr = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
-                        if PdhGetFormattedCounterArrayW(self.memory_counter, PDH_FMT_LARGE, &mut mem_buffer_size, &mut mem_item_count, Some(items_ptr)) == 0 {
-                            let items = std::slice::from_raw_parts(items_ptr, mem_item_count as usize);
-                            for item in items {
-                                let val = item.FmtValue.Anonymous.largeValue;
-                                total_memory += val as u64;
-                            }
-                        }
-                    }
-
-                    let mut shared_buffer_size = 0;
-                    let mut shared_item_count = 0;
-                    let _ = PdhGetFormattedCounterArrayW(self.shared_memory_counter, PDH_FMT_LARGE, &mut shared_buffer_size, &mut shared_item_count, None);
-
-                    if shared_item_count > 0 {
-                        let mut buffer = vec![0u8; shared_buffer_size as usize];
-                        let items_ptr = buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
-                        if PdhGetFormattedCounterArrayW(self.shared_memory_counter, PDH_FMT_LARGE, &mut shared_buffer_size, &mut shared_item_count, Some(items_ptr)) == 0 {
-                            let items = std::slice::from_raw_parts(items_ptr, shared_item_count as usize);
-                            for item in items {
-                                let val = item.FmtValue.Anonymous.largeValue;
-                                total_shared_memory += val as u64;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        (usage_map, total_memory, total_shared_memory)
-    }
+#[derive(serde::Serialize)]
+struct ResourceUsage {
+    pids: Vec<u32>,
+    memory_current: u64,
+    memory_peak: Option<u64>,
+    cpu_usage_usec: u64,
+    cpu_nr_throttled: u64,
+    cpu_throttled_usec: u64,
+    memory_high_events: u64,
+    memory_max_events: u64,
+    oom_events: u64,
+    oom_kill_events: u64,
+}
+
+struct ManagedProcess {
+    start_time: u64,
+    limits: ResourceLimits,
+    group: ResourceGroup,
+}
+
+#[derive(serde::Serialize)]
+struct ManagedProcessInfo {
+    pid: u32,
+    name: Option<String>,
+    limits: ResourceLimits,
+    usage: ResourceUsage,
 }
 
-struct NetworkMonitor {
-    query: isize,
-    io_counter: isize,
-    pid_counter: isize,
-    initialized: bool,
+#[derive(serde::Serialize, Clone, Default, PartialEq)]
+struct MemoryBreakdown {
+    // resident pages mapped by this process only (the private working set)
+    private: u64,
+    shared: u64,
+    // private pages plus each shared page divided by the number of processes mapping it
+    pss: Option<u64>,
+    swap: Option<u64>,
+    virtual_size: u64,
+    commit: u64,
 }
 
-unsafe impl Send for NetworkMonitor {}
+const MEMORY_METRICS: [&str; 4] = ["rss", "private", "pss", "commit"];
+
+// How stale breakdowns may get while they only feed the optional detail columns.
+const MEMORY_BREAKDOWN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Default)]
+struct MemoryBreakdownCache {
+    refreshed: Option<std::time::Instant>,
+    // pid -> (start time, breakdown)
+    entries: HashMap<u32, (u64, MemoryBreakdown)>,
+}
 
-impl NetworkMonitor {
-    fn new() -> Self {
-        Self {
-            query: 0,
-            io_counter: 0,
-            pid_counter: 0,
-            initialized: false,
+impl MemoryBreakdownCache {
+    // Reading the breakdown of every process is expensive, so it only happens each tick
+    // while the selected metric needs it.
+    fn refresh(&mut self, sys: &System, metric: &str) {
+        let due = metric != "rss"
+            || self.refreshed.is_none_or(|at| at.elapsed() >= MEMORY_BREAKDOWN_INTERVAL);
+        if !due {
+            return;
         }
+        self.entries = Platform::get_memory_breakdowns()
+            .into_iter()
+            .filter_map(|(pid, breakdown)| {
+                let start_time = sys.process(Pid::from_u32(pid))?.start_time();
+                Some((pid, (start_time, breakdown)))
+            })
+            .collect();
+        self.refreshed = Some(std::time::Instant::now());
     }
 
-    fn refresh(&mut self) -> HashMap<u32, u64> {
-        let mut usage_map = HashMap::new();
-        unsafe {
-            if !self.initialized {
-                if PdhOpenQueryW(None, 0, &mut self.query) == 0 {
-                    let io_path = to_wstring("\\Process(*)\\IO Other Bytes/sec");
-                    let pid_path = to_wstring("\\Process(*)\\ID Process");
-                    let res1 = PdhAddEnglishCounterW(self.query, PCWSTR(io_path.as_ptr()), 0, &mut self.io_counter);
-                    let res2 = PdhAddEnglishCounterW(self.query, PCWSTR(pid_path.as_ptr()), 0, &mut self.pid_counter);
-                    if res1 == 0 && res2 == 0 {
-                        self.initialized = true;
-                        println!("PDH Network Monitor Initialized Successfully");
-                    }
-                }
-            }
+    fn get(&self, process: &sysinfo::Process) -> Option<MemoryBreakdown> {
+        self.entries
+            .get(&process.pid().as_u32())
+            .filter(|(start_time, _)| *start_time == process.start_time())
+            .map(|(_, breakdown)| breakdown.clone())
+    }
+}
 
-            if self.initialized {
-                if PdhCollectQueryData(self.query) == 0 {
-                    let mut io_buffer_size = 0;
-                    let mut io_item_count = 0;
-                    let _ = PdhGetFormattedCounterArrayW(self.io_counter, PDH_FMT_DOUBLE, &mut io_buffer_size, &mut io_item_count, None);
-
-                    let mut pid_buffer_size = 0;
-                    let mut pid_item_count = 0;
-                    let _ = PdhGetFormattedCounterArrayW(self.pid_counter, PDH_FMT_DOUBLE, &mut pid_buffer_size, &mut pid_item_count, None);
-
-                    if io_item_count > 0 && pid_item_count > 0 {
-                        let mut io_buffer = vec![0u8; io_buffer_size as usize];
-                        let mut pid_buffer = vec![0u8; pid_buffer_size as usize];
-                        let io_items_ptr = io_buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
-                        let pid_items_ptr = pid_buffer.as_mut_ptr() as *mut PDH_FMT_COUNTERVALUE_ITEM_W;
-
-                        if PdhGetFormattedCounterArrayW(self.io_counter, PDH_FMT_DOUBLE, &mut io_buffer_size, &mut io_item_count, Some(io_items_ptr)) == 0 &&
-                           PdhGetFormattedCounterArrayW(self.pid_counter, PDH_FMT_DOUBLE, &mut pid_buffer_size, &mut pid_item_count, Some(pid_items_ptr)) == 0 {
-                            
-                            let io_items = std::slice::from_raw_parts(io_items_ptr, io_item_count as usize);
-                            let pid_items = std::slice::from_raw_parts(pid_items_ptr, pid_item_count as usize);
-
-                            let mut name_to_pid = HashMap::new();
-                            for item in pid_items {
-                                if !item.szName.is_null() {
-                                    let name = item.szName.to_string().unwrap_or_default();
-                                    let pid = item.FmtValue.Anonymous.doubleValue as u32;
-                                    name_to_pid.insert(name, pid);
-                                }
-                            }
-
-                            for item in io_items {
-                                if !item.szName.is_null() {
-                                    let name = item.szName.to_string().unwrap_or_default();
-                                    if let Some(&pid) = name_to_pid.get(&name) {
-                                        let val = item.FmtValue.Anonymous.doubleValue;
-                                        if val > 0.0 {
-                                            *usage_map.entry(pid).or_insert(0) += val as u64;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        usage_map
+// Windows has no PSS; the private working set is the closest thing that does not
+// count shared pages more than once.
+fn memory_metric_value(metric: &str, rss: u64, breakdown: Option<&MemoryBreakdown>) -> u64 {
+    let Some(breakdown) = breakdown else {
+        return rss;
+    };
+    match metric {
+        "private" => breakdown.private,
+        "pss" => breakdown.pss.unwrap_or(breakdown.private),
+        "commit" => breakdown.commit,
+        _ => rss,
     }
 }
 
-struct SystemMetricsMonitor {
-    query: isize,
-    counters: HashMap<String, isize>,
-    initialized: bool,
+#[tauri::command]
+fn set_memory_metric(state: State<'_, AppState>, metric: String) -> bool {
+    if !MEMORY_METRICS.contains(&metric.as_str()) {
+        return false;
+    }
+    *state.memory_metric.lock().unwrap() = metric;
+    true
 }
 
-unsafe impl Send for SystemMetricsMonitor {}
+#[derive(serde::Serialize)]
+struct ThreadInfo {
+    tid: u32,
+    name: String,
+    state: String,
+    cpu_usage: f32,
+    // user + kernel time in milliseconds
+    cpu_time: u64,
+    priority: i32,
+    nice: i32,
+    last_cpu: Option<u32>,
+    context_switches: u64,
+    // Windows only counts the total
+    voluntary_switches: Option<u64>,
+    involuntary_switches: Option<u64>,
+    wait_channel: Option<String>,
+}
 
-impl SystemMetricsMonitor {
-    fn new() -> Self {
-        Self {
-            query: 0,
-            counters: HashMap::new(),
-            initialized: false,
-        }
-    }
+#[derive(serde::Serialize)]
+struct OpenFile {
+    // descriptor on Linux, handle value on Windows; none for cwd, root, exe and mapped
+    fd: Option<u64>,
+    // file, directory, socket, pipe, anon_inode or device; cwd, root, exe or mapped
+    // for what find_file_holders reports besides descriptors
+    kind: String,
+    path: String,
+    flags: Vec<String>,
+    position: Option<u64>,
+}
 
-    fn refresh(&mut self) -> HashMap<String, f64> {
-        let mut results = HashMap::new();
-        unsafe {
-            if !self.initialized {
-                if PdhOpenQueryW(None, 0, &mut self.query) == 0 {
-                    let counters_to_add = vec![
-                        ("threads", "\\System\\Threads"),
-                        ("handles", "\\Process(_Total)\\Handle Count"),
-                        ("committed", "\\Memory\\Committed Bytes"),
-                        ("cached", "\\Memory\\Cache Bytes"),
-                        ("pool_paged", "\\Memory\\Pool Paged Bytes"),
-                        ("pool_nonpaged", "\\Memory\\Pool Nonpaged Bytes"),
-                    ];
-
-                    for (key, path) in counters_to_add {
-                        let mut counter_handle = 0;
-                        let wide_path = to_wstring(path);
-                        if PdhAddEnglishCounterW(self.query, PCWSTR(wide_path.as_ptr()), 0, &mut counter_handle) == 0 {
-                            self.counters.insert(key.to_string(), counter_handle);
-                        }
-                    }
-                    self.initialized = true;
-                    println!("PDH System Metrics Initialized");
-                }
-            }
+#[derive(serde::Serialize)]
+struct FileHolder {
+    pid: u32,
+    process_name: Option<String>,
+    file: OpenFile,
+}
 
-            if self.initialized {
-                if PdhCollectQueryData(self.query) == 0 {
-                    for (key, handle) in &self.counters {
-                        let mut type_ = 0;
-                        let mut value = std::mem::zeroed();
-                        if PdhGetFormattedCounterValue(*handle, PDH_FMT_DOUBLE, Some(&mut type_), &mut value) == 0 {
-                            results.insert(key.clone(), value.Anonymous.doubleValue);
-                        }
-                    }
-                }
-            }
-        }
-        results
-    }
+#[derive(serde::Serialize)]
+struct FileLock {
+    // none for open file description locks, which belong to no single process
+    pid: Option<u32>,
+    process_name: Option<String>,
+    // posix, flock, ofd, lease or delegation
+    kind: String,
+    mode: String,
+    mandatory: bool,
+    // waiting for a conflicting lock to be released
+    blocked: bool,
+    path: Option<String>,
+    start: u64,
+    // none when the lock runs to the end of the file
+    end: Option<u64>,
+}
+
+#[derive(serde::Serialize)]
+struct FileHolders {
+    holders: Vec<FileHolder>,
+    locks: Vec<FileLock>,
+}
+
+#[derive(serde::Deserialize, Default)]
+struct DumpOptions {
+    // leave out memory that can be read back from the mapped files
+    #[serde(default)]
+    exclude_file_backed: bool,
 }
 
 #[derive(serde::Serialize, Clone)]
-struct ProcessInfo {
+struct DumpProgress {
     pid: u32,
-    name: String,
-    cpu_usage: f32,
-    memory: u64,
-    disk_usage: u64,
-    network_usage: u64,
-    gpu_usage: f32,
-    is_app: bool,
-    icon: Option<String>,
+    written: u64,
+    total: u64,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ProcessEvent {
+    // increasing sequence number, for paging with get_process_events
+    id: u64,
+    // milliseconds since the Unix epoch
+    timestamp: u64,
+    // start, exec (Linux only) or exit
+    kind: String,
+    pid: u32,
+    parent_pid: Option<u32>,
+    exe_path: Option<String>,
+    command_line: String,
+    // exit only; exit_code or signal when the platform reports how the process ended
+    exit_code: Option<i32>,
+    signal: Option<i32>,
+    // milliseconds
+    run_time: Option<u64>,
+    peak_memory: Option<u64>,
+}
+
+#[derive(Default)]
+struct ProcessEventLog {
+    events: std::collections::VecDeque<ProcessEvent>,
+    last_id: u64,
+}
+
+#[derive(serde::Serialize)]
+struct ModuleInfo {
+    path: String,
+    base_address: u64,
+    // from the lowest to the highest address the file is mapped at
+    size: u64,
+    deleted: bool,
+    // the path now names a different file than the one that was loaded
+    replaced: bool,
+}
+
+#[derive(serde::Serialize)]
+struct MemoryRegion {
+    start: u64,
+    end: u64,
+    // rwx followed by p(rivate) or s(hared), as in /proc/<pid>/maps
+    permissions: String,
+    offset: u64,
+    path: Option<String>,
+    // "file" or "anonymous"
+    backing: String,
+    // Windows does not account these per region
+    rss: Option<u64>,
+    pss: Option<u64>,
+    swap: Option<u64>,
 }
 
 #[derive(serde::Serialize)]
+struct ProcessDetails {
+    pid: u32,
+    parent_pid: Option<u32>,
+    name: String,
+    exe_path: Option<String>,
+    command_line: Vec<String>,
+    cwd: Option<String>,
+    environment: Vec<String>,
+    user: Option<String>,
+    // numeric uid on Linux, SID on Windows
+    user_id: Option<String>,
+    start_time: u64,
+    run_time: u64,
+    session_id: Option<u32>,
+    architecture: Option<String>,
+    elevated: Option<bool>,
+}
+
+#[derive(serde::Serialize, Clone)]
 struct DiskInfo {
     name: String,
     mount_point: String,
@@ -324,7 +386,7 @@ struct DiskInfo {
     disk_type: String,
 }
 
-#[derive(serde::Serialize, Clone)]
+#[derive(serde::Serialize, Clone, Default)]
 struct MemoryConfigInfo {
     speed_mhz: u32,
     slots_used: u32,
@@ -333,7 +395,32 @@ struct MemoryConfigInfo {
     hardware_reserved: u64,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Clone)]
+struct GpuAdapterInfo {
+    index: u32,
+    name: String,
+    vendor: String,
+    memory_total: u64,
+    driver_version: String,
+    driver_date: String,
+    location: String,
+    dedicated_memory_used: u64,
+    shared_memory_used: u64,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct LogicalProcessor {
+    id: u32,
+    socket: u32,
+    core: u32,
+    // SMT siblings sharing the physical core, including this processor
+    siblings: Vec<u32>,
+    numa_node: Option<u32>,
+    // lowest processor id sharing this processor's last-level cache
+    cache_domain: Option<u32>,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
 struct HardwareInfo {
     cpu_name: String,
     cpu_cores: usize,
@@ -343,10 +430,25 @@ struct HardwareInfo {
     gpu_driver_version: String,
     gpu_driver_date: String,
     gpu_location: String,
+    gpus: Vec<GpuAdapterInfo>,
     memory_config: MemoryConfigInfo,
+    cpu_topology: Vec<LogicalProcessor>,
 }
 
-#[derive(serde::Serialize)]
+#[derive(serde::Serialize, Clone)]
+struct GpuEngineUsage {
+    engine_type: String,
+    utilization: f32,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct GpuAdapterUsage {
+    index: u32,
+    utilization: f32,
+    engines: Vec<GpuEngineUsage>,
+}
+
+#[derive(serde::Serialize, Clone, Default)]
 struct SystemStats {
     total_memory: u64,
     used_memory: u64,
@@ -365,153 +467,357 @@ struct SystemStats {
     gpu_total_usage: f32,
     gpu_memory_used: u64,
     gpu_shared_memory_used: u64,
+    gpu_adapters: Vec<GpuAdapterUsage>,
     disks: Vec<DiskInfo>,
     hardware: HardwareInfo,
 }
 
+#[derive(serde::Serialize, Clone)]
+struct ConnectionInfo {
+    protocol: String,
+    local_address: String,
+    local_port: Option<u16>,
+    remote_address: String,
+    remote_port: Option<u16>,
+    state: String,
+    pid: Option<u32>,
+    process_name: Option<String>,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ListeningPort {
+    protocol: String,
+    address: String,
+    port: u16,
+    // loopback, all (wildcard address) or interface (one specific address)
+    scope: String,
+    pid: Option<u32>,
+    process_name: Option<String>,
+    service: Option<String>,
+}
+
 #[derive(serde::Serialize)]
+struct ProcessTreeNode {
+    pid: u32,
+    name: String,
+    cpu_usage: f32,
+    memory: u64,
+    start_time: u64,
+    children: Vec<ProcessTreeNode>,
+}
+
+#[derive(serde::Serialize)]
+struct KillOutcome {
+    pid: u32,
+    name: String,
+    killed: bool,
+}
+
+#[derive(serde::Serialize, Clone)]
 struct ProcessesResponse {
     processes: Vec<ProcessInfo>,
     stats: SystemStats,
 }
 
-unsafe extern "system" fn enum_windows_proc(hwnd: HWND, lparam: LPARAM) -> BOOL {
-    if IsWindowVisible(hwnd).as_bool() {
-        let mut rect = RECT::default();
-        let _ = GetWindowRect(hwnd, &mut rect);
-        if (rect.right - rect.left) > 0 && (rect.bottom - rect.top) > 0 {
-            let mut pid = 0;
-            GetWindowThreadProcessId(hwnd, Some(&mut pid));
-            if pid != 0 {
-                let pids = &mut *(lparam.0 as *mut HashSet<u32>);
-                pids.insert(pid);
-            }
-        }
+
+// How often the sampler collects a snapshot, by the names the settings page uses;
+// "paused" stops sampling.
+const UPDATE_SPEEDS: [(&str, u64); 3] = [("high", 500), ("normal", 1000), ("low", 2000)];
+const SAMPLER_PAUSED_CHECK: std::time::Duration = std::time::Duration::from_millis(250);
+
+#[tauri::command]
+fn set_update_speed(state: State<'_, AppState>, speed: String) -> bool {
+    let interval = match UPDATE_SPEEDS.iter().find(|(name, _)| *name == speed) {
+        Some((_, ms)) => Some(std::time::Duration::from_millis(*ms)),
+        None if speed == "paused" => None,
+        None => return false,
+    };
+    *state.update_interval.lock().unwrap() = interval;
+    true
+}
+
+// The latest snapshot from the sampler; only the very first call, before the sampler has
+// finished its first tick, collects one itself.
+#[tauri::command]
+fn get_processes(state: State<'_, AppState>) -> ProcessesResponse {
+    latest_snapshot(&state)
+}
+
+fn latest_snapshot(state: &AppState) -> ProcessesResponse {
+    if let Some(snapshot) = state.snapshot.lock().unwrap().clone() {
+        return snapshot;
     }
-    BOOL(1) 
+    let snapshot = collect_snapshot(state);
+    state.snapshot.lock().unwrap().get_or_insert(snapshot).clone()
 }
 
-fn get_app_pids() -> HashSet<u32> {
-    let mut pids = HashSet::new();
-    unsafe {
-        let _ = EnumWindows(
-            Some(enum_windows_proc), 
-            LPARAM(&mut pids as *mut _ as isize)
-        );
+// Collects on the update interval and publishes every snapshot to the subscribed
+// channels, so the interval stays steady however many views are open.
+fn run_sampler(app: tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    loop {
+        let Some(interval) = *state.update_interval.lock().unwrap() else {
+            std::thread::sleep(SAMPLER_PAUSED_CHECK);
+            continue;
+        };
+        let started = std::time::Instant::now();
+        let snapshot = collect_snapshot(&state);
+        publish_snapshot(&state, snapshot);
+        std::thread::sleep(interval.saturating_sub(started.elapsed()));
     }
-    pids
 }
 
-fn to_wstring(s: &str) -> Vec<u16> {
-    s.encode_utf16().chain(std::iter::once(0)).collect()
+const UPDATE_ENCODINGS: [&str; 2] = ["json", "msgpack"];
+
+struct ProcessSubscriber {
+    id: u32,
+    channel: tauri::ipc::Channel,
+    msgpack: bool,
 }
 
-fn extract_icon_base64(path: &str) -> Option<String> {
-    unsafe {
-        let wide_path = to_wstring(path);
-        let mut sh_file_info = SHFILEINFOW::default();
-        
-        let result = SHGetFileInfoW(
-            windows::core::PCWSTR(wide_path.as_ptr()),
-            FILE_FLAGS_AND_ATTRIBUTES(0),
-            Some(&mut sh_file_info),
-            std::mem::size_of::<SHFILEINFOW>() as u32,
-            SHGFI_ICON | SHGFI_LARGEICON,
-        );
-
-        if result == 0 || sh_file_info.hIcon.is_invalid() {
-            return None;
-        }
+// The snapshot in AppState.snapshot is always the one numbered `version`: publish_snapshot
+// replaces it and bumps the version together under this lock. latest_snapshot stores one
+// without the lock, but only while there is none yet, which is version 0.
+#[derive(Default)]
+struct ProcessFeed {
+    version: u64,
+    next_id: u32,
+    subscribers: Vec<ProcessSubscriber>,
+}
 
-        let h_icon = sh_file_info.hIcon;
-        let mut icon_info = std::mem::zeroed();
-        if GetIconInfo(h_icon, &mut icon_info).is_err() {
-            let _ = DestroyIcon(h_icon);
-            return None;
-        }
+// The first message on a channel, and the answer to a resync, is the full list. Every
+// tick after that only carries what changed since base_version; a client that sees a
+// base_version other than the version it holds has missed one and should resync.
+#[derive(serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ProcessUpdate<'a> {
+    Full {
+        version: u64,
+        processes: &'a [ProcessInfo],
+        stats: &'a SystemStats,
+    },
+    Delta {
+        version: u64,
+        base_version: u64,
+        // new processes, and ones that are not the same process any more
+        added: Vec<&'a ProcessInfo>,
+        removed: Vec<u32>,
+        changed: Vec<ProcessChange>,
+        stats: &'a SystemStats,
+    },
+}
 
-        let dc = GetDC(None);
-        let mem_dc = CreateCompatibleDC(dc);
-        let _ = ReleaseDC(None, dc);
+// What changed in a process since the previous tick; only the fields that did are set,
+// and only those are serialized.
+#[derive(serde::Serialize, Default, PartialEq)]
+struct ProcessChange {
+    pid: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nice: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    priority_class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sched_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    io_class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    efficiency_mode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_usage: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rss: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_breakdown: Option<Option<MemoryBreakdown>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disk_usage: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    net_rx_bps: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    net_tx_bps: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gpu_usage: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gpu_engine: Option<Option<String>>,
+}
 
-        let mut bitmap: BITMAP = std::mem::zeroed();
-        let h_bitmap = if !icon_info.hbmColor.is_invalid() {
-            icon_info.hbmColor
-        } else {
-            icon_info.hbmMask
-        };
+// False when the pid was reused or something that does not change over the life of a
+// process did anyway, in which case the process is sent whole again.
+fn same_process(previous: &ProcessInfo, current: &ProcessInfo) -> bool {
+    previous.start_time == current.start_time
+        && previous.parent_pid == current.parent_pid
+        && previous.name == current.name
+        && previous.user == current.user
+        && previous.command_line == current.command_line
+        && previous.exe_path == current.exe_path
+        && previous.session_id == current.session_id
+        && previous.architecture == current.architecture
+        && previous.elevated == current.elevated
+        && previous.is_app == current.is_app
+        && previous.icon == current.icon
+}
 
-        if GetObjectW(
-            windows::Win32::Graphics::Gdi::HGDIOBJ(h_bitmap.0),
-            std::mem::size_of::<BITMAP>() as i32,
-            Some(&mut bitmap as *mut _ as *mut _),
-        ) == 0 {
-            let _ = DeleteDC(mem_dc);
-            let _ = DeleteObject(icon_info.hbmColor);
-            let _ = DeleteObject(icon_info.hbmMask);
-            let _ = DestroyIcon(h_icon);
-            return None;
-        }
+fn changed<T: PartialEq + Clone>(previous: &T, current: &T) -> Option<T> {
+    (previous != current).then(|| current.clone())
+}
 
-        let width = bitmap.bmWidth;
-        let height = bitmap.bmHeight;
-        let size = (width * height * 4) as usize;
-        let mut pixels: Vec<u8> = vec![0; size];
-
-        let mut bi = BITMAPINFO {
-            bmiHeader: BITMAPINFOHEADER {
-                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
-                biWidth: width,
-                biHeight: -height, 
-                biPlanes: 1,
-                biBitCount: 32,
-                biCompression: BI_RGB.0, 
-                ..Default::default()
-            },
-            ..Default::default()
-        };
+fn process_change(previous: &ProcessInfo, current: &ProcessInfo) -> Option<ProcessChange> {
+    let change = ProcessChange {
+        pid: current.pid,
+        status: changed(&previous.status, &current.status),
+        nice: changed(&previous.nice, &current.nice),
+        priority_class: changed(&previous.priority_class, &current.priority_class),
+        sched_policy: changed(&previous.sched_policy, &current.sched_policy),
+        io_class: changed(&previous.io_class, &current.io_class),
+        efficiency_mode: changed(&previous.efficiency_mode, &current.efficiency_mode),
+        cpu_usage: changed(&previous.cpu_usage, &current.cpu_usage),
+        memory: changed(&previous.memory, &current.memory),
+        rss: changed(&previous.rss, &current.rss),
+        memory_breakdown: changed(&previous.memory_breakdown, &current.memory_breakdown),
+        disk_usage: changed(&previous.disk_usage, &current.disk_usage),
+        net_rx_bps: changed(&previous.net_rx_bps, &current.net_rx_bps),
+        net_tx_bps: changed(&previous.net_tx_bps, &current.net_tx_bps),
+        gpu_usage: changed(&previous.gpu_usage, &current.gpu_usage),
+        gpu_engine: changed(&previous.gpu_engine, &current.gpu_engine),
+    };
+    let unchanged = ProcessChange { pid: current.pid, ..Default::default() };
+    (change != unchanged).then_some(change)
+}
 
-        if GetDIBits(
-            mem_dc,
-            h_bitmap,
-            0,
-            height.abs() as u32,
-            Some(pixels.as_mut_ptr() as *mut _),
-            &mut bi,
-            DIB_RGB_COLORS,
-        ) == 0 {
-            let _ = DeleteDC(mem_dc);
-            let _ = DeleteObject(icon_info.hbmColor);
-            let _ = DeleteObject(icon_info.hbmMask);
-            let _ = DestroyIcon(h_icon);
-            return None;
-        }
-        
-        let _ = DeleteDC(mem_dc);
-        let _ = DeleteObject(icon_info.hbmColor);
-        let _ = DeleteObject(icon_info.hbmMask);
-        let _ = DestroyIcon(h_icon);
-
-        for chunk in pixels.chunks_mut(4) {
-            let b = chunk[0];
-            let r = chunk[2];
-            chunk[0] = r;
-            chunk[2] = b;
+fn process_delta<'a>(
+    previous: &ProcessesResponse,
+    current: &'a ProcessesResponse,
+    base_version: u64,
+) -> ProcessUpdate<'a> {
+    let before: HashMap<u32, &ProcessInfo> =
+        previous.processes.iter().map(|p| (p.pid, p)).collect();
+    let mut added = Vec::new();
+    let mut changes = Vec::new();
+    for process in &current.processes {
+        match before.get(&process.pid) {
+            Some(old) if same_process(old, process) => changes.extend(process_change(old, process)),
+            _ => added.push(process),
         }
+    }
+    let current_pids: std::collections::HashSet<u32> =
+        current.processes.iter().map(|p| p.pid).collect();
+    let removed = previous
+        .processes
+        .iter()
+        .map(|p| p.pid)
+        .filter(|pid| !current_pids.contains(pid))
+        .collect();
+    ProcessUpdate::Delta {
+        version: base_version + 1,
+        base_version,
+        added,
+        removed,
+        changed: changes,
+        stats: &current.stats,
+    }
+}
 
-        if let Some(img_buffer) = RgbaImage::from_raw(width as u32, height.abs() as u32, pixels) {
-             let mut png_data = Vec::new();
-             if img_buffer.write_to(&mut Cursor::new(&mut png_data), ImageOutputFormat::Png).is_ok() {
-                 return Some(format!("data:image/png;base64,{}", BASE64.encode(png_data)));
-             }
+fn encode_update(update: &ProcessUpdate, msgpack: bool) -> Result<tauri::ipc::InvokeResponseBody, String> {
+    if msgpack {
+        rmp_serde::to_vec_named(update)
+            .map(tauri::ipc::InvokeResponseBody::Raw)
+            .map_err(|e| e.to_string())
+    } else {
+        serde_json::to_string(update)
+            .map(tauri::ipc::InvokeResponseBody::Json)
+            .map_err(|e| e.to_string())
+    }
+}
+
+// Sends to every subscriber that wants this encoding; each encoding is serialized at most
+// once. Subscribers whose channel is gone are dropped.
+fn broadcast_update(feed: &mut ProcessFeed, update: &ProcessUpdate) {
+    let mut encoded: [Option<tauri::ipc::InvokeResponseBody>; 2] = [None, None];
+    feed.subscribers.retain(|subscriber| {
+        let slot = &mut encoded[usize::from(subscriber.msgpack)];
+        if slot.is_none() {
+            // A subscriber that misses an update cannot apply the deltas after it, so one
+            // that cannot be served is dropped like one whose channel is gone.
+            match encode_update(update, subscriber.msgpack) {
+                Ok(body) => *slot = Some(body),
+                Err(_) => return false,
+            }
         }
-        
-        None
+        let body = slot.clone().unwrap();
+        subscriber.channel.send(body).is_ok()
+    });
+}
+
+fn publish_snapshot(state: &AppState, snapshot: ProcessesResponse) {
+    let mut feed = state.process_feed.lock().unwrap();
+    let previous = state.snapshot.lock().unwrap().replace(snapshot.clone());
+    let base_version = feed.version;
+    feed.version += 1;
+    let update = match &previous {
+        Some(previous) => process_delta(previous, &snapshot, base_version),
+        None => ProcessUpdate::Full {
+            version: feed.version,
+            processes: &snapshot.processes,
+            stats: &snapshot.stats,
+        },
+    };
+    broadcast_update(&mut feed, &update);
+}
+
+fn send_full_update(state: &AppState, feed: &ProcessFeed, subscriber: &ProcessSubscriber) -> Result<(), String> {
+    let snapshot = latest_snapshot(state);
+    let update = ProcessUpdate::Full {
+        version: feed.version,
+        processes: &snapshot.processes,
+        stats: &snapshot.stats,
+    };
+    let body = encode_update(&update, subscriber.msgpack)?;
+    subscriber.channel.send(body).map_err(|e| e.to_string())
+}
+
+// Starts streaming process updates to `channel` with the full list, and returns the id
+// to resync or unsubscribe with.
+#[tauri::command]
+fn subscribe_processes(
+    state: State<'_, AppState>,
+    channel: tauri::ipc::Channel,
+    encoding: String,
+) -> Result<u32, String> {
+    if !UPDATE_ENCODINGS.contains(&encoding.as_str()) {
+        return Err(format!("unknown encoding: {encoding}"));
     }
+    let mut feed = state.process_feed.lock().unwrap();
+    feed.next_id += 1;
+    let subscriber = ProcessSubscriber {
+        id: feed.next_id,
+        channel,
+        msgpack: encoding == "msgpack",
+    };
+    send_full_update(&state, &feed, &subscriber)?;
+    feed.subscribers.push(subscriber);
+    Ok(feed.next_id)
 }
 
 #[tauri::command]
-fn get_processes(state: State<'_, AppState>) -> ProcessesResponse {
+fn resync_processes(state: State<'_, AppState>, id: u32) -> Result<(), String> {
+    let feed = state.process_feed.lock().unwrap();
+    let subscriber = feed
+        .subscribers
+        .iter()
+        .find(|s| s.id == id)
+        .ok_or_else(|| format!("no process subscription {id}"))?;
+    send_full_update(&state, &feed, subscriber)
+}
+
+#[tauri::command]
+fn unsubscribe_processes(state: State<'_, AppState>, id: u32) {
+    state.process_feed.lock().unwrap().subscribers.retain(|s| s.id != id);
+}
+
+fn collect_snapshot(state: &AppState) -> ProcessesResponse {
     let mut sys = state.sys.lock().unwrap();
     
     let mut prev_disk_usage = HashMap::new();
@@ -524,41 +830,62 @@ fn get_processes(state: State<'_, AppState>) -> ProcessesResponse {
     
     let mut networks = state.networks.lock().unwrap();
     networks.refresh(true);
+
+    let elapsed_ms = {
+        let mut last_update = state.last_update.lock().unwrap();
+        let now = std::time::Instant::now();
+        let elapsed_ms = now.duration_since(*last_update).as_millis() as u64;
+        *last_update = now;
+        elapsed_ms
+    };
     
+    // The interface counters cover the whole tick, whose length depends on the update
+    // speed, so they are turned into bytes per second.
     let mut network_total_usage = 0;
-    for (_interface_name, data) in networks.iter() {
-        network_total_usage += data.received() + data.transmitted();
+    let mut interface_usage = Vec::new();
+    for (interface_name, data) in networks.iter() {
+        let usage = (data.received() + data.transmitted()) * 1000 / elapsed_ms.max(1);
+        network_total_usage += usage;
+        interface_usage.push((interface_name.clone(), usage));
     }
     
     let mut gpu_monitor = state.gpu_monitor.lock().unwrap();
-    let (gpu_usage_map, gpu_memory_used, gpu_shared_memory_used) = gpu_monitor.refresh();
+    let gpu_sample = gpu_monitor.refresh();
+    let gpu_usage_map = gpu_sample.process_usage();
+    let gpu_adapters = gpu_sample.adapter_usage();
+    let total_gpu_usage = gpu_adapters.iter().map(|a| a.utilization).fold(0.0, f32::max);
 
     let mut network_monitor = state.network_monitor.lock().unwrap();
     let network_usage_map = network_monitor.refresh();
 
-    let app_pids = get_app_pids();
-    let mut icon_cache = state.icon_cache.lock().unwrap();
+    let app_pids = Platform::get_app_pids();
+    let suspended = state.suspended.lock().unwrap();
+    let mut efficiency = state.efficiency.lock().unwrap();
+    efficiency.retain(|pid, (start_time, _)| {
+        sys.process(Pid::from_u32(*pid)).is_some_and(|p| p.start_time() == *start_time)
+    });
+    let icons = state.icons.get();
+    let mut users = state.users.lock().unwrap();
+    let mut users_refreshed = false;
+    let mut identity_cache = state.identity_cache.lock().unwrap();
+    identity_cache.retain(|pid, (start_time, _)| {
+        sys.process(Pid::from_u32(*pid)).is_some_and(|p| p.start_time() == *start_time)
+    });
+    let memory_metric = state.memory_metric.lock().unwrap().clone();
+    let mut memory_breakdowns = state.memory_breakdowns.lock().unwrap();
+    memory_breakdowns.refresh(&sys, &memory_metric);
     
     let mut total_disk_usage = 0;
-    let mut total_gpu_usage = 0.0;
 
-    let mut processes: Vec<ProcessInfo> = sys.processes().iter().map(|(pid, process)| {
+    // Linux lists every thread of a process as a process too; count the process once.
+    let mut processes: Vec<ProcessInfo> = sys.processes().iter()
+        .filter(|(_, process)| process.thread_kind() != Some(ThreadKind::Userland))
+        .map(|(pid, process)| {
         let is_app = app_pids.contains(&pid.as_u32());
-        let mut icon = None;
-        
-        if is_app {
-            if let Some(exe_path) = process.exe() {
-                let path_str = exe_path.to_string_lossy().into_owned();
-                if let Some(cached) = icon_cache.get(&path_str) {
-                    icon = Some(cached.clone());
-                } else {
-                    if let Some(extracted) = extract_icon_base64(&path_str) {
-                        icon = Some(extracted.clone());
-                        icon_cache.insert(path_str, extracted);
-                    }
-                }
-            }
-        }
+        let icon = match (is_app, process.exe(), icons) {
+            (true, Some(exe_path), Some(icons)) => icons.url(&exe_path.to_string_lossy()),
+            _ => None,
+        };
 
         let current_disk = process.disk_usage();
         let current_total = current_disk.total_read_bytes + current_disk.total_written_bytes;
@@ -571,10 +898,20 @@ fn get_processes(state: State<'_, AppState>) -> ProcessesResponse {
         
         total_disk_usage += disk_usage;
         
-        let gpu_usage = *gpu_usage_map.get(&pid.as_u32()).unwrap_or(&0.0);
-        total_gpu_usage += gpu_usage;
+        let (gpu_usage, gpu_engine) = match gpu_usage_map.get(&pid.as_u32()) {
+            Some((usage, engine)) => (*usage, engine.clone()),
+            None => (0.0, None),
+        };
 
-        let proc_network_usage = *network_usage_map.get(&pid.as_u32()).unwrap_or(&0);
+        let network_usage = network_usage_map.get(&pid.as_u32()).copied().unwrap_or_default();
+
+        let identity = process_identity(&mut identity_cache, process);
+        let memory_breakdown = memory_breakdowns.get(process);
+        let scheduling = Platform::get_process_scheduling(pid.as_u32());
+        let (nice, priority_class, sched_policy, io_class) = match scheduling {
+            Some(s) => (s.nice, s.priority_class, s.policy, s.io_class),
+            None => (0, String::new(), String::new(), String::new()),
+        };
 
         let num_cores = sys.cpus().len() as f32;
         let normalized_cpu = if num_cores > 0.0 {
@@ -585,12 +922,30 @@ fn get_processes(state: State<'_, AppState>) -> ProcessesResponse {
 
         ProcessInfo {
             pid: pid.as_u32(),
-            name: process.name().to_string_lossy().into_owned(),
+            parent_pid: parent_pid(&sys, process).map(|p| p.as_u32()),
+            name: process_name(process),
+            status: process_status(process, &suspended).to_string(),
+            nice,
+            priority_class,
+            sched_policy,
+            io_class,
+            efficiency_mode: efficiency.contains_key(&pid.as_u32()),
+            user: process.user_id().and_then(|uid| user_name(&mut users, &mut users_refreshed, uid)),
+            command_line: command_line(process),
+            exe_path: process.exe().map(|p| p.to_string_lossy().into_owned()),
+            start_time: process.start_time(),
+            session_id: identity.session_id,
+            architecture: identity.architecture,
+            elevated: identity.elevated,
             cpu_usage: normalized_cpu,
-            memory: process.memory(),
+            memory: memory_metric_value(&memory_metric, process.memory(), memory_breakdown.as_ref()),
+            rss: process.memory(),
+            memory_breakdown,
             disk_usage,
-            network_usage: proc_network_usage,
+            net_rx_bps: network_usage.rx_bps,
+            net_tx_bps: network_usage.tx_bps,
             gpu_usage,
+            gpu_engine,
             is_app,
             icon,
         }
@@ -600,35 +955,34 @@ fn get_processes(state: State<'_, AppState>) -> ProcessesResponse {
     
     {
         let mut app_history = state.app_history.lock().unwrap();
-        let mut last_update = state.last_update.lock().unwrap();
-        let now = std::time::Instant::now();
-        let elapsed_ms = now.duration_since(*last_update).as_millis() as u64;
-        *last_update = now;
         
-        let mut app_stats: HashMap<String, (f32, u64, u64, Option<String>)> = HashMap::new();
+        let mut app_stats: HashMap<String, (f32, u64, u64, u64, Option<String>)> = HashMap::new();
         for proc in &processes {
-            let entry = app_stats.entry(proc.name.clone()).or_insert((0.0, 0, 0, None));
+            let entry = app_stats.entry(proc.name.clone()).or_insert((0.0, 0, 0, 0, None));
             entry.0 += proc.cpu_usage;          
-            entry.1 += proc.network_usage;      
+            entry.1 += proc.net_rx_bps + proc.net_tx_bps;
             entry.2 += proc.disk_usage;         
-            if entry.3.is_none() && proc.icon.is_some() {
-                entry.3 = proc.icon.clone();
+            entry.3 += proc.memory;
+            if entry.4.is_none() && proc.icon.is_some() {
+                entry.4 = proc.icon.clone();
             }
         }
         
-        for (name, (cpu_pct, net_bytes, disk_bytes, icon)) in app_stats {
+        for (name, (cpu_pct, net_bytes, disk_bytes, memory, icon)) in app_stats {
             let cpu_time_delta = (cpu_pct as u64 * elapsed_ms) / 100;
             
             let history_entry = app_history.entry(name).or_insert(AppHistoryEntry {
                 cpu_time_ms: 0,
                 network_bytes: 0,
                 disk_bytes: 0,
+                peak_memory: 0,
                 icon: None,
             });
             
             history_entry.cpu_time_ms += cpu_time_delta;
             history_entry.network_bytes += net_bytes;
             history_entry.disk_bytes += disk_bytes;
+            history_entry.peak_memory = history_entry.peak_memory.max(memory);
             if history_entry.icon.is_none() && icon.is_some() {
                 history_entry.icon = icon;
             }
@@ -670,14 +1024,36 @@ fn get_processes(state: State<'_, AppState>) -> ProcessesResponse {
     let logical_processors = sys.cpus().len();
     let cpu_cores = sys.physical_core_count().unwrap_or(logical_processors);
     let cpu_usage_per_core: Vec<f32> = sys.cpus().iter().map(|cpu| cpu.cpu_usage()).collect();
-    let (gpu_name, gpu_memory_total, gpu_driver_version, gpu_driver_date, gpu_location) = get_gpu_info(&state);
-    let memory_config = get_memory_config(&state, sys.total_memory(), sys.used_memory());
+    let mut gpus = get_gpu_info(state);
+    for gpu in &mut gpus {
+        if let Some((dedicated, shared)) = gpu_sample.adapter_memory.get(&gpu.index) {
+            gpu.dedicated_memory_used = *dedicated;
+            gpu.shared_memory_used = *shared;
+        }
+    }
+    let (gpu_name, gpu_memory_total, gpu_driver_version, gpu_driver_date, gpu_location) = match gpus.first() {
+        Some(gpu) => (
+            gpu.name.clone(),
+            gpu.memory_total,
+            gpu.driver_version.clone(),
+            gpu.driver_date.clone(),
+            gpu.location.clone(),
+        ),
+        None => (
+            "Unknown GPU".to_string(),
+            0,
+            "Unknown".to_string(),
+            "Unknown".to_string(),
+            "PCI bus".to_string(),
+        ),
+    };
+    let memory_config = get_memory_config(state, sys.total_memory());
+    let cpu_topology = get_cpu_topology(state);
     let process_count = processes.len();
     let system_metrics = state.system_metrics_monitor.lock().unwrap().refresh();
-    let system_metrics = state.system_metrics_monitor.lock().unwrap().refresh();
     let uptime = System::uptime(); // Returns seconds
     
-    ProcessesResponse {
+    let response = ProcessesResponse {
         processes,
         stats: SystemStats {
             total_memory: sys.total_memory(),
@@ -696,8 +1072,9 @@ fn get_processes(state: State<'_, AppState>) -> ProcessesResponse {
             disk_total_usage: total_disk_usage,
             network_total_usage,
             gpu_total_usage: total_gpu_usage,
-            gpu_memory_used,
-            gpu_shared_memory_used,
+            gpu_memory_used: gpu_sample.dedicated_memory,
+            gpu_shared_memory_used: gpu_sample.shared_memory,
+            gpu_adapters,
             disks: disk_infos,
             hardware: HardwareInfo {
                 cpu_name,
@@ -708,267 +1085,732 @@ fn get_processes(state: State<'_, AppState>) -> ProcessesResponse {
                 gpu_driver_version,
                 gpu_driver_date,
                 gpu_location,
+                gpus,
                 memory_config,
+                cpu_topology,
             },
         }
+    };
+    record_metric_history(state, &response.stats, &interface_usage, &system_metrics);
+    response
+}
+
+// Everything the performance pages graph, under the ids of their routes: cpu, cpu-<core>,
+// memory, disk-<mount point without punctuation>, network, network-<interface>, gpu,
+// gpu-<adapter> and system-<counter>. Network series are in bytes per second.
+fn record_metric_history(
+    state: &AppState,
+    stats: &SystemStats,
+    interfaces: &[(String, u64)],
+    system_metrics: &HashMap<String, f64>,
+) {
+    let mut samples = vec![
+        ("cpu".to_string(), f64::from(stats.total_cpu_usage)),
+        ("memory".to_string(), stats.used_memory as f64 / stats.total_memory.max(1) as f64 * 100.0),
+        ("network".to_string(), stats.network_total_usage as f64),
+        ("gpu".to_string(), f64::from(stats.gpu_total_usage)),
+    ];
+    for (core, usage) in stats.cpu_usage_per_core.iter().enumerate() {
+        samples.push((format!("cpu-{}", core), f64::from(*usage)));
+    }
+    for disk in &stats.disks {
+        let mount: String = disk.mount_point.chars().filter(char::is_ascii_alphanumeric).collect();
+        samples.push((format!("disk-{}", mount), f64::from(disk.usage_percent)));
+    }
+    for (name, usage) in interfaces {
+        samples.push((format!("network-{}", name), *usage as f64));
+    }
+    for adapter in &stats.gpu_adapters {
+        samples.push((format!("gpu-{}", adapter.index), f64::from(adapter.utilization)));
+    }
+    for (counter, value) in system_metrics {
+        samples.push((format!("system-{}", counter), *value));
+    }
+    state.metric_history.lock().unwrap().record(epoch_ms(), samples);
+}
+
+// `range` and `resolution` are in seconds; without a resolution the points come at the
+// step of whichever tier covers the range.
+#[tauri::command]
+fn get_metric_history(
+    state: State<'_, AppState>,
+    metric_id: String,
+    range: u64,
+    resolution: Option<u64>,
+) -> Result<history::MetricHistory, String> {
+    state
+        .metric_history
+        .lock()
+        .unwrap()
+        .query(&metric_id, epoch_ms(), range, resolution)
+        .ok_or_else(|| format!("no history for metric {}", metric_id))
+}
+
+// Picks up new and exited processes without touching CPU usage, which only the sampler
+// refreshes so its deltas cover whole intervals.
+fn refresh_process_list(sys: &mut System) {
+    sys.refresh_processes_specifics(sysinfo::ProcessesToUpdate::All, true, sysinfo::ProcessRefreshKind::nothing());
+}
+
+fn process_name(process: &sysinfo::Process) -> String {
+    process.name().to_string_lossy().into_owned()
+}
+
+// Users created after startup are picked up by refreshing the list on a miss, at most
+// once per call site so uids without an account don't re-read it for every process.
+fn user_name(users: &mut Users, refreshed: &mut bool, uid: &sysinfo::Uid) -> Option<String> {
+    if users.get_user_by_id(uid).is_none() && !*refreshed {
+        users.refresh();
+        *refreshed = true;
+    }
+    users.get_user_by_id(uid).map(|user| user.name().to_string())
+}
+
+fn command_line(process: &sysinfo::Process) -> String {
+    join_command_line(process.cmd().iter().map(|arg| arg.to_string_lossy()))
+}
+
+// Arguments containing whitespace are quoted so the line can be copied into a shell.
+fn join_command_line<'a>(args: impl Iterator<Item = std::borrow::Cow<'a, str>>) -> String {
+    args.map(|arg| {
+        if arg.contains(char::is_whitespace) {
+            format!("\"{}\"", arg)
+        } else {
+            arg.into_owned()
+        }
+    })
+    .collect::<Vec<_>>()
+    .join(" ")
+}
+
+fn epoch_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn process_identity(cache: &mut HashMap<u32, (u64, ProcessIdentity)>, process: &sysinfo::Process) -> ProcessIdentity {
+    let pid = process.pid().as_u32();
+    match cache.get(&pid) {
+        Some((start_time, identity)) if *start_time == process.start_time() => identity.clone(),
+        _ => {
+            let identity = Platform::process_identity(pid);
+            cache.insert(pid, (process.start_time(), identity.clone()));
+            identity
+        }
+    }
+}
+
+#[tauri::command]
+fn get_process_details(state: State<'_, AppState>, pid: u32) -> Option<ProcessDetails> {
+    let mut sys = state.sys.lock().unwrap();
+    let target = Pid::from_u32(pid);
+    sys.refresh_processes_specifics(
+        sysinfo::ProcessesToUpdate::Some(&[target]),
+        true,
+        // cwd and environment can change while the process runs, so they are always re-read
+        // CPU usage is left to the sampler so its deltas cover whole intervals.
+        sysinfo::ProcessRefreshKind::everything()
+            .without_cpu()
+            .with_cwd(sysinfo::UpdateKind::Always)
+            .with_environ(sysinfo::UpdateKind::Always),
+    );
+    let process = sys.process(target)?;
+
+    let mut users = state.users.lock().unwrap();
+    let mut identity_cache = state.identity_cache.lock().unwrap();
+    let identity = process_identity(&mut identity_cache, process);
+
+    Some(ProcessDetails {
+        pid,
+        parent_pid: parent_pid(&sys, process).map(|p| p.as_u32()),
+        name: process_name(process),
+        exe_path: process.exe().map(|p| p.to_string_lossy().into_owned()),
+        command_line: process.cmd().iter().map(|arg| arg.to_string_lossy().into_owned()).collect(),
+        cwd: process.cwd().map(|p| p.to_string_lossy().into_owned()),
+        environment: process.environ().iter().map(|var| var.to_string_lossy().into_owned()).collect(),
+        user: process.user_id().and_then(|uid| user_name(&mut users, &mut false, uid)),
+        user_id: process.user_id().map(|uid| uid.to_string()),
+        start_time: process.start_time(),
+        run_time: process.run_time(),
+        session_id: identity.session_id,
+        architecture: identity.architecture,
+        elevated: identity.elevated,
+    })
+}
+
+fn with_process_names(sys: &System, mut connections: Vec<ConnectionInfo>) -> Vec<ConnectionInfo> {
+    for connection in &mut connections {
+        connection.process_name = connection
+            .pid
+            .and_then(|pid| sys.process(Pid::from_u32(pid)))
+            .map(process_name);
+    }
+    connections
+}
+
+#[tauri::command]
+fn get_connections(state: State<'_, AppState>) -> Vec<ConnectionInfo> {
+    let sys = state.sys.lock().unwrap();
+    with_process_names(&sys, Platform::get_connections())
+}
+
+#[tauri::command]
+fn get_process_connections(state: State<'_, AppState>, pid: u32) -> Vec<ConnectionInfo> {
+    let sys = state.sys.lock().unwrap();
+    let connections = Platform::get_connections()
+        .into_iter()
+        .filter(|c| c.pid == Some(pid))
+        .collect();
+    with_process_names(&sys, connections)
+}
+
+fn listen_scope(address: &str) -> &'static str {
+    match address.parse::<std::net::IpAddr>() {
+        Ok(ip) if ip.is_loopback() => "loopback",
+        Ok(ip) if ip.is_unspecified() => "all",
+        _ => "interface",
+    }
+}
+
+// TCP sockets in LISTEN and UDP sockets without a fixed peer.
+fn listening_ports(state: &State<'_, AppState>) -> Vec<ListeningPort> {
+    let connections = {
+        let sys = state.sys.lock().unwrap();
+        with_process_names(&sys, Platform::get_connections())
+    };
+    let services: HashMap<u32, String> = Platform::get_services()
+        .into_iter()
+        .filter_map(|service| Some((service.pid.filter(|pid| *pid != 0)?, service.name)))
+        .collect();
+
+    let mut ports: Vec<ListeningPort> = connections
+        .into_iter()
+        .filter(|c| match c.protocol.as_str() {
+            "TCP" | "TCPv6" => c.state == "LISTEN",
+            "UDP" | "UDPv6" => c.remote_port.unwrap_or(0) == 0,
+            _ => false,
+        })
+        .filter_map(|c| {
+            Some(ListeningPort {
+                scope: listen_scope(&c.local_address).to_string(),
+                service: c.pid.and_then(|pid| services.get(&pid).cloned()),
+                protocol: c.protocol,
+                address: c.local_address,
+                port: c.local_port?,
+                pid: c.pid,
+                process_name: c.process_name,
+            })
+        })
+        .collect();
+    ports.sort_by(|a, b| a.port.cmp(&b.port).then_with(|| a.protocol.cmp(&b.protocol)));
+    ports
+}
+
+#[tauri::command]
+fn get_listening_ports(state: State<'_, AppState>) -> Vec<ListeningPort> {
+    listening_ports(&state)
+}
+
+// protocol is "tcp" or "udp" and matches both the IPv4 and IPv6 sockets.
+#[tauri::command]
+fn find_port_owners(state: State<'_, AppState>, port: u16, protocol: String) -> Vec<ListeningPort> {
+    let protocol = protocol.to_uppercase();
+    listening_ports(&state)
+        .into_iter()
+        .filter(|p| p.port == port && p.protocol.trim_end_matches("v6") == protocol)
+        .collect()
+}
+
+fn get_gpu_info(state: &AppState) -> Vec<GpuAdapterInfo> {
+    let mut cache = state.gpu_info_cache.lock().unwrap();
+    if let Some(info) = cache.as_ref() {
+        return info.clone();
+    }
+
+    let info = Platform::get_gpu_info();
+    *cache = Some(info.clone());
+    info
+}
+
+fn get_cpu_topology(state: &AppState) -> Vec<LogicalProcessor> {
+    let mut cache = state.cpu_topology_cache.lock().unwrap();
+    if let Some(info) = cache.as_ref() {
+        return info.clone();
+    }
+
+    let info = Platform::get_cpu_topology();
+    *cache = Some(info.clone());
+    info
+}
+
+fn get_memory_config(state: &AppState, total_memory: u64) -> MemoryConfigInfo {
+    let mut cache = state.memory_info_cache.lock().unwrap();
+    if let Some(info) = cache.as_ref() {
+        return info.clone();
+    }
+
+    let info = Platform::get_memory_config(total_memory);
+    *cache = Some(info.clone());
+    info
+}
+
+#[tauri::command]
+fn kill_process(state: State<'_, AppState>, pid: u32) -> bool {
+    let sys = state.sys.lock().unwrap();
+    if let Some(process) = sys.processes().get(&Pid::from_u32(pid)) {
+        return process.kill();
+    }
+    false
+}
+
+fn process_status(process: &sysinfo::Process, suspended: &HashMap<u32, u64>) -> &'static str {
+    if suspended.get(&process.pid().as_u32()) == Some(&process.start_time()) {
+        return "stopped";
+    }
+    match process.status() {
+        ProcessStatus::Stop | ProcessStatus::Tracing => "stopped",
+        ProcessStatus::Zombie | ProcessStatus::Dead => "zombie",
+        ProcessStatus::UninterruptibleDiskSleep => "uninterruptible",
+        ProcessStatus::Sleep
+        | ProcessStatus::Idle
+        | ProcessStatus::Parked
+        | ProcessStatus::LockBlocked
+        | ProcessStatus::Waking
+        | ProcessStatus::Wakekill => "sleeping",
+        _ => "running",
+    }
+}
+
+#[tauri::command]
+fn suspend_process(state: State<'_, AppState>, pid: u32) -> bool {
+    let sys = state.sys.lock().unwrap();
+    let Some(process) = sys.process(Pid::from_u32(pid)) else {
+        return false;
+    };
+    let suspended = Platform::suspend_process(pid);
+    if suspended {
+        state.suspended.lock().unwrap().insert(pid, process.start_time());
     }
+    suspended
 }
 
-fn get_gpu_info(state: &State<'_, AppState>) -> (String, u64, String, String, String) {
-    let mut cache = state.gpu_info_cache.lock().unwrap();
-    if let Some(info) = cache.as_ref() {
-        return info.clone();
+#[tauri::command]
+fn resume_process(state: State<'_, AppState>, pid: u32) -> bool {
+    let resumed = Platform::resume_process(pid);
+    if resumed {
+        state.suspended.lock().unwrap().remove(&pid);
     }
+    resumed
+}
 
-    use std::process::Command;
-    use std::os::windows::process::CommandExt;
-    
-    const CREATE_NO_WINDOW: u32 = 0x08000000;
+#[tauri::command]
+fn get_process_scheduling(pid: u32) -> Option<ProcessScheduling> {
+    Platform::get_process_scheduling(pid)
+}
 
-    let output = Command::new("powershell")
-        .args([
-            "-NoProfile", 
-            "-Command", 
-            "Get-CimInstance Win32_VideoController | Select-Object Name, AdapterRAM, DriverVersion, DriverDate, PNPDeviceID | ConvertTo-Json -Compress"
-        ])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output();
-    
-    let mut name = "Unknown GPU".to_string();
-    let mut memory = 0u64;
-    let mut version = "Unknown".to_string();
-    let mut date_str = "Unknown".to_string();
-    let mut location = "PCI bus".to_string();
-    let mut pnp_id = "".to_string();
+#[tauri::command]
+fn set_process_scheduling(pid: u32, scheduling: SchedulingUpdate) -> bool {
+    Platform::set_process_scheduling(pid, &scheduling)
+}
 
-    if let Ok(output) = output {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        #[derive(serde::Deserialize)]
-        struct GpuInfoRaw {
-            Name: Option<String>,
-            AdapterRAM: Option<u64>,
-            DriverVersion: Option<String>,
-            DriverDate: Option<String>,
-            PNPDeviceID: Option<String>,
-        }
+#[tauri::command]
+fn get_affinity(pid: u32) -> Option<Vec<u32>> {
+    Platform::get_affinity(pid)
+}
 
-        fn extract_data(gpu: &GpuInfoRaw) -> (String, u64, String, String, String) {
-             let n = gpu.Name.clone().unwrap_or("Unknown".to_string());
-             let m = gpu.AdapterRAM.unwrap_or(0);
-             let v = gpu.DriverVersion.clone().unwrap_or("Unknown".to_string());
-             
-             let d_raw = gpu.DriverDate.clone().unwrap_or("Unknown".to_string());
-             let d = if d_raw.starts_with("/Date(") {
-                 if let Some(start) = d_raw.find('(') {
-                      if let Some(end) = d_raw.find(')') {
-                          if let Ok(ms) = d_raw[start+1..end].parse::<i64>() {
-                                use std::time::{UNIX_EPOCH, Duration};
-                                if UNIX_EPOCH.checked_add(Duration::from_millis(ms as u64)).is_some() {
-                                     d_raw
-                                } else { d_raw }
-                          } else { d_raw }
-                      } else { d_raw }
-                  } else { d_raw }
-             } else {
-                 d_raw
-             };
-             
-             let pid = gpu.PNPDeviceID.clone().unwrap_or_default();
-             (n, m, v, d, pid)
-        }
+#[tauri::command]
+fn set_affinity(pid: u32, cpus: Vec<u32>) -> bool {
+    Platform::set_affinity(pid, &cpus)
+}
 
-        if let Ok(gpus) = serde_json::from_str::<Vec<GpuInfoRaw>>(&output_str) {
-             if let Some(gpu) = gpus.first() {
-                 let (n, m, v, d, pid) = extract_data(gpu);
-                 name = n; memory = m; version = v; date_str = d; pnp_id = pid;
-             }
-        } else if let Ok(gpu) = serde_json::from_str::<GpuInfoRaw>(&output_str) {
-             let (n, m, v, d, pid) = extract_data(&gpu);
-             name = n; memory = m; version = v; date_str = d; pnp_id = pid;
-        }
-    }
+// Oldest start/exit events are dropped once the log holds this many.
+const PROCESS_EVENT_LOG_SIZE: usize = 10_000;
+const PROCESS_EVENT_PAGE_SIZE: usize = 500;
 
-    if !pnp_id.is_empty() {
-        let escaped_id = pnp_id.replace("\\", "\\\\");
-        let loc_output = Command::new("powershell")
-            .args([
-                "-NoProfile", 
-                "-Command", 
-                &format!(
-                    "Get-CimInstance Win32_PnPEntity -Filter \"DeviceID='{}'\" | Select-Object -ExpandProperty Location", 
-                    escaped_id
-                )
-            ])
-            .creation_flags(CREATE_NO_WINDOW)
-            .output();
-            
-        if let Ok(l_out) = loc_output {
-             let l_str = String::from_utf8_lossy(&l_out.stdout).trim().to_string();
-             if !l_str.is_empty() {
-                 location = l_str;
-             }
+// Samples of processes whose threads nobody has looked at for this long are dropped.
+const THREAD_SAMPLE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// CPU % is of the whole machine, like cpu_usage in get_processes, so a thread never
+// shows more than its process; it covers the time since the previous call for the same
+// process, and the first call reports zero.
+#[tauri::command]
+fn get_process_threads(state: State<'_, AppState>, pid: u32) -> Vec<ThreadInfo> {
+    let num_cores = state.sys.lock().unwrap().cpus().len().max(1) as f32;
+    let mut threads = Platform::get_threads(pid);
+    let now = std::time::Instant::now();
+
+    let mut samples = state.thread_samples.lock().unwrap();
+    samples.retain(|_, (at, _)| now.duration_since(*at) < THREAD_SAMPLE_TTL);
+    if let Some((at, previous)) = samples.get(&pid) {
+        let elapsed_ms = now.duration_since(*at).as_millis() as u64;
+        if elapsed_ms > 0 {
+            for thread in &mut threads {
+                if let Some(prev) = previous.get(&thread.tid) {
+                    thread.cpu_usage =
+                        thread.cpu_time.saturating_sub(*prev) as f32 * 100.0 / elapsed_ms as f32 / num_cores;
+                }
+            }
         }
     }
-    
-    let info = (name, memory, version, date_str, location);
-    *cache = Some(info.clone());
-    info
+    samples.insert(pid, (now, threads.iter().map(|t| (t.tid, t.cpu_time)).collect()));
+    threads
 }
 
-fn get_memory_config(state: &State<'_, AppState>, total_memory: u64, used_memory: u64) -> MemoryConfigInfo {
-    let mut cache = state.memory_info_cache.lock().unwrap();
-    if let Some(info) = cache.as_ref() {
-        let mut info = info.clone();
-        return info;
+#[tauri::command]
+fn kill_process_group(tid: u32) -> bool {
+    Platform::kill_process_group(tid)
+}
+
+#[tauri::command]
+fn get_process_modules(pid: u32) -> Vec<ModuleInfo> {
+    Platform::get_process_modules(pid)
+}
+
+#[tauri::command]
+fn get_process_memory_map(pid: u32) -> Vec<MemoryRegion> {
+    Platform::get_process_memory_map(pid)
+}
+
+#[tauri::command]
+fn get_process_open_files(pid: u32) -> Vec<OpenFile> {
+    Platform::get_process_open_files(pid)
+}
+
+// Matches the path itself and, for a directory, everything below it.
+#[tauri::command]
+fn find_file_holders(state: State<'_, AppState>, path_or_prefix: String) -> FileHolders {
+    let mut result = Platform::find_file_holders(&path_or_prefix);
+    let mut sys = state.sys.lock().unwrap();
+    refresh_process_list(&mut sys);
+    let name = |pid: u32| sys.process(Pid::from_u32(pid)).map(process_name);
+    for holder in &mut result.holders {
+        holder.process_name = name(holder.pid);
     }
+    for lock in &mut result.locks {
+        lock.process_name = lock.pid.and_then(name);
+    }
+    result
+}
 
-    use std::process::Command;
-    use std::os::windows::process::CommandExt;
-    
-    const CREATE_NO_WINDOW: u32 = 0x08000000;
+// Runs off the async runtime; progress arrives as "dump-progress" events and the result
+// is the size of the finished file.
+#[tauri::command]
+async fn create_process_dump(app: tauri::AppHandle, pid: u32, path: String, options: DumpOptions) -> Result<u64, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        Platform::create_process_dump(pid, std::path::Path::new(&path), &options, &mut |written, total| {
+            let _ = app.emit("dump-progress", DumpProgress { pid, written, total });
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
 
-    let output = Command::new("powershell")
-        .args([
-            "-NoProfile", 
-            "-Command", 
-            "Get-CimInstance Win32_PhysicalMemory | Select-Object Speed, FormFactor, Capacity | ConvertTo-Json -Compress"
-        ])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output();
-    
-    let mut speed_mhz = 0u32;
-    let mut slots_used = 0u32;
-    let mut form_factor = "Unknown".to_string();
-    let mut total_installed: u64 = 0;
+// The oldest events are dropped once the log is full; callers pass the id of the last
+// event they have seen and get at most a page of newer ones.
+#[tauri::command]
+fn get_process_events(state: State<'_, AppState>, since: u64) -> Vec<ProcessEvent> {
+    let log = state.process_events.lock().unwrap();
+    log.events
+        .iter()
+        .skip_while(|event| event.id <= since)
+        .take(PROCESS_EVENT_PAGE_SIZE)
+        .cloned()
+        .collect()
+}
 
-    if let Ok(output) = output {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        
-        #[derive(serde::Deserialize)]
-        struct MemModule {
-            Speed: Option<u32>,
-            FormFactor: Option<u32>,
-            Capacity: Option<u64>,
+fn record_process_event(app: &tauri::AppHandle, mut event: ProcessEvent) {
+    {
+        let state = app.state::<AppState>();
+        let mut log = state.process_events.lock().unwrap();
+        log.last_id += 1;
+        event.id = log.last_id;
+        if log.events.len() == PROCESS_EVENT_LOG_SIZE {
+            log.events.pop_front();
         }
+        log.events.push_back(event.clone());
+    }
+    let _ = app.emit("process-event", event);
+}
 
-        fn form_factor_to_string(ff: u32) -> String {
-            match ff {
-                0 => "Unknown".to_string(),
-                1 => "Other".to_string(),
-                2 => "SIP".to_string(),
-                3 => "DIP".to_string(),
-                4 => "ZIP".to_string(),
-                5 => "SOJ".to_string(),
-                6 => "Proprietary".to_string(),
-                7 => "SIMM".to_string(),
-                8 => "DIMM".to_string(),
-                9 => "TSOP".to_string(),
-                10 => "PGA".to_string(),
-                11 => "RIMM".to_string(),
-                12 => "SODIMM".to_string(),
-                13 => "SRIMM".to_string(),
-                14 => "SMD".to_string(),
-                15 => "SSMP".to_string(),
-                16 => "QFP".to_string(),
-                17 => "TQFP".to_string(),
-                18 => "SOIC".to_string(),
-                19 => "LCC".to_string(),
-                20 => "PLCC".to_string(),
-                21 => "BGA".to_string(),
-                22 => "FPBGA".to_string(),
-                23 => "LGA".to_string(),
-                _ => "Unknown".to_string(),
-            }
-        }
+#[tauri::command]
+fn get_thread_affinity(tid: u32) -> Option<Vec<u32>> {
+    Platform::get_thread_affinity(tid)
+}
 
-        if let Ok(modules) = serde_json::from_str::<Vec<MemModule>>(&output_str) {
-            slots_used = modules.len() as u32;
-            for module in &modules {
-                if let Some(spd) = module.Speed {
-                    if spd > speed_mhz {
-                        speed_mhz = spd;
-                    }
-                }
-                if let Some(ff) = module.FormFactor {
-                    if form_factor == "Unknown" || form_factor == "Other" {
-                        form_factor = form_factor_to_string(ff);
-                    }
-                }
-                if let Some(cap) = module.Capacity {
-                    total_installed += cap;
+#[tauri::command]
+fn set_thread_affinity(tid: u32, cpus: Vec<u32>) -> bool {
+    Platform::set_thread_affinity(tid, &cpus)
+}
+
+// Returns whether every targeted process was switched. The tree variant covers the
+// descendants as they are now; children started later inherit the Linux scheduling
+// policy and cgroup but not the Windows EcoQoS state.
+#[tauri::command]
+fn set_efficiency_mode(state: State<'_, AppState>, pid: u32, enabled: bool, include_tree: Option<bool>) -> bool {
+    let mut sys = state.sys.lock().unwrap();
+    refresh_process_list(&mut sys);
+
+    let root = Pid::from_u32(pid);
+    let targets = if include_tree.unwrap_or(false) {
+        descendants(&sys, root)
+    } else {
+        vec![root]
+    };
+
+    let mut efficiency = state.efficiency.lock().unwrap();
+    let limited: HashSet<u32> = state
+        .managed
+        .lock()
+        .unwrap()
+        .values()
+        .flat_map(|m| Platform::resource_usage(&m.group).pids)
+        .collect();
+    let mut all_ok = true;
+    for target in targets {
+        let Some(process) = sys.process(target) else {
+            all_ok = false;
+            continue;
+        };
+        let pid = target.as_u32();
+        let start_time = process.start_time();
+        let current = efficiency.get(&pid).filter(|(t, _)| *t == start_time).is_some();
+
+        let ok = match (enabled, current) {
+            (true, true) | (false, false) => true,
+            // A limited process stays in its limit cgroup; see in_efficiency_mode.
+            (true, false) if limited.contains(&pid) => false,
+            (true, false) => match Platform::enter_efficiency_mode(pid) {
+                Some(restore) => {
+                    efficiency.insert(pid, (start_time, restore));
+                    true
                 }
+                None => false,
+            },
+            (false, true) => {
+                let (_, restore) = efficiency.remove(&pid).unwrap();
+                Platform::leave_efficiency_mode(pid, &restore)
             }
-        } else if let Ok(module) = serde_json::from_str::<MemModule>(&output_str) {
-            slots_used = 1;
-            if let Some(spd) = module.Speed {
-                speed_mhz = spd;
-            }
-            if let Some(ff) = module.FormFactor {
-                form_factor = form_factor_to_string(ff);
-            }
-            if let Some(cap) = module.Capacity {
-                total_installed = cap;
-            }
-        }
+        };
+        all_ok &= ok;
     }
+    all_ok
+}
 
-    let slots_output = Command::new("powershell")
-        .args([
-            "-NoProfile", 
-            "-Command", 
-            "Get-CimInstance Win32_PhysicalMemoryArray | Select-Object MemoryDevices | ConvertTo-Json -Compress"
-        ])
-        .creation_flags(CREATE_NO_WINDOW)
-        .output();
-    
-    let mut slots_total = slots_used;
+// On Linux efficiency mode and resource limits each move the process into a cgroup of
+// their own, and entering one would silently undo the other, so a process gets one or
+// the other but never both.
+fn in_efficiency_mode(sys: &System, efficiency: &HashMap<u32, (u64, EfficiencyRestore)>, pid: u32) -> bool {
+    efficiency.get(&pid).is_some_and(|(start_time, _)| {
+        sys.process(Pid::from_u32(pid)).is_some_and(|p| p.start_time() == *start_time)
+    })
+}
 
-    if let Ok(output) = slots_output {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        
-        #[derive(serde::Deserialize)]
-        struct MemArray {
-            MemoryDevices: Option<u32>,
-        }
+fn descendants(sys: &System, root: Pid) -> Vec<Pid> {
+    let children = tree_children(sys);
+    let mut pids = vec![root];
+    let mut index = 0;
+    while index < pids.len() {
+        pids.extend(children.get(&pids[index]).into_iter().flatten().copied());
+        index += 1;
+    }
+    pids
+}
 
-        if let Ok(arr) = serde_json::from_str::<MemArray>(&output_str) {
-            if let Some(devices) = arr.MemoryDevices {
-                slots_total = devices;
-            }
-        } else if let Ok(arrays) = serde_json::from_str::<Vec<MemArray>>(&output_str) {
-            for arr in arrays {
-                if let Some(devices) = arr.MemoryDevices {
-                    slots_total = devices;
-                    break;
-                }
-            }
+fn managed_process_info(sys: &System, pid: u32, managed: &ManagedProcess) -> ManagedProcessInfo {
+    ManagedProcessInfo {
+        pid,
+        name: sys.process(Pid::from_u32(pid)).map(process_name),
+        limits: managed.limits.clone(),
+        usage: Platform::resource_usage(&managed.group),
+    }
+}
+
+// Calling this again for a process that is already limited just replaces its limits.
+#[tauri::command]
+fn limit_process(state: State<'_, AppState>, pid: u32, limits: ResourceLimits, include_tree: Option<bool>) -> bool {
+    let mut sys = state.sys.lock().unwrap();
+    refresh_process_list(&mut sys);
+    let Some(start_time) = sys.process(Pid::from_u32(pid)).map(|p| p.start_time()) else {
+        return false;
+    };
+
+    let efficiency = state.efficiency.lock().unwrap();
+    let mut managed = state.managed.lock().unwrap();
+    if let Some(existing) = managed.get_mut(&pid).filter(|m| m.start_time == start_time) {
+        let applied = Platform::apply_resource_limits(&existing.group, &limits);
+        if applied {
+            existing.limits = limits;
         }
+        return applied;
+    }
+
+    // A group left over from an earlier process with this pid must be gone before the new
+    // one is created, since the Linux cgroup is named after the pid.
+    if let Some(stale) = managed.remove(&pid) {
+        Platform::release_resource_group(stale.group);
     }
 
-    let hardware_reserved = if total_installed > total_memory {
-        total_installed - total_memory
+    let pids: Vec<u32> = if include_tree.unwrap_or(false) {
+        descendants(&sys, Pid::from_u32(pid)).into_iter().map(|p| p.as_u32()).collect()
     } else {
-        0
+        vec![pid]
     };
+    if pids.iter().any(|p| in_efficiency_mode(&sys, &efficiency, *p)) {
+        return false;
+    }
+    match Platform::create_resource_group(pid, &pids, &limits) {
+        Some(group) => {
+            managed.insert(pid, ManagedProcess { start_time, limits, group });
+            true
+        }
+        None => false,
+    }
+}
 
-    let info = MemoryConfigInfo {
-        speed_mhz,
-        slots_used,
-        slots_total,
-        form_factor,
-        hardware_reserved,
-    };
-    *cache = Some(info.clone());
-    info
+#[tauri::command]
+fn get_process_limits(state: State<'_, AppState>, pid: u32) -> Option<ManagedProcessInfo> {
+    let sys = state.sys.lock().unwrap();
+    let managed = state.managed.lock().unwrap();
+    managed.get(&pid).map(|m| managed_process_info(&sys, pid, m))
 }
 
+// Groups whose processes have all exited are released here rather than left behind.
 #[tauri::command]
-fn kill_process(state: State<'_, AppState>, pid: u32) -> bool {
+fn get_managed_processes(state: State<'_, AppState>) -> Vec<ManagedProcessInfo> {
     let sys = state.sys.lock().unwrap();
-    if let Some(process) = sys.processes().get(&Pid::from_u32(pid)) {
-        return process.kill();
+    let mut managed = state.managed.lock().unwrap();
+
+    let finished: Vec<u32> = managed
+        .iter()
+        .filter(|(_, m)| Platform::resource_usage(&m.group).pids.is_empty())
+        .map(|(pid, _)| *pid)
+        .collect();
+    for pid in finished {
+        if let Some(m) = managed.remove(&pid) {
+            Platform::release_resource_group(m.group);
+        }
     }
-    false
+
+    let mut result: Vec<ManagedProcessInfo> = managed
+        .iter()
+        .map(|(pid, m)| managed_process_info(&sys, *pid, m))
+        .collect();
+    result.sort_by_key(|m| m.pid);
+    result
+}
+
+#[tauri::command]
+fn release_process_limits(state: State<'_, AppState>, pid: u32) -> bool {
+    let managed = state.managed.lock().unwrap().remove(&pid);
+    match managed {
+        Some(m) => Platform::release_resource_group(m.group),
+        None => false,
+    }
+}
+
+// A parent that started after its child is a reused PID, not the real parent, so the
+// child is treated as an orphan. Orphans re-parented by the OS keep their new parent.
+fn parent_pid(sys: &System, process: &sysinfo::Process) -> Option<Pid> {
+    let parent = process.parent()?;
+    let parent_process = sys.process(parent)?;
+    (parent != process.pid() && parent_process.start_time() <= process.start_time()).then_some(parent)
+}
+
+// Linux reports every thread of a process as a process of its own; those stay out of the
+// tree, while kernel threads are listed like everywhere else.
+fn tree_children(sys: &System) -> HashMap<Pid, Vec<Pid>> {
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for (pid, process) in sys.processes() {
+        if process.thread_kind() == Some(ThreadKind::Userland) {
+            continue;
+        }
+        if let Some(parent) = parent_pid(sys, process) {
+            children.entry(parent).or_default().push(*pid);
+        }
+    }
+    for pids in children.values_mut() {
+        pids.sort_unstable();
+    }
+    children
+}
+
+fn build_tree_node(sys: &System, children: &HashMap<Pid, Vec<Pid>>, pid: Pid) -> Option<ProcessTreeNode> {
+    let process = sys.process(pid)?;
+    let num_cores = sys.cpus().len().max(1) as f32;
+    Some(ProcessTreeNode {
+        pid: pid.as_u32(),
+        name: process_name(process),
+        cpu_usage: process.cpu_usage() / num_cores,
+        memory: process.memory(),
+        start_time: process.start_time(),
+        children: children
+            .get(&pid)
+            .into_iter()
+            .flatten()
+            .filter_map(|child| build_tree_node(sys, children, *child))
+            .collect(),
+    })
+}
+
+#[tauri::command]
+fn get_process_tree(state: State<'_, AppState>) -> Vec<ProcessTreeNode> {
+    let mut sys = state.sys.lock().unwrap();
+    refresh_process_list(&mut sys);
+
+    let children = tree_children(&sys);
+    let mut roots: Vec<Pid> = sys
+        .processes()
+        .iter()
+        .filter(|(_, process)| process.thread_kind() != Some(ThreadKind::Userland) && parent_pid(&sys, process).is_none())
+        .map(|(pid, _)| *pid)
+        .collect();
+    roots.sort_unstable();
+    roots
+        .into_iter()
+        .filter_map(|pid| build_tree_node(&sys, &children, pid))
+        .collect()
+}
+
+// Children are killed before their parents so nothing gets re-parented mid-way. When
+// start_time is given it must match, so a PID reused since the UI last refreshed is left alone.
+#[tauri::command]
+fn kill_process_tree(state: State<'_, AppState>, pid: u32, start_time: Option<u64>) -> Vec<KillOutcome> {
+    let mut sys = state.sys.lock().unwrap();
+    refresh_process_list(&mut sys);
+
+    let root = Pid::from_u32(pid);
+    match sys.process(root) {
+        Some(process) if start_time.is_none_or(|t| t == process.start_time()) => {}
+        _ => return Vec::new(),
+    }
+
+    let children = tree_children(&sys);
+    let mut order = Vec::new();
+    let mut stack = vec![(root, false)];
+    while let Some((pid, expanded)) = stack.pop() {
+        if expanded {
+            order.push(pid);
+            continue;
+        }
+        stack.push((pid, true));
+        for child in children.get(&pid).into_iter().flatten() {
+            stack.push((*child, false));
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|pid| {
+            let process = sys.process(pid)?;
+            Some(KillOutcome {
+                pid: pid.as_u32(),
+                name: process_name(process),
+                killed: process.kill(),
+            })
+        })
+        .collect()
 }
 
 #[derive(serde::Serialize)]
@@ -977,6 +1819,7 @@ struct AppHistoryInfo {
     cpu_time_ms: u64,
     network_bytes: u64,
     disk_bytes: u64,
+    peak_memory: u64,
     icon: Option<String>,
 }
 
@@ -990,6 +1833,7 @@ fn get_app_history(state: State<'_, AppState>) -> Vec<AppHistoryInfo> {
             cpu_time_ms: entry.cpu_time_ms,
             network_bytes: entry.network_bytes,
             disk_bytes: entry.disk_bytes,
+            peak_memory: entry.peak_memory,
             icon: entry.icon.clone(),
         })
         .collect();
@@ -1022,7 +1866,10 @@ fn get_user_sessions(state: State<'_, AppState>) -> Vec<UserSessionInfo> {
     use std::collections::HashMap;
     
     let mut users: HashMap<String, UserSessionInfo> = HashMap::new();
-    let current_user = std::env::var("USERNAME").unwrap_or_default().to_lowercase();
+    let current_user = std::env::var("USERNAME")
+        .or_else(|_| std::env::var("USER"))
+        .unwrap_or_default()
+        .to_lowercase();
     let domain = std::env::var("USERDOMAIN").unwrap_or_else(|_| "LOCAL".to_string());
     
     let output = Command::new("query")
@@ -1065,7 +1912,7 @@ fn get_user_sessions(state: State<'_, AppState>) -> Vec<UserSessionInfo> {
     }
     
     if users.is_empty() {
-        if let Ok(username) = std::env::var("USERNAME") {
+        if let Ok(username) = std::env::var("USERNAME").or_else(|_| std::env::var("USER")) {
             let username_key = username.to_lowercase();
             users.insert(username_key, UserSessionInfo {
                 username: username.clone(),
@@ -1080,51 +1927,15 @@ fn get_user_sessions(state: State<'_, AppState>) -> Vec<UserSessionInfo> {
         }
     }
     
-    let mut sys = state.sys.lock().unwrap();
-    sys.refresh_all();
-    let num_cores = sys.cpus().len() as f32;
-    let app_pids = get_app_pids();
-    let mut icon_cache = state.icon_cache.lock().unwrap();
-
-    for (pid, process) in sys.processes() {
-        if let Some(user) = users.get_mut(&current_user) {
-            let cpu = if num_cores > 0.0 {
-                process.cpu_usage() / num_cores
-            } else {
-                process.cpu_usage()
-            };
-            
-            let is_app = app_pids.contains(&pid.as_u32());
-            let mut icon = None;
-            if is_app {
-                if let Some(exe_path) = process.exe() {
-                    let path_str = exe_path.to_string_lossy().into_owned();
-                    if let Some(cached) = icon_cache.get(&path_str) {
-                        icon = Some(cached.clone());
-                    } else if let Some(extracted) = extract_icon_base64(&path_str) {
-                        icon = Some(extracted.clone());
-                        icon_cache.insert(path_str, extracted);
-                    }
-                }
-            }
-
-            user.cpu_usage += cpu;
-            user.memory_bytes += process.memory();
-            user.process_count += 1;
-            user.processes.push(ProcessInfo {
-                pid: pid.as_u32(),
-                name: process.name().to_string_lossy().into_owned(),
-                cpu_usage: cpu,
-                memory: process.memory(),
-                disk_usage: 0,
-                network_usage: 0,
-                gpu_usage: 0.0,
-                is_app,
-                icon,
-            });
-        }
+    // All processes are counted for the signed-in user.
+    let processes = latest_snapshot(&state).processes;
+    if let Some(user) = users.get_mut(&current_user) {
+        user.cpu_usage = processes.iter().map(|p| p.cpu_usage).sum();
+        user.memory_bytes = processes.iter().map(|p| p.memory).sum();
+        user.process_count = processes.len() as u32;
+        user.processes = processes;
     }
-    
+
     for user in users.values_mut() {
         user.processes.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
     }
@@ -1149,6 +1960,7 @@ struct StartupData {
     last_bios_time: u64,
 }
 
+#[cfg(target_os = "windows")]
 #[tauri::command]
 async fn get_startup_apps(state: State<'_, AppState>) -> Result<StartupData, String> {
     use winreg::enums::*;
@@ -1179,7 +1991,7 @@ async fn get_startup_apps(state: State<'_, AppState>) -> Result<StartupData, Str
 
     let mut apps: Vec<StartupApp> = Vec::new();
     let mut paths_to_query: Vec<String> = Vec::new();
-    let mut icon_cache = state.icon_cache.lock().unwrap();
+    let icons = state.icons.get();
 
     fn local_clean_path(p: &str) -> String {
         let p = p.trim();
@@ -1216,7 +2028,7 @@ async fn get_startup_apps(state: State<'_, AppState>) -> Result<StartupData, Str
             }
         }
 
-        let icon = get_cached_icon(&clean_path, &mut icon_cache);
+        let icon = icons.and_then(|icons| icons.url_now(&clean_path));
         if !clean_path.is_empty() && !clean_path.contains("System32") {
             paths_to_query.push(clean_path.clone());
         }
@@ -1288,19 +2100,16 @@ async fn get_startup_apps(state: State<'_, AppState>) -> Result<StartupData, Str
     })
 }
 
-fn get_cached_icon(path: &str, cache: &mut HashMap<String, String>) -> Option<String> {
-    if let Some(cached) = cache.get(path) {
-        return Some(cached.clone());
-    }
-    
-    if let Some(icon) = extract_icon_base64(path) {
-        cache.insert(path.to_string(), icon.clone());
-        return Some(icon);
-    }
-    
-    None
+#[cfg(not(target_os = "windows"))]
+#[tauri::command]
+async fn get_startup_apps() -> Result<StartupData, String> {
+    Ok(StartupData {
+        apps: Vec::new(),
+        last_bios_time: 0,
+    })
 }
 
+#[cfg(target_os = "windows")]
 #[tauri::command]
 fn toggle_startup_app(name: String, enabled: bool) -> bool {
     use winreg::enums::*;
@@ -1332,6 +2141,13 @@ fn toggle_startup_app(name: String, enabled: bool) -> bool {
     false
 }
 
+#[cfg(not(target_os = "windows"))]
+#[tauri::command]
+fn toggle_startup_app(_name: String, _enabled: bool) -> bool {
+    false
+}
+
+#[cfg(target_os = "windows")]
 #[tauri::command]
 fn set_auto_start(enabled: bool) -> bool {
     use winreg::enums::*;
@@ -1356,6 +2172,12 @@ fn set_auto_start(enabled: bool) -> bool {
     false
 }
 
+#[cfg(not(target_os = "windows"))]
+#[tauri::command]
+fn set_auto_start(_enabled: bool) -> bool {
+    false
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 struct ServiceInfo {
     name: String,
@@ -1366,61 +2188,12 @@ struct ServiceInfo {
 
 #[tauri::command]
 fn get_services() -> Vec<ServiceInfo> {
-    use std::process::Command;
-    
-    let output = Command::new("powershell")
-        .args([
-            "-NoProfile",
-            "-Command",
-            r#"Get-CimInstance -ClassName Win32_Service | Select-Object @{N='name';E={$_.Name}}, @{N='pid';E={$_.ProcessId}}, @{N='description';E={$_.DisplayName}}, @{N='status';E={$_.State}} | ConvertTo-Json -Compress"#
-        ])
-        .output();
-    
-    if let Ok(output) = output {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if let Ok(services) = serde_json::from_str::<Vec<ServiceInfo>>(&stdout) {
-            return services;
-        } else if let Ok(service) = serde_json::from_str::<ServiceInfo>(&stdout) {
-            return vec![service];
-        }
-    }
-    
-    Vec::new()
+    Platform::get_services()
 }
 
-
 #[tauri::command]
 fn manage_service(name: String, action: String) -> bool {
-    use std::process::Command;
-    
-    let _cmd = match action.as_str() {
-        "start" => "start",
-        "stop" => "stop",
-        "restart" => "restart",
-        _ => return false,
-    };
-    
-    let shell_cmd = match action.as_str() {
-        "restart" => format!("Restart-Service -Name '{}' -Force", name),
-        "open_msc" => "services.msc".to_string(),
-        _ => format!("{}-Service -Name '{}' -Force", action.to_uppercase(), name),
-    };
-
-    let output = if action == "open_msc" {
-        Command::new("cmd")
-            .args(["/c", "start", "services.msc"])
-            .output()
-    } else {
-        Command::new("powershell")
-            .args([
-                "-NoProfile",
-                "-Command",
-                &shell_cmd
-            ])
-            .output()
-    };
-    
-    output.is_ok() && output.unwrap().status.success()
+    Platform::manage_service(&name, &action)
 }
 
 use tauri::Manager;
@@ -1434,17 +2207,45 @@ fn set_always_on_top(window: tauri::Window, on_top: bool) -> bool {
     window.set_always_on_top(on_top).is_ok()
 }
 
+// icon://localhost/<key>.<ext>; a key changes whenever the executable does, so the
+// webview may keep an icon for good.
+fn serve_icon(app: &tauri::AppHandle, request: &tauri::http::Request<Vec<u8>>) -> tauri::http::Response<Vec<u8>> {
+    let name = request.uri().path().trim_start_matches('/');
+    let icon = app.state::<AppState>().icons.get().and_then(|icons| icons.read(name));
+    let response = match icon {
+        Some((bytes, mime)) => tauri::http::Response::builder()
+            .header(tauri::http::header::CONTENT_TYPE, mime)
+            .header(tauri::http::header::CACHE_CONTROL, "max-age=31536000, immutable")
+            .body(bytes),
+        None => tauri::http::Response::builder()
+            .status(tauri::http::StatusCode::NOT_FOUND)
+            .body(Vec::new()),
+    };
+    response.unwrap()
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_process::init())
+        .register_uri_scheme_protocol("icon", |ctx, request| serve_icon(ctx.app_handle(), &request))
         .setup(|app| {
             let window = app.get_webview_window("main").unwrap();
             #[cfg(target_os = "macos")]
             apply_vibrancy(&window, NSVisualEffectMaterial::HudWindow, None, None).ok();
             #[cfg(target_os = "windows")]
             apply_acrylic(&window, Some((21, 24, 30, 128))).ok();
+
+            let icon_dir = app.path().app_cache_dir()?.join("icons");
+            let _ = app.state::<AppState>().icons.set(icons::IconCache::open(icon_dir, Platform::extract_icon));
+
+            let handle = app.handle().clone();
+            std::thread::spawn(move || {
+                Platform::watch_processes(&mut |event| record_process_event(&handle, event));
+            });
+            let handle = app.handle().clone();
+            std::thread::spawn(move || run_sampler(handle));
             Ok(())
         })
         .manage(AppState {
@@ -1453,19 +2254,67 @@ pub fn run() {
             gpu_monitor: Mutex::new(GpuMonitor::new()),
             network_monitor: Mutex::new(NetworkMonitor::new()),
             system_metrics_monitor: Mutex::new(SystemMetricsMonitor::new()),
-            icon_cache: Mutex::new(HashMap::new()),
+            icons: std::sync::OnceLock::new(),
+            users: Mutex::new(Users::new_with_refreshed_list()),
+            identity_cache: Mutex::new(HashMap::new()),
+            thread_samples: Mutex::new(HashMap::new()),
+            suspended: Mutex::new(HashMap::new()),
+            efficiency: Mutex::new(HashMap::new()),
+            managed: Mutex::new(HashMap::new()),
             app_history: Mutex::new(HashMap::new()),
+            memory_metric: Mutex::new("rss".to_string()),
+            memory_breakdowns: Mutex::new(MemoryBreakdownCache::default()),
+            process_events: Mutex::new(ProcessEventLog::default()),
+            update_interval: Mutex::new(Some(std::time::Duration::from_millis(1000))),
+            snapshot: Mutex::new(None),
+            process_feed: Mutex::new(ProcessFeed::default()),
+            metric_history: Mutex::new(history::MetricStore::default()),
             gpu_info_cache: Mutex::new(None),
             memory_info_cache: Mutex::new(None),
+            cpu_topology_cache: Mutex::new(None),
             last_update: Mutex::new(std::time::Instant::now()),
         })
         .invoke_handler(tauri::generate_handler![
             get_processes, 
+            set_update_speed,
+            subscribe_processes,
+            resync_processes,
+            unsubscribe_processes,
+            get_process_details,
             kill_process, 
+            get_process_tree,
+            kill_process_tree,
+            suspend_process,
+            resume_process,
+            get_process_scheduling,
+            set_process_scheduling,
+            get_affinity,
+            set_affinity,
+            get_process_threads,
+            kill_process_group,
+            get_process_modules,
+            get_process_memory_map,
+            get_process_open_files,
+            find_file_holders,
+            create_process_dump,
+            get_process_events,
+            get_metric_history,
+            get_thread_affinity,
+            set_thread_affinity,
+            set_efficiency_mode,
+            limit_process,
+            get_process_limits,
+            get_managed_processes,
+            release_process_limits,
+            get_connections,
+            get_process_connections,
+            get_listening_ports,
+            find_port_owners,
             get_startup_apps, 
             toggle_startup_app, 
             get_app_history, 
             clear_app_history, 
+            set_memory_metric,
             get_user_sessions,
             get_services,
             manage_service,
@@ -1475,3 +2324,101 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            parent_pid: Some(1),
+            name: "bash".to_string(),
+            status: "sleeping".to_string(),
+            nice: 0,
+            priority_class: "normal".to_string(),
+            sched_policy: "other".to_string(),
+            io_class: "best_effort".to_string(),
+            efficiency_mode: false,
+            user: Some("alice".to_string()),
+            command_line: "/usr/bin/bash".to_string(),
+            exe_path: Some("/usr/bin/bash".to_string()),
+            start_time: 1_700_000_000,
+            session_id: Some(2),
+            architecture: None,
+            elevated: Some(false),
+            cpu_usage: 0.5,
+            memory: 4096,
+            rss: 4096,
+            memory_breakdown: None,
+            disk_usage: 0,
+            net_rx_bps: 0,
+            net_tx_bps: 0,
+            gpu_usage: 0.0,
+            gpu_engine: None,
+            is_app: false,
+            icon: None,
+        }
+    }
+
+    fn response(processes: Vec<ProcessInfo>) -> ProcessesResponse {
+        ProcessesResponse { processes, stats: SystemStats::default() }
+    }
+
+    #[test]
+    fn unchanged_process_sends_nothing() {
+        assert!(process_change(&process(10), &process(10)).is_none());
+    }
+
+    #[test]
+    fn change_carries_only_what_changed() {
+        let previous = process(10);
+        let mut current = process(10);
+        current.cpu_usage = 12.5;
+        current.status = "running".to_string();
+        current.gpu_engine = Some("3D".to_string());
+        let change = process_change(&previous, &current).unwrap();
+        assert_eq!(change.status.as_deref(), Some("running"));
+        assert_eq!(change.gpu_engine, Some(Some("3D".to_string())));
+        assert_eq!(change.memory, None);
+
+        let json = serde_json::to_value(&change).unwrap();
+        let mut keys: Vec<&str> = json.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["cpu_usage", "gpu_engine", "pid", "status"]);
+    }
+
+    #[test]
+    fn cleared_optional_value_is_sent_as_null() {
+        let mut previous = process(10);
+        previous.gpu_engine = Some("Video Decode".to_string());
+        let change = process_change(&previous, &process(10)).unwrap();
+        let json = serde_json::to_value(&change).unwrap();
+        assert!(json["gpu_engine"].is_null());
+        assert!(json.as_object().unwrap().contains_key("gpu_engine"));
+    }
+
+    #[test]
+    fn delta_splits_added_removed_and_changed() {
+        let previous = response(vec![process(10), process(11), process(12)]);
+        let mut still_running = process(10);
+        still_running.memory = 8192;
+        // pid 11 was reused by a process started later
+        let mut reused = process(11);
+        reused.start_time += 60;
+        let current = response(vec![still_running, reused, process(13)]);
+
+        match process_delta(&previous, &current, 4) {
+            ProcessUpdate::Delta { version, base_version, added, removed, changed, .. } => {
+                assert_eq!((version, base_version), (5, 4));
+                let added: Vec<u32> = added.iter().map(|p| p.pid).collect();
+                assert_eq!(added, [11, 13]);
+                assert_eq!(removed, [12]);
+                assert_eq!(changed.len(), 1);
+                assert_eq!(changed[0].pid, 10);
+                assert_eq!(changed[0].memory, Some(8192));
+            }
+            ProcessUpdate::Full { .. } => panic!("expected a delta"),
+        }
+    }
+}
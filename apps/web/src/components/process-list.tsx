@@ -7,6 +7,7 @@ import {
   ArrowUp,
   ArrowDown,
   AppWindow,
+  Leaf,
 } from "lucide-react";
 import { useSidebar } from "@/components/ui/sidebar";
 import {
@@ -22,8 +23,12 @@ interface ProcessInfo {
   cpu_usage: number;
   memory: number;
   disk_usage: number;
-  network_usage: number;
+  net_rx_bps: number;
+  net_tx_bps: number;
   gpu_usage: number;
+  nice: number;
+  priority_class: string;
+  efficiency_mode: boolean;
   is_app: boolean;
   icon?: string;
 }
@@ -36,6 +41,9 @@ interface ProcessGroup {
   totalDisk: number;
   totalNetwork: number;
   totalGpu: number;
+  nice: number;
+  priorityClass: string;
+  efficiencyMode: boolean;
   icon?: string;
   is_app: boolean;
 }
@@ -55,6 +63,7 @@ interface ProcessListProps {
   onSelect: (pid: number) => void;
   systemStats: SystemStats | null;
   onKill?: (pid: number) => void;
+  onSetEfficiencyMode?: (pid: number, enabled: boolean) => void;
 }
 
 const getUsageBackground = (usage: number, maxUsage: number = 100) => {
@@ -78,6 +87,7 @@ export function ProcessList({
   onSelect,
   systemStats,
   onKill,
+  onSetEfficiencyMode,
 }: ProcessListProps) {
   const { open } = useSidebar();
   const [isSmallWindow, setIsSmallWindow] = useState(false);
@@ -109,6 +119,12 @@ export function ProcessList({
     return mb.toFixed(1) + " MB/s";
   };
 
+  const formatPriority = (priorityClass: string) =>
+    priorityClass
+      .split("_")
+      .map((word) => word.charAt(0).toUpperCase() + word.slice(1))
+      .join(" ");
+
   const formatNetwork = (bytes: number) => {
     const mbps = (bytes * 8) / 1_000_000;
     return mbps.toFixed(1) + " Mbps";
@@ -142,9 +158,14 @@ export function ProcessList({
         existing.totalCpu += proc.cpu_usage;
         existing.totalMemory += proc.memory;
         existing.totalDisk += proc.disk_usage;
-        existing.totalNetwork += proc.network_usage;
+        existing.totalNetwork += proc.net_rx_bps + proc.net_tx_bps;
         existing.totalGpu += proc.gpu_usage;
+        if (proc.nice < existing.nice) {
+          existing.nice = proc.nice;
+          existing.priorityClass = proc.priority_class;
+        }
         if (proc.is_app) existing.is_app = true;
+        if (proc.efficiency_mode) existing.efficiencyMode = true;
         if (!existing.icon && proc.icon) {
           existing.icon = proc.icon;
         } else if (proc.is_app && proc.icon) {
@@ -157,8 +178,11 @@ export function ProcessList({
           totalCpu: proc.cpu_usage,
           totalMemory: proc.memory,
           totalDisk: proc.disk_usage,
-          totalNetwork: proc.network_usage,
+          totalNetwork: proc.net_rx_bps + proc.net_tx_bps,
           totalGpu: proc.gpu_usage,
+          nice: proc.nice,
+          priorityClass: proc.priority_class,
+          efficiencyMode: proc.efficiency_mode,
           icon: proc.icon,
           is_app: proc.is_app,
         });
@@ -189,7 +213,7 @@ export function ProcessList({
           aVal = a.totalDisk;
           bVal = b.totalDisk;
           break;
-        case "network_usage":
+        case "net_rx_bps":
           aVal = a.totalNetwork;
           bVal = b.totalNetwork;
           break;
@@ -197,6 +221,10 @@ export function ProcessList({
           aVal = a.totalGpu;
           bVal = b.totalGpu;
           break;
+        case "nice":
+          aVal = -a.nice;
+          bVal = -b.nice;
+          break;
         default:
           aVal = a.name;
           bVal = b.name;
@@ -259,7 +287,11 @@ export function ProcessList({
                   </span>
                 </div>
               </td>
-              <td className="px-3.5 py-0 text-[13px] text-muted-foreground text-left w-[70px]"></td>
+              <td className="px-3.5 py-0 text-[13px] text-muted-foreground text-left w-[70px]">
+                {proc.efficiency_mode && (
+                  <Leaf className="h-3.5 w-3.5 text-emerald-500" />
+                )}
+              </td>
               <td
                 className="px-3.5 py-0 text-[13px] text-foreground text-right tabular-nums w-[100px]"
                 style={{ backgroundColor: cpuBg }}
@@ -278,11 +310,14 @@ export function ProcessList({
               {showExtraColumns && (
                 <>
                   <td className="px-4 py-0 text-[13px] text-muted-foreground text-right tabular-nums w-[110px]">
-                    {formatNetwork(proc.network_usage)}
+                    {formatNetwork(proc.net_rx_bps + proc.net_tx_bps)}
                   </td>
                   <td className="px-4 py-0 text-[13px] text-muted-foreground text-right tabular-nums w-[75px]">
                     {proc.gpu_usage.toFixed(0)} %
                   </td>
+                  <td className="px-4 py-0 text-[13px] text-muted-foreground text-right w-[110px]">
+                    {formatPriority(proc.priority_class)}
+                  </td>
                 </>
               )}
             </tr>
@@ -297,6 +332,17 @@ export function ProcessList({
             <span>End task</span>
             <span className="ml-auto text-xs text-muted-foreground">Del</span>
           </ContextMenuItem>
+          <ContextMenuItem
+            onClick={() =>
+              onSetEfficiencyMode?.(proc.pid, !proc.efficiency_mode)
+            }
+            className="gap-2 focus:bg-surface-hover focus:text-foreground"
+          >
+            <Leaf className="h-3.5 w-3.5" />
+            <span>
+              {proc.efficiency_mode ? "Exit efficiency mode" : "Efficiency mode"}
+            </span>
+          </ContextMenuItem>
         </ContextMenuContent>
       </ContextMenu>
     );
@@ -365,7 +411,11 @@ export function ProcessList({
                   </span>
                 </div>
               </td>
-              <td className="px-3.5 py-0 text-[13px] text-muted-foreground text-left w-[70px]"></td>
+              <td className="px-3.5 py-0 text-[13px] text-muted-foreground text-left w-[70px]">
+                {group.efficiencyMode && (
+                  <Leaf className="h-3.5 w-3.5 text-emerald-500" />
+                )}
+              </td>
               <td
                 className="px-3.5 py-0 text-[13px] text-foreground text-right tabular-nums w-[100px]"
                 style={{ backgroundColor: cpuBg }}
@@ -389,6 +439,9 @@ export function ProcessList({
                   <td className="px-4 py-0 text-[13px] text-muted-foreground text-right tabular-nums w-[75px]">
                     {group.totalGpu.toFixed(0)} %
                   </td>
+                  <td className="px-4 py-0 text-[13px] text-muted-foreground text-right w-[110px]">
+                    {formatPriority(group.priorityClass)}
+                  </td>
                 </>
               )}
             </tr>
@@ -405,6 +458,17 @@ export function ProcessList({
             </span>
             <span className="ml-auto text-xs text-muted-foreground">Del</span>
           </ContextMenuItem>
+          <ContextMenuItem
+            onClick={() =>
+              onSetEfficiencyMode?.(mainProcess.pid, !group.efficiencyMode)
+            }
+            className="gap-2 focus:bg-surface-hover focus:text-foreground"
+          >
+            <Leaf className="h-3.5 w-3.5" />
+            <span>
+              {group.efficiencyMode ? "Exit efficiency mode" : "Efficiency mode"}
+            </span>
+          </ContextMenuItem>
         </ContextMenuContent>
       </ContextMenu>,
     );
@@ -427,7 +491,7 @@ export function ProcessList({
       className="h-[34px] border-b border-border-subtle/50 hover:bg-surface-hover cursor-pointer transition-colors"
       onClick={onToggle}
     >
-      <td colSpan={showExtraColumns ? 7 : 5} className="px-3.5">
+      <td colSpan={showExtraColumns ? 8 : 5} className="px-3.5">
         <div className="flex items-center gap-2">
           {expanded ? (
             <ChevronDown className="h-3.5 w-3.5 text-muted-foreground" />
@@ -499,10 +563,10 @@ export function ProcessList({
               <>
                 <th
                   className="px-3.5 font-normal text-[12px] text-muted-foreground hover:bg-surface-hover cursor-pointer transition-colors text-right w-[110px]"
-                  onClick={() => handleSort("network_usage")}
+                  onClick={() => handleSort("net_rx_bps")}
                 >
                   <div className="flex items-center justify-end whitespace-nowrap">
-                    Network <SortIndicator columnKey="network_usage" />
+                    Network <SortIndicator columnKey="net_rx_bps" />
                   </div>
                 </th>
                 <th
@@ -513,6 +577,14 @@ export function ProcessList({
                     GPU <SortIndicator columnKey="gpu_usage" />
                   </div>
                 </th>
+                <th
+                  className="px-3.5 font-normal text-[12px] text-muted-foreground hover:bg-surface-hover cursor-pointer transition-colors text-right w-[110px]"
+                  onClick={() => handleSort("nice")}
+                >
+                  <div className="flex items-center justify-end whitespace-nowrap">
+                    Priority <SortIndicator columnKey="nice" />
+                  </div>
+                </th>
               </>
             )}
           </tr>
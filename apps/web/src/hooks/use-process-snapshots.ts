@@ -0,0 +1,115 @@
+import { useEffect, useRef } from "react";
+import { Channel, invoke } from "@tauri-apps/api/core";
+import { decodeMsgpack } from "@/lib/msgpack";
+
+type ProcessEntry = { pid: number } & Record<string, unknown>;
+
+type ProcessUpdate =
+  | {
+      kind: "full";
+      version: number;
+      processes: ProcessEntry[];
+      stats: unknown;
+    }
+  | {
+      kind: "delta";
+      version: number;
+      base_version: number;
+      added: ProcessEntry[];
+      removed: number[];
+      changed: ProcessEntry[];
+      stats: unknown;
+    };
+
+// The backend samples on its own interval and streams versioned updates over a channel:
+// a full list first, then only what changed. This keeps the merged list and hands every
+// resulting snapshot over; on a version gap it asks for the full list again.
+export function useProcessSnapshots<T>(
+  onSnapshot: (snapshot: T) => void,
+  onError?: (error: unknown) => void,
+) {
+  const handlers = useRef({ onSnapshot, onError });
+  handlers.current = { onSnapshot, onError };
+
+  useEffect(() => {
+    let active = true;
+    let subscription: number | null = null;
+    let version: number | null = null;
+    let resyncing = false;
+    const processes = new Map<number, ProcessEntry>();
+
+    const fail = (e: unknown) => {
+      console.error(e);
+      handlers.current.onError?.(e);
+    };
+
+    const resync = () => {
+      version = null;
+      if (subscription === null || resyncing) return;
+      resyncing = true;
+      invoke("resync_processes", { id: subscription })
+        .catch(fail)
+        .finally(() => (resyncing = false));
+    };
+
+    const apply = (update: ProcessUpdate) => {
+      if (update.kind === "full") {
+        processes.clear();
+        for (const process of update.processes) {
+          processes.set(process.pid, process);
+        }
+      } else {
+        if (update.base_version !== version) {
+          resync();
+          return;
+        }
+        for (const pid of update.removed) processes.delete(pid);
+        for (const process of update.added) processes.set(process.pid, process);
+        for (const change of update.changed) {
+          const process = processes.get(change.pid);
+          if (process) processes.set(change.pid, { ...process, ...change });
+        }
+      }
+      version = update.version;
+      handlers.current.onSnapshot({
+        processes: Array.from(processes.values()),
+        stats: update.stats,
+      } as T);
+    };
+
+    const channel = new Channel<ArrayBuffer | ProcessUpdate>();
+    channel.onmessage = (message) => {
+      if (!active) return;
+      try {
+        apply(
+          message instanceof ArrayBuffer
+            ? (decodeMsgpack(message) as ProcessUpdate)
+            : message,
+        );
+      } catch (e) {
+        fail(e);
+        resync();
+      }
+    };
+
+    invoke<number>("subscribe_processes", { channel, encoding: "msgpack" })
+      .then((id) => {
+        if (!active) {
+          invoke("unsubscribe_processes", { id }).catch(console.error);
+          return;
+        }
+        subscription = id;
+        if (version === null) resync();
+      })
+      .catch(fail);
+
+    return () => {
+      active = false;
+      if (subscription !== null) {
+        invoke("unsubscribe_processes", { id: subscription }).catch(
+          console.error,
+        );
+      }
+    };
+  }, []);
+}
@@ -0,0 +1,57 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export interface MetricPoint {
+  // unix time in ms; recorded points are the start of the second they average
+  timestamp: number;
+  value: number;
+}
+
+interface MetricHistory {
+  metric_id: string;
+  resolution: number;
+  points: MetricPoint[];
+}
+
+// The last `points` seconds the backend recorded for a metric, one value per second, so
+// graphs start out filled instead of empty; an empty list if nothing was recorded yet.
+export async function fetchMetricHistory(
+  metricId: string,
+  points: number,
+): Promise<MetricPoint[]> {
+  try {
+    const history = await invoke<MetricHistory>("get_metric_history", {
+      metricId,
+      range: points,
+      resolution: 1,
+    });
+    return history.points.slice(-points);
+  } catch {
+    return [];
+  }
+}
+
+export function appendPoint(
+  points: MetricPoint[],
+  value: number,
+  max: number,
+): MetricPoint[] {
+  return [...points, { timestamp: Date.now(), value }].slice(-max);
+}
+
+// Live points keep arriving while the history is fetched; the ones from after the last
+// recorded second are kept after it rather than thrown away.
+export function mergeHistory(
+  recorded: MetricPoint[],
+  live: MetricPoint[],
+  max: number,
+): MetricPoint[] {
+  if (recorded.length === 0) return live;
+  const recordedUntil = recorded[recorded.length - 1].timestamp + 1000;
+  return [
+    ...recorded,
+    ...live.filter((p) => p.timestamp >= recordedUntil),
+  ].slice(-max);
+}
+
+export const pointValues = (points: MetricPoint[]) =>
+  points.map((p) => p.value);
@@ -0,0 +1,93 @@
+// Decodes the MessagePack the backend produces with rmp_serde::to_vec_named: maps with
+// string keys, arrays, strings, numbers, booleans, nil and bin. Extension types are
+// never sent, so they are rejected.
+export function decodeMsgpack(data: ArrayBuffer | Uint8Array): unknown {
+  const bytes = data instanceof Uint8Array ? data : new Uint8Array(data);
+  const view = new DataView(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+  const text = new TextDecoder();
+  let offset = 0;
+
+  const take = (length: number) => {
+    const start = offset;
+    offset += length;
+    if (offset > bytes.length) throw new Error("truncated msgpack data");
+    return start;
+  };
+  const str = (length: number) =>
+    text.decode(bytes.subarray(take(length), offset));
+  const bin = (length: number) => bytes.slice(take(length), offset);
+  const array = (length: number) => {
+    const result = new Array(length);
+    for (let i = 0; i < length; i++) result[i] = value();
+    return result;
+  };
+  const map = (length: number) => {
+    const result: Record<string, unknown> = {};
+    for (let i = 0; i < length; i++) {
+      const key = String(value());
+      result[key] = value();
+    }
+    return result;
+  };
+
+  const value = (): unknown => {
+    const type = view.getUint8(take(1));
+    if (type <= 0x7f) return type;
+    if (type <= 0x8f) return map(type & 0x0f);
+    if (type <= 0x9f) return array(type & 0x0f);
+    if (type <= 0xbf) return str(type & 0x1f);
+    if (type >= 0xe0) return type - 0x100;
+    switch (type) {
+      case 0xc0:
+        return null;
+      case 0xc2:
+        return false;
+      case 0xc3:
+        return true;
+      case 0xc4:
+        return bin(view.getUint8(take(1)));
+      case 0xc5:
+        return bin(view.getUint16(take(2)));
+      case 0xc6:
+        return bin(view.getUint32(take(4)));
+      case 0xca:
+        return view.getFloat32(take(4));
+      case 0xcb:
+        return view.getFloat64(take(8));
+      case 0xcc:
+        return view.getUint8(take(1));
+      case 0xcd:
+        return view.getUint16(take(2));
+      case 0xce:
+        return view.getUint32(take(4));
+      case 0xcf:
+        return Number(view.getBigUint64(take(8)));
+      case 0xd0:
+        return view.getInt8(take(1));
+      case 0xd1:
+        return view.getInt16(take(2));
+      case 0xd2:
+        return view.getInt32(take(4));
+      case 0xd3:
+        return Number(view.getBigInt64(take(8)));
+      case 0xd9:
+        return str(view.getUint8(take(1)));
+      case 0xda:
+        return str(view.getUint16(take(2)));
+      case 0xdb:
+        return str(view.getUint32(take(4)));
+      case 0xdc:
+        return array(view.getUint16(take(2)));
+      case 0xdd:
+        return array(view.getUint32(take(4)));
+      case 0xde:
+        return map(view.getUint16(take(2)));
+      case 0xdf:
+        return map(view.getUint32(take(4)));
+      default:
+        throw new Error(`unsupported msgpack type 0x${type.toString(16)}`);
+    }
+  };
+
+  return value();
+}
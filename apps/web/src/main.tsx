@@ -1,5 +1,6 @@
 import { RouterProvider, createRouter } from "@tanstack/react-router";
 import ReactDOM from "react-dom/client";
+import { invoke } from "@tauri-apps/api/core";
 
 import Loader from "./components/loader";
 import { routeTree } from "./routeTree.gen";
@@ -22,7 +23,7 @@ const rootElement = document.getElementById("app");
 const savedSettings = localStorage.getItem("taskManagerSettings");
 if (savedSettings) {
   try {
-    const { theme } = JSON.parse(savedSettings);
+    const { theme, memoryMetric, updateSpeed } = JSON.parse(savedSettings);
     document.documentElement.classList.remove("light", "dark");
     if (theme === "system") {
       const prefersDark = window.matchMedia(
@@ -32,6 +33,12 @@ if (savedSettings) {
     } else {
       document.documentElement.classList.add(theme);
     }
+    if (memoryMetric) {
+      invoke("set_memory_metric", { metric: memoryMetric }).catch(console.error);
+    }
+    if (updateSpeed) {
+      invoke("set_update_speed", { speed: updateSpeed }).catch(console.error);
+    }
   } catch (e) {
     console.error("Failed to initialize theme:", e);
   }
@@ -21,6 +21,7 @@ interface AppHistoryInfo {
   cpu_time_ms: number;
   network_bytes: number;
   disk_bytes: number;
+  peak_memory: number;
   icon: string | null;
 }
 
@@ -114,13 +115,16 @@ function AppHistoryPage() {
                 <th className="px-3 font-medium text-[12px] text-muted-foreground text-right w-[100px]">
                   Disk
                 </th>
+                <th className="px-3 font-medium text-[12px] text-muted-foreground text-right w-[110px]">
+                  Peak memory
+                </th>
               </tr>
             </thead>
             <tbody>
               {loading ? (
                 <tr>
                   <td
-                    colSpan={4}
+                    colSpan={5}
                     className="px-4 py-8 text-center text-muted-foreground"
                   >
                     Loading...
@@ -129,7 +133,7 @@ function AppHistoryPage() {
               ) : filteredHistory.length === 0 ? (
                 <tr>
                   <td
-                    colSpan={4}
+                    colSpan={5}
                     className="px-4 py-8 text-center text-muted-foreground"
                   >
                     {searchQuery
@@ -172,6 +176,9 @@ function AppHistoryPage() {
                     <td className="px-3 py-0 text-[13px] text-muted-foreground text-right tabular-nums">
                       {formatBytes(app.disk_bytes)}
                     </td>
+                    <td className="px-3 py-0 text-[13px] text-muted-foreground text-right tabular-nums">
+                      {formatBytes(app.peak_memory)}
+                    </td>
                   </tr>
                 ))
               )}
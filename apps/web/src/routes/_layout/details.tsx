@@ -1,16 +1,22 @@
 import { createFileRoute } from "@tanstack/react-router";
-import { useEffect, useState } from "react";
+import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { Square } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import {
   ContextMenu,
+  ContextMenuCheckboxItem,
   ContextMenuContent,
   ContextMenuItem,
+  ContextMenuSeparator,
+  ContextMenuSub,
+  ContextMenuSubContent,
+  ContextMenuSubTrigger,
   ContextMenuTrigger,
 } from "@/components/ui/context-menu";
 import { cn } from "@/lib/utils";
 import { useSearch } from "@/contexts/search-context";
+import { useProcessSnapshots } from "@/hooks/use-process-snapshots";
 
 export const Route = createFileRoute("/_layout/details")({
   component: DetailsPage,
@@ -19,23 +25,109 @@ export const Route = createFileRoute("/_layout/details")({
 interface ProcessInfo {
   pid: number;
   name: string;
+  status: string;
+  user: string | null;
+  command_line: string;
+  exe_path: string | null;
+  start_time: number;
+  session_id: number | null;
+  architecture: string | null;
+  elevated: boolean | null;
   cpu_usage: number;
   memory: number;
+  rss: number;
+  memory_breakdown: MemoryBreakdown | null;
   disk_usage: number;
-  network_usage: number;
+  net_rx_bps: number;
+  net_tx_bps: number;
   gpu_usage: number;
+  gpu_engine: string | null;
   is_app: boolean;
 }
 
+interface MemoryBreakdown {
+  private: number;
+  shared: number;
+  pss: number | null;
+  swap: number | null;
+  virtual_size: number;
+  commit: number;
+}
+
 interface ProcessesResponse {
   processes: ProcessInfo[];
   stats: any;
 }
 
+type OptionalColumn =
+  | "user"
+  | "session_id"
+  | "architecture"
+  | "elevated"
+  | "start_time"
+  | "exe_path"
+  | "command_line"
+  | "private"
+  | "shared"
+  | "pss"
+  | "swap"
+  | "commit"
+  | "virtual_size";
+
+const optionalColumns: { key: OptionalColumn; label: string }[] = [
+  { key: "user", label: "User name" },
+  { key: "session_id", label: "Session ID" },
+  { key: "architecture", label: "Architecture" },
+  { key: "elevated", label: "Elevated" },
+  { key: "start_time", label: "Start time" },
+  { key: "exe_path", label: "Image path name" },
+  { key: "command_line", label: "Command line" },
+  { key: "private", label: "Private working set" },
+  { key: "shared", label: "Shared working set" },
+  { key: "pss", label: "Proportional set" },
+  { key: "swap", label: "Swapped out" },
+  { key: "commit", label: "Commit size" },
+  { key: "virtual_size", label: "Virtual size" },
+];
+
+const formatKilobytes = (bytes: number | null | undefined) =>
+  bytes == null ? "" : (bytes / 1024).toFixed(0) + " K";
+
+const formatColumn = (proc: ProcessInfo, key: OptionalColumn) => {
+  switch (key) {
+    case "elevated":
+      return proc.elevated === null ? "" : proc.elevated ? "Yes" : "No";
+    case "start_time":
+      return new Date(proc.start_time * 1000).toLocaleString();
+    case "private":
+    case "shared":
+    case "pss":
+    case "swap":
+    case "commit":
+    case "virtual_size":
+      return formatKilobytes(proc.memory_breakdown?.[key]);
+    default:
+      return proc[key] ?? "";
+  }
+};
+
 function DetailsPage() {
   const [processes, setProcesses] = useState<ProcessInfo[]>([]);
   const { searchQuery } = useSearch();
   const [selectedPid, setSelectedPid] = useState<number | null>(null);
+  const [visibleColumns, setVisibleColumns] = useState<OptionalColumn[]>([
+    "user",
+  ]);
+
+  const toggleColumn = (key: OptionalColumn, visible: boolean) => {
+    setVisibleColumns((columns) =>
+      visible ? [...columns, key] : columns.filter((c) => c !== key),
+    );
+  };
+
+  const shownColumns = optionalColumns.filter((c) =>
+    visibleColumns.includes(c.key),
+  );
 
   const fetchData = async () => {
     try {
@@ -46,11 +138,9 @@ function DetailsPage() {
     }
   };
 
-  useEffect(() => {
-    fetchData();
-    const interval = setInterval(fetchData, 2000);
-    return () => clearInterval(interval);
-  }, []);
+  useProcessSnapshots<ProcessesResponse>((response) =>
+    setProcesses(response.processes),
+  );
 
   const formatBytes = (bytes: number) => {
     if (bytes === 0) return "0 K";
@@ -110,6 +200,20 @@ function DetailsPage() {
                 <th className="px-2 font-medium text-muted-foreground text-right">
                   Memory
                 </th>
+                <th className="px-2 font-medium text-muted-foreground text-right">
+                  GPU
+                </th>
+                <th className="px-2 font-medium text-muted-foreground">
+                  GPU engine
+                </th>
+                {shownColumns.map((column) => (
+                  <th
+                    key={column.key}
+                    className="px-2 font-medium text-muted-foreground"
+                  >
+                    {column.label}
+                  </th>
+                ))}
               </tr>
             </thead>
             <tbody>
@@ -128,13 +232,29 @@ function DetailsPage() {
                   <td className="px-2 py-0 text-muted-foreground text-right tabular-nums">
                     {proc.pid}
                   </td>
-                  <td className="px-2 py-0 text-muted-foreground">Running</td>
+                  <td className="px-2 py-0 text-muted-foreground">
+                    {proc.status}
+                  </td>
                   <td className="px-2 py-0 text-foreground text-right tabular-nums">
                     {proc.cpu_usage.toFixed(1)}%
                   </td>
                   <td className="px-2 py-0 text-foreground text-right tabular-nums">
                     {formatBytes(proc.memory)}
                   </td>
+                  <td className="px-2 py-0 text-foreground text-right tabular-nums">
+                    {proc.gpu_usage.toFixed(0)}%
+                  </td>
+                  <td className="px-2 py-0 text-muted-foreground">
+                    {proc.gpu_engine ?? ""}
+                  </td>
+                  {shownColumns.map((column) => (
+                    <td
+                      key={column.key}
+                      className="px-2 py-0 text-muted-foreground truncate max-w-[320px]"
+                    >
+                      {formatColumn(proc, column.key)}
+                    </td>
+                  ))}
                 </tr>
               ))}
             </tbody>
@@ -147,6 +267,21 @@ function DetailsPage() {
       </ContextMenuTrigger>
       <ContextMenuContent>
         <ContextMenuItem onClick={() => fetchData()}>Refresh</ContextMenuItem>
+        <ContextMenuSeparator />
+        <ContextMenuSub>
+          <ContextMenuSubTrigger>Select columns</ContextMenuSubTrigger>
+          <ContextMenuSubContent>
+            {optionalColumns.map((column) => (
+              <ContextMenuCheckboxItem
+                key={column.key}
+                checked={visibleColumns.includes(column.key)}
+                onCheckedChange={(checked) => toggleColumn(column.key, checked)}
+              >
+                {column.label}
+              </ContextMenuCheckboxItem>
+            ))}
+          </ContextMenuSubContent>
+        </ContextMenuSub>
       </ContextMenuContent>
     </ContextMenu>
   );
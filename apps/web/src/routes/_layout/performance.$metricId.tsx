@@ -1,6 +1,5 @@
 import { createFileRoute, Link } from "@tanstack/react-router";
 import { useEffect, useState, useMemo } from "react";
-import { invoke } from "@tauri-apps/api/core";
 import {
   ChevronLeft,
   Cpu,
@@ -12,6 +11,14 @@ import {
 } from "lucide-react";
 import { LineChart } from "@mui/x-charts/LineChart";
 import { SparkLineChart } from "@mui/x-charts/SparkLineChart";
+import { useProcessSnapshots } from "@/hooks/use-process-snapshots";
+import {
+  appendPoint,
+  fetchMetricHistory,
+  mergeHistory,
+  pointValues,
+  type MetricPoint,
+} from "@/lib/metric-history";
 
 export const Route = createFileRoute("/_layout/performance/$metricId")({
   component: PerformanceDetailsPage,
@@ -78,52 +85,80 @@ const MAX_DATA_POINTS = 60;
 function PerformanceDetailsPage() {
   const { metricId } = Route.useParams();
   const [stats, setStats] = useState<SystemStats | null>(null);
-  const [history, setHistory] = useState<number[]>([]);
-  const [perCoreHistory, setPerCoreHistory] = useState<number[][]>([]);
+  const [history, setHistory] = useState<MetricPoint[]>([]);
+  const [perCoreHistory, setPerCoreHistory] = useState<MetricPoint[][]>([]);
 
-  const fetchData = async () => {
-    try {
-      const response = await invoke<ProcessesResponse>("get_processes");
-      setStats(response.stats);
+  const applyResponse = (response: ProcessesResponse) => {
+    setStats(response.stats);
 
-      let currentValue = 0;
-      if (metricId === "cpu") {
-        currentValue = response.stats.total_cpu_usage;
-        setPerCoreHistory((prev) => {
-          const newHistory = [...prev];
-          response.stats.cpu_usage_per_core.forEach((usage, i) => {
-            if (!newHistory[i]) newHistory[i] = [];
-            newHistory[i] = [...newHistory[i], usage].slice(-MAX_DATA_POINTS);
-          });
-          return newHistory;
+    let currentValue = 0;
+    if (metricId === "cpu") {
+      currentValue = response.stats.total_cpu_usage;
+      setPerCoreHistory((prev) => {
+        const newHistory = [...prev];
+        response.stats.cpu_usage_per_core.forEach((usage, i) => {
+          newHistory[i] = appendPoint(
+            newHistory[i] ?? [],
+            usage,
+            MAX_DATA_POINTS,
+          );
         });
-      } else if (metricId === "memory") {
-        currentValue =
-          (response.stats.used_memory / response.stats.total_memory) * 100;
-      } else if (metricId === "network") {
-        currentValue = response.stats.network_total_usage;
-      } else if (metricId === "gpu") {
-        currentValue = response.stats.gpu_total_usage;
-      } else if (metricId.startsWith("disk-")) {
-        const mount = metricId.replace("disk-", "");
-        const disk = response.stats.disks.find(
-          (d) => d.mount_point.replace(/[^a-zA-Z0-9]/g, "") === mount,
-        );
-        currentValue = disk ? disk.usage_percent : 0;
-      }
-
-      setHistory((prev) => [...prev, currentValue].slice(-MAX_DATA_POINTS));
-    } catch (e) {
-      console.error(e);
+        return newHistory;
+      });
+    } else if (metricId === "memory") {
+      currentValue =
+        (response.stats.used_memory / response.stats.total_memory) * 100;
+    } else if (metricId === "network") {
+      currentValue = response.stats.network_total_usage;
+    } else if (metricId === "gpu") {
+      currentValue = response.stats.gpu_total_usage;
+    } else if (metricId.startsWith("disk-")) {
+      const mount = metricId.replace("disk-", "");
+      const disk = response.stats.disks.find(
+        (d) => d.mount_point.replace(/[^a-zA-Z0-9]/g, "") === mount,
+      );
+      currentValue = disk ? disk.usage_percent : 0;
     }
+
+    setHistory((prev) => appendPoint(prev, currentValue, MAX_DATA_POINTS));
   };
 
+  useProcessSnapshots(applyResponse);
+
   useEffect(() => {
-    fetchData();
-    const interval = setInterval(fetchData, 1000);
-    return () => clearInterval(interval);
+    let active = true;
+    // Points of the metric shown before do not belong in this one.
+    setHistory([]);
+    fetchMetricHistory(metricId, MAX_DATA_POINTS).then((points) => {
+      if (active)
+        setHistory((live) => mergeHistory(points, live, MAX_DATA_POINTS));
+    });
+    return () => {
+      active = false;
+    };
   }, [metricId]);
 
+  const coreCount = stats?.cpu_usage_per_core.length ?? 0;
+  useEffect(() => {
+    if (metricId !== "cpu" || coreCount === 0) return;
+    let active = true;
+    Promise.all(
+      Array.from({ length: coreCount }, (_, i) =>
+        fetchMetricHistory(`cpu-${i}`, MAX_DATA_POINTS),
+      ),
+    ).then((cores) => {
+      if (!active) return;
+      setPerCoreHistory((live) =>
+        cores.map((points, i) =>
+          mergeHistory(points, live[i] ?? [], MAX_DATA_POINTS),
+        ),
+      );
+    });
+    return () => {
+      active = false;
+    };
+  }, [metricId, coreCount]);
+
   const formatBytes = (bytes: number) => {
     const gb = bytes / (1024 * 1024 * 1024);
     return gb.toFixed(1);
@@ -255,11 +290,14 @@ function PerformanceDetailsPage() {
                       Core {i}
                     </span>
                     <span className="text-[10px] text-foreground font-medium tabular-nums">
-                      {coreData[coreData.length - 1]?.toFixed(0)}%
+                      {coreData[coreData.length - 1]?.value.toFixed(0)}%
                     </span>
                   </div>
                   <div className="flex-1 relative">
-                    <MiniGraph data={coreData} color={info.color} />
+                    <MiniGraph
+                      data={pointValues(coreData)}
+                      color={info.color}
+                    />
                   </div>
                 </div>
               ))}
@@ -275,7 +313,7 @@ function PerformanceDetailsPage() {
                 </p>
               </div>
               <div className="w-full h-full">
-                <MainGraph data={history} color={info.color} />
+                <MainGraph data={pointValues(history)} color={info.color} />
               </div>
             </div>
           )}
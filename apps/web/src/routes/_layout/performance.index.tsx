@@ -1,6 +1,5 @@
 import { createFileRoute, Link } from "@tanstack/react-router";
-import { useEffect, useState } from "react";
-import { invoke } from "@tauri-apps/api/core";
+import { useEffect, useState, type Dispatch, type SetStateAction } from "react";
 import {
   Activity,
   Cpu,
@@ -10,6 +9,14 @@ import {
   ChevronRight,
 } from "lucide-react";
 import { SparkLineChart } from "@mui/x-charts/SparkLineChart";
+import { useProcessSnapshots } from "@/hooks/use-process-snapshots";
+import {
+  appendPoint,
+  fetchMetricHistory,
+  mergeHistory,
+  pointValues,
+  type MetricPoint,
+} from "@/lib/metric-history";
 
 export const Route = createFileRoute("/_layout/performance/")({
   component: PerformancePage,
@@ -42,62 +49,100 @@ const DISK_COLORS = ["#10b981", "#14b8a6", "#06b6d4", "#0ea5e9", "#8b5cf6"];
 
 function PerformancePage() {
   const [stats, setStats] = useState<SystemStats | null>(null);
-  const [cpuHistory, setCpuHistory] = useState<number[]>([]);
-  const [memoryHistory, setMemoryHistory] = useState<number[]>([]);
-  const [diskHistories, setDiskHistories] = useState<Map<string, number[]>>(
-    new Map(),
-  );
-  const [networkHistory, setNetworkHistory] = useState<number[]>([]);
-  const [gpuHistory, setGpuHistory] = useState<number[]>([]);
+  const [cpuHistory, setCpuHistory] = useState<MetricPoint[]>([]);
+  const [memoryHistory, setMemoryHistory] = useState<MetricPoint[]>([]);
+  const [diskHistories, setDiskHistories] = useState<
+    Map<string, MetricPoint[]>
+  >(new Map());
+  const [networkHistory, setNetworkHistory] = useState<MetricPoint[]>([]);
+  const [gpuHistory, setGpuHistory] = useState<MetricPoint[]>([]);
 
-  const fetchData = async () => {
-    try {
-      const response = await invoke<ProcessesResponse>("get_processes");
-      setStats(response.stats);
+  const applyResponse = (response: ProcessesResponse) => {
+    setStats(response.stats);
 
-      setCpuHistory((prev) => {
-        const newHistory = [...prev, response.stats.total_cpu_usage];
-        return newHistory.slice(-MAX_DATA_POINTS);
-      });
+    setCpuHistory((prev) =>
+      appendPoint(prev, response.stats.total_cpu_usage, MAX_DATA_POINTS),
+    );
 
-      const memoryPercent =
-        (response.stats.used_memory / response.stats.total_memory) * 100;
-      setMemoryHistory((prev) => {
-        const newHistory = [...prev, memoryPercent];
-        return newHistory.slice(-MAX_DATA_POINTS);
-      });
+    const memoryPercent =
+      (response.stats.used_memory / response.stats.total_memory) * 100;
+    setMemoryHistory((prev) =>
+      appendPoint(prev, memoryPercent, MAX_DATA_POINTS),
+    );
 
-      setDiskHistories((prev) => {
-        const newHistories = new Map(prev);
-        for (const disk of response.stats.disks) {
-          const key = disk.mount_point;
-          const currentHistory = newHistories.get(key) || [];
-          const newHistory = [...currentHistory, disk.usage_percent];
-          newHistories.set(key, newHistory.slice(-MAX_DATA_POINTS));
-        }
-        return newHistories;
-      });
+    setDiskHistories((prev) => {
+      const newHistories = new Map(prev);
+      for (const disk of response.stats.disks) {
+        const key = disk.mount_point;
+        const currentHistory = newHistories.get(key) || [];
+        newHistories.set(
+          key,
+          appendPoint(currentHistory, disk.usage_percent, MAX_DATA_POINTS),
+        );
+      }
+      return newHistories;
+    });
 
-      setNetworkHistory((prev) => {
-        const newHistory = [...prev, response.stats.network_total_usage];
-        return newHistory.slice(-MAX_DATA_POINTS);
-      });
+    setNetworkHistory((prev) =>
+      appendPoint(prev, response.stats.network_total_usage, MAX_DATA_POINTS),
+    );
 
-      setGpuHistory((prev) => {
-        const newHistory = [...prev, response.stats.gpu_total_usage];
-        return newHistory.slice(-MAX_DATA_POINTS);
-      });
-    } catch (e) {
-      console.error("Failed to fetch stats:", e);
-    }
+    setGpuHistory((prev) =>
+      appendPoint(prev, response.stats.gpu_total_usage, MAX_DATA_POINTS),
+    );
   };
 
+  useProcessSnapshots(applyResponse);
+
   useEffect(() => {
-    fetchData();
-    const interval = setInterval(fetchData, 1000);
-    return () => clearInterval(interval);
+    let active = true;
+    const seed = (
+      metricId: string,
+      setHistory: Dispatch<SetStateAction<MetricPoint[]>>,
+    ) =>
+      fetchMetricHistory(metricId, MAX_DATA_POINTS).then((points) => {
+        if (active)
+          setHistory((live) => mergeHistory(points, live, MAX_DATA_POINTS));
+      });
+    seed("cpu", setCpuHistory);
+    seed("memory", setMemoryHistory);
+    seed("network", setNetworkHistory);
+    seed("gpu", setGpuHistory);
+    return () => {
+      active = false;
+    };
   }, []);
 
+  const diskMounts = stats?.disks.map((d) => d.mount_point).join("\n") ?? "";
+  useEffect(() => {
+    if (!diskMounts) return;
+    let active = true;
+    const mounts = diskMounts.split("\n");
+    Promise.all(
+      mounts.map((mount) =>
+        fetchMetricHistory(
+          `disk-${mount.replace(/[^a-zA-Z0-9]/g, "")}`,
+          MAX_DATA_POINTS,
+        ),
+      ),
+    ).then((histories) => {
+      if (!active) return;
+      setDiskHistories((prev) => {
+        const seeded = new Map(prev);
+        mounts.forEach((mount, i) => {
+          seeded.set(
+            mount,
+            mergeHistory(histories[i], prev.get(mount) ?? [], MAX_DATA_POINTS),
+          );
+        });
+        return seeded;
+      });
+    });
+    return () => {
+      active = false;
+    };
+  }, [diskMounts]);
+
   const formatBytes = (bytes: number) => {
     const gb = bytes / (1024 * 1024 * 1024);
     return gb.toFixed(1);
@@ -125,7 +170,7 @@ function PerformancePage() {
             value={`${stats?.total_cpu_usage.toFixed(0) ?? 0}%`}
             maxValue="100%"
             minValue="0%"
-            data={cpuHistory}
+            data={pointValues(cpuHistory)}
             color="#ef4444"
             accentColor="border-red-500/30"
           />
@@ -139,7 +184,7 @@ function PerformancePage() {
             value={`${stats ? formatBytes(stats.total_memory) : 0} GB`}
             maxValue="100%"
             minValue="0"
-            data={memoryHistory}
+            data={pointValues(memoryHistory)}
             color="#3b82f6"
             accentColor="border-blue-500/30"
           />
@@ -153,7 +198,7 @@ function PerformancePage() {
             value={`${((stats?.network_total_usage ?? 0) / 1024).toFixed(1)} KB/s`}
             maxValue="Auto"
             minValue="0"
-            data={networkHistory}
+            data={pointValues(networkHistory)}
             color="#ca8a04"
             accentColor="border-yellow-600/30"
           />
@@ -167,7 +212,7 @@ function PerformancePage() {
             value={`${stats?.gpu_total_usage.toFixed(0) ?? 0}%`}
             maxValue="100%"
             minValue="0"
-            data={gpuHistory}
+            data={pointValues(gpuHistory)}
             color="#60a5fa"
             accentColor="border-blue-400/30"
           />
@@ -189,7 +234,7 @@ function PerformancePage() {
                 value={`${disk.usage_percent.toFixed(0)}%`}
                 maxValue="100%"
                 minValue="0"
-                data={diskHistory}
+                data={pointValues(diskHistory)}
                 color={color}
                 accentColor={`border-[${color}]/30`}
               />
@@ -1,5 +1,5 @@
 import { createFileRoute } from "@tanstack/react-router";
-import { useEffect, useState } from "react";
+import { useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import {
   AlertCircle,
@@ -13,6 +13,7 @@ import { ProcessList } from "@/components/process-list";
 import { toast } from "sonner";
 import { cn } from "@/lib/utils";
 import { useSearch } from "@/contexts/search-context";
+import { useProcessSnapshots } from "@/hooks/use-process-snapshots";
 
 export const Route = createFileRoute("/_layout/processes")({
   component: ProcessesPage,
@@ -24,8 +25,12 @@ interface ProcessInfo {
   cpu_usage: number;
   memory: number;
   disk_usage: number;
-  network_usage: number;
+  net_rx_bps: number;
+  net_tx_bps: number;
   gpu_usage: number;
+  nice: number;
+  priority_class: string;
+  efficiency_mode: boolean;
   is_app: boolean;
   icon?: string;
 }
@@ -52,25 +57,28 @@ function ProcessesPage() {
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
 
+  const applyResponse = (response: ProcessesResponse) => {
+    setProcesses(response.processes);
+    setStats(response.stats);
+    setLoading(false);
+    setError(null);
+  };
+
+  const handleError = (e: any) => {
+    setError(e.toString());
+    setLoading(false);
+  };
+
   const fetchData = async () => {
     try {
-      const response = await invoke<ProcessesResponse>("get_processes");
-      setProcesses(response.processes);
-      setStats(response.stats);
-      setLoading(false);
-      setError(null);
+      applyResponse(await invoke<ProcessesResponse>("get_processes"));
     } catch (e: any) {
       console.error(e);
-      setError(e.toString());
-      setLoading(false);
+      handleError(e);
     }
   };
 
-  useEffect(() => {
-    fetchData();
-    const interval = setInterval(fetchData, 1000);
-    return () => clearInterval(interval);
-  }, []);
+  useProcessSnapshots(applyResponse, handleError);
 
   const handleKill = async (pidToKill?: number) => {
     const targetPid = pidToKill || selectedPid;
@@ -93,6 +101,25 @@ function ProcessesPage() {
     }
   };
 
+  const handleSetEfficiencyMode = async (pid: number, enabled: boolean) => {
+    try {
+      const success = await invoke<boolean>("set_efficiency_mode", {
+        pid,
+        enabled,
+        includeTree: true,
+      });
+      if (success) {
+        fetchData();
+      } else {
+        toast.error(
+          `Failed to ${enabled ? "enable" : "disable"} efficiency mode for ${pid}`,
+        );
+      }
+    } catch (e) {
+      toast.error("Error invoking set_efficiency_mode command");
+    }
+  };
+
   const filteredProcesses = processes.filter(
     (p) =>
       p.name.toLowerCase().includes(searchQuery.toLowerCase()) ||
@@ -179,6 +206,7 @@ function ProcessesPage() {
           selectedPid={selectedPid}
           onSelect={setSelectedPid}
           onKill={handleKill}
+          onSetEfficiencyMode={handleSetEfficiencyMode}
           systemStats={stats}
         />
       </div>
@@ -25,6 +25,7 @@ interface AppSettings {
   alwaysOnTop: boolean;
   cpuAlert: boolean;
   startWithPC: boolean;
+  memoryMetric: string;
 }
 
 const defaultSettings: AppSettings = {
@@ -34,6 +35,7 @@ const defaultSettings: AppSettings = {
   alwaysOnTop: false,
   cpuAlert: true,
   startWithPC: false,
+  memoryMetric: "rss",
 };
 
 function SettingsPage() {
@@ -56,6 +58,7 @@ function SettingsPage() {
 
   const handleUpdateSpeedChange = (value: string) => {
     saveSettings({ ...settings, updateSpeed: value });
+    invoke("set_update_speed", { speed: value }).catch(console.error);
     window.dispatchEvent(
       new CustomEvent("updateSpeedChange", { detail: value }),
     );
@@ -77,6 +80,15 @@ function SettingsPage() {
     }
   };
 
+  const handleMemoryMetricChange = async (value: string) => {
+    try {
+      await invoke("set_memory_metric", { metric: value });
+      saveSettings({ ...settings, memoryMetric: value });
+    } catch (err) {
+      console.error("Failed to set memory metric:", err);
+    }
+  };
+
   const handleCpuAlertChange = (checked: boolean) => {
     saveSettings({ ...settings, cpuAlert: checked });
   };
@@ -207,6 +219,46 @@ function SettingsPage() {
                 </Select>
               </SettingCard>
 
+              <SettingCard
+                title="Memory metric"
+                description="What the Memory column and app history count; resident memory counts shared pages once per process"
+              >
+                <Select
+                  value={settings.memoryMetric}
+                  onValueChange={handleMemoryMetricChange}
+                >
+                  <SelectTrigger className="w-[180px] h-[32px] rounded-sm bg-accent border-border text-foreground text-[13px]">
+                    <SelectValue />
+                  </SelectTrigger>
+                  <SelectContent className="bg-accent border-border text-foreground">
+                    <SelectItem
+                      value="rss"
+                      className="text-[13px] focus:bg-accent/50"
+                    >
+                      Resident (RSS)
+                    </SelectItem>
+                    <SelectItem
+                      value="private"
+                      className="text-[13px] focus:bg-accent/50"
+                    >
+                      Private working set
+                    </SelectItem>
+                    <SelectItem
+                      value="pss"
+                      className="text-[13px] focus:bg-accent/50"
+                    >
+                      Proportional (PSS)
+                    </SelectItem>
+                    <SelectItem
+                      value="commit"
+                      className="text-[13px] focus:bg-accent/50"
+                    >
+                      Commit size
+                    </SelectItem>
+                  </SelectContent>
+                </Select>
+              </SettingCard>
+
               <SettingCard
                 title="Start with PC"
                 description="Launch Task Manager automatically when you start your computer"
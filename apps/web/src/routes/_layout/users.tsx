@@ -28,7 +28,8 @@ interface ProcessInfo {
   cpu_usage: number;
   memory: number;
   disk_usage: number;
-  network_usage: number;
+  net_rx_bps: number;
+  net_tx_bps: number;
   gpu_usage: number;
   is_app: boolean;
   icon: string | null;